@@ -0,0 +1,51 @@
+{{! layout: base }}
+    <div class="row">
+      <div class="leftcolumn">
+        <div class="card">
+          <h2>Archive - {{title}}</h2>
+          <p>{{post_count}} post(s)</p>
+        </div>
+
+        {{#post_list}}
+        <div class="card">
+          <h2><a href='{{link}}'>{{title}}</a></h2>
+          <h5>(Posted {{date}} {{time}})</h5>
+          <p>{{{summary}}}</p>
+          <p>... more ...</p>
+        </div>
+        {{/post_list}}
+
+        {{#show_pagination}}
+        <div class="card">
+        Pages:
+        {{#page_list}}
+          {{#current}}
+            {{number}}
+          {{/current}}
+          {{^current}}
+            <a href="?page={{number}}">{{number}}</a>
+          {{/current}}
+        {{/page_list}}
+        </div>
+        {{/show_pagination}}
+    </div>
+
+    <div class="rightcolumn">
+      <div class="card archive-list">
+        <h3>Archive</h3>
+        <ul>
+          <li><a href="/archive/">all</a></li>
+          {{#periods}}
+          <li>
+            {{#current}}<strong>{{year}}</strong>{{/current}}{{^current}}<a href="{{link}}">{{year}}</a>{{/current}} ({{count}})
+            <ul>
+              {{#months}}
+              <li>{{#current}}<strong>{{name}}</strong>{{/current}}{{^current}}<a href="{{link}}">{{name}}</a>{{/current}} ({{count}})</li>
+              {{/months}}
+            </ul>
+          </li>
+          {{/periods}}
+        </ul>
+      </div>
+    </div>
+</div>
@@ -0,0 +1,3 @@
+<div class="footer">
+        <p>&copy; {{site.year}} {{site.title}} - Powered by Texted {{site.version}}</p>
+    </div>
@@ -0,0 +1,5 @@
+<meta name="viewport" content="maximum-scale=1.0,width=device-width,initial-scale=1.0">
+    <title>{{site.title}}</title>
+    <meta name="description" content="{{site.description}}">
+    <link href="/public/prism.css" rel="stylesheet" />
+    <link rel="stylesheet" type="text/css" href="/public/simple_flex.css">
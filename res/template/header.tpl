@@ -0,0 +1,4 @@
+<div class="header">
+        <a href="/">{{site.title}}</a> - {{site.description}}
+        <p>{{#site.nav}}<a href="{{url}}">{{name}}</a>&nbsp;&nbsp;{{/site.nav}}</p>
+    </div>
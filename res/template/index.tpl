@@ -1,18 +1,4 @@
-<html>
-
-<head>
-    <meta name="viewport" content="maximum-scale=1.0,width=device-width,initial-scale=1.0">
-    <title>Texted</title>
-    <link href="/public/prism.css" rel="stylesheet" />
-</head>
-
-<body>
-    <link rel="stylesheet" type="text/css" href="/public/simple_flex.css">
-
-    <div class="header">
-        Texted - Free your text!</span>
-    </div>
-
+{{! layout: base }}
     <div class="row">
         <div class="card">
             <h2>Who's talking?</h2>
@@ -42,8 +28,3 @@
         </div>
 
     </div>
-
-
-</body>
-
-</html>
\ No newline at end of file
@@ -0,0 +1,19 @@
+<!DOCTYPE html>
+<html>
+
+<head>
+    {{> head}}
+</head>
+
+<body>
+    {{> header}}
+
+{{> content}}
+
+    {{> footer}}
+
+    <!-- JS highlighter -->
+    <script src="/public/prism.js"></script>
+</body>
+
+</html>
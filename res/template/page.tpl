@@ -1,20 +1,6 @@
-<html>
-
-<head>
-    <meta name="viewport" content="maximum-scale=1.0,width=device-width,initial-scale=1.0">
-    <title>Texted</title>
-    <link href="/public/prism.css" rel="stylesheet" />
-</head>
-
-<body>
-    <link rel="stylesheet" type="text/css" href="/public/simple_flex.css">
-
-    <div class="header">
-        <a href="/">Texted</a> - Free your text!</span>
-    </div>
-
-
+{{! layout: base }}
     <div class="row">
+      <div class="leftcolumn">
         <div class="card">
 
             <h2>{{{post_title}}}</h2>
@@ -25,7 +11,9 @@
             </p>
         </div>
 
-    </div>
-</body>
+      </div>
 
-</html>
\ No newline at end of file
+      <div class="rightcolumn">
+        {{> sidebar}}
+      </div>
+    </div>
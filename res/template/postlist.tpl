@@ -1,17 +1,4 @@
-<!DOCTYPE html>
-<html>
-<head>
-    <meta name="viewport" content="maximum-scale=1.0,width=device-width,initial-scale=1.0">
-    <title>Texted</title>
-    <link href="/public/prism.css" rel="stylesheet" />
-</head>
-<body>
-    <link rel="stylesheet" type="text/css" href="/public/simple_flex.css">
-
-    <div class="header">
-        <a href="/">Texted</a> - Free your text!</span>
-    </div>
-
+{{! layout: base }}
     <div id="mini-bio" class="card">
       <h2>About this blog</h2>
       <p>
@@ -21,10 +8,19 @@
 
     <div class="row">
       <div class="leftcolumn">
+        {{#author}}
+        <div class="card author">
+          {{#avatar}}<img class="avatar" src="{{avatar}}" width="64">{{/avatar}}
+          <h2>Posts by {{name}}</h2>
+          <p>{{bio}}</p>
+          {{#links}}<a href="{{url}}">{{name}}</a>&nbsp;&nbsp;{{/links}}
+        </div>
+        {{/author}}
+
         {{#post_list}}
         <div class="card">
           <h2><a href='{{link}}'>{{title}}</a></h2>
-          <h5>(Posted {{date}} {{time}})</h5>
+          <h5>(Posted {{date}} {{time}} by {{#authors}}<a href="{{link}}">{{name}}</a> {{/authors}}) - {{reading_time}} min read{{#view_count}}, {{view_count}} views{{/view_count}}</h5>
           <p>{{{summary}}}</p>
           <p>... more ...</p>
         </div>
@@ -52,15 +48,6 @@
           This blog contains a list of posts and documents that I wrote myself
         </p>
       </div>
-      <div class="card tag-list">
-        <h3>List of tags</h3>
-        <ul>
-          <li><a href="/list">all</a></li>
-          {{#tags}}<li><a href="/list/{{tag}}/">#{{tag}}</a></li>{{/tags}}
-        </ul>
-      </div>
+        {{> sidebar}}
           </div>
 </div>
-
-</body>
-</html>
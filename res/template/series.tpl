@@ -0,0 +1,18 @@
+{{! layout: base }}
+    <div class="row">
+        <div class="card">
+          <h2>Series: {{name}}</h2>
+          <p>{{count}} part(s)</p>
+        </div>
+
+        {{#parts}}
+        <div class="card">
+          {{#post}}
+          <h2>Part {{number}}: <a href='{{link}}'>{{title}}</a></h2>
+          <h5>(Posted {{date}} {{time}})</h5>
+          <p>{{{summary}}}</p>
+          <p>... more ...</p>
+          {{/post}}
+        </div>
+        {{/parts}}
+    </div>
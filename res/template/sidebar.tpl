@@ -0,0 +1,23 @@
+<div class="card recent-posts">
+          <h3>Recent posts</h3>
+          <ul>
+            {{#site.recent_posts}}<li><a href="{{link}}">{{title}}</a> <small>{{date}}</small></li>{{/site.recent_posts}}
+          </ul>
+        </div>
+        <div class="card popular-posts">
+          <h3>Popular this week</h3>
+          <ul>
+            {{#site.popular_posts_week}}<li><a href="{{link}}">{{title}}</a> <small>{{view_count}} views</small></li>{{/site.popular_posts_week}}
+            {{^site.popular_posts_week}}<li>No views yet</li>{{/site.popular_posts_week}}
+          </ul>
+        </div>
+        <div class="card tag-list">
+          <h3>Tags</h3>
+          <p>
+            <a href="/list">all ({{site.post_count}})</a>
+            {{#site.tags}}<a href="{{link}}">#{{name}} ({{count}})</a> {{/site.tags}}
+          </p>
+        </div>
+        <div class="card">
+          <p><a href="/archive/">Archive by date</a></p>
+        </div>
@@ -1,24 +1,21 @@
-<!DOCTYPE html>
-<html>
-
-<head>
-    <meta name="viewport" content="maximum-scale=1.0,width=device-width,initial-scale=1.0">
-    <title>Texted</title>
-    <link href="/public/prism.css" rel="stylesheet" />
-</head>
-
-<body>
-    <link rel="stylesheet" type="text/css" href="/public/simple_flex.css">
-
-    <div class="header">
-        <a href="/">Texted</a> - Free your text!</span>
-    </div>
-
+{{! layout: base }}
     <div class="row">
+      <div class="leftcolumn">
         <div class="card">
 
             <h2>{{{post_title}}}</h2>
-            <h5>Created by {{author}} on {{date}} {{time}}</h5>
+            <h5>Created by {{#authors}}<a href="{{link}}">{{name}}</a> {{/authors}}on {{date}} {{time}}</h5>
+            <h6>{{#is_updated}}Updated on {{updated_date}} {{updated_time}} - {{/is_updated}}{{word_count}} words, {{reading_time}} min read{{#view_count}}, {{view_count}} views{{/view_count}}</h6>
+            {{#series}}
+            <div class="series">
+                <p>Part {{part}} of {{count}} of the series <a href="{{link}}">{{name}}</a></p>
+                <ol>
+                    {{#parts}}
+                    <li>{{#current}}<strong>{{title}}</strong>{{/current}}{{^current}}<a href="{{link}}">{{title}}</a>{{/current}}</li>
+                    {{/parts}}
+                </ol>
+            </div>
+            {{/series}}
             <p>
                 <strong>Tags:</strong>
                 {{#tags}}
@@ -29,11 +26,44 @@
                 {{{post_content}}}
             </p>
 
+            {{#series}}
+            <p>
+                {{#previous}}<a href="{{link}}">&larr; Previous: {{title}}</a>&nbsp;&nbsp;{{/previous}}
+                {{#next}}<a href="{{link}}">Next: {{title}} &rarr;</a>{{/next}}
+            </p>
+            {{/series}}
+
         </div>
 
-    </div>
-    <!-- JS highlighter -->
-    <script src="/public/prism.js"></script>
-</body>
+        {{#authors}}
+        <div class="card author">
+            {{#avatar}}<img class="avatar" src="{{avatar}}" width="64">{{/avatar}}
+            <h3><a href="{{link}}">{{name}}</a></h3>
+            <p>{{bio}}</p>
+            {{#links}}<a href="{{url}}">{{name}}</a>&nbsp;&nbsp;{{/links}}
+        </div>
+        {{/authors}}
 
-</html>
\ No newline at end of file
+        <div class="card post-navigation">
+            {{#previous}}<p>Previous: <a href="{{link}}">{{title}}</a></p>{{/previous}}
+            {{#next}}<p>Next: <a href="{{link}}">{{title}}</a></p>{{/next}}
+            {{#has_related}}
+            <h3>Related posts</h3>
+            <ul>
+                {{#related}}<li><a href="{{link}}">{{title}}</a></li>{{/related}}
+            </ul>
+            {{/has_related}}
+            {{#has_backlinks}}
+            <h3>Posts that link here</h3>
+            <ul>
+                {{#backlinks}}<li><a href="{{link}}">{{title}}</a></li>{{/backlinks}}
+            </ul>
+            {{/has_backlinks}}
+        </div>
+
+      </div>
+
+      <div class="rightcolumn">
+        {{> sidebar}}
+      </div>
+    </div>
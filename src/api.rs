@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::content::Content;
+use crate::paginator::Paginator;
+
+/// Kind of content returned by the JSON API
+#[derive(Clone, Copy)]
+pub enum ApiKind {
+    Post,
+    Page,
+}
+
+impl ApiKind {
+    fn html_link(&self, slug: &str) -> String {
+        match self {
+            ApiKind::Post => format!("/view/{}/", slug),
+            ApiKind::Page => format!("/page/{}/", slug),
+        }
+    }
+
+    fn api_link(&self, slug: &str) -> String {
+        match self {
+            ApiKind::Post => format!("/api/posts/{}", slug),
+            ApiKind::Page => format!("/api/pages/{}", slug),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ApiLinks {
+    pub html: String,
+    pub json: String,
+}
+
+/// A post or page, with the header fields, summary and full html
+#[derive(Serialize)]
+pub struct ApiContent<'a> {
+    #[serde(flatten)]
+    pub content: &'a Content,
+    pub summary: &'a str,
+    /// Full html, only returned when a single post or page is requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<&'a str>,
+    pub links: ApiLinks,
+}
+
+impl<'a> ApiContent<'a> {
+    /// summary is a content rendered as preview
+    pub fn new(kind: ApiKind, summary: &'a Content, full: Option<&'a Content>) -> Self {
+        ApiContent {
+            content: summary,
+            summary: &summary.rendered,
+            html: full.map(|c| c.rendered.as_str()),
+            links: ApiLinks {
+                html: kind.html_link(&summary.link),
+                json: kind.api_link(&summary.link),
+            },
+        }
+    }
+}
+
+/// A page of posts, sorted by date reversed
+#[derive(Serialize)]
+pub struct ApiPostList<'a> {
+    pub page: u32,
+    pub page_size: u32,
+    pub page_count: u32,
+    pub total_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<&'a str>,
+    pub posts: Vec<ApiContent<'a>>,
+}
+
+impl<'a> ApiPostList<'a> {
+    /// Pages after the last one have no posts
+    pub fn new(paginator: &Paginator<'a, Arc<Content>>, page: u32, page_size: u32, tag: Option<&'a str>) -> Self {
+        let posts = paginator.get_page(page).unwrap_or_default();
+        ApiPostList {
+            page,
+            page_size,
+            page_count: paginator.page_count(),
+            total_count: paginator.total_count(),
+            tag,
+            posts: posts.iter().map(|c| ApiContent::new(ApiKind::Post, c, None)).collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ApiTag {
+    pub name: String,
+    pub count: i32,
+    pub links: ApiLinks,
+}
+
+/// Tags sorted by the number of posts reversed, then by name
+pub fn api_tags(tag_map: &HashMap<String, i32>) -> Vec<ApiTag> {
+    let mut tags: Vec<ApiTag> = tag_map.iter()
+        .map(|(name, count)| ApiTag {
+            name: name.clone(),
+            count: *count,
+            links: ApiLinks {
+                html: format!("/list/{}/", name),
+                json: format!("/api/posts?{}", serde_urlencoded::to_string([("tag", name)]).unwrap()),
+            },
+        })
+        .collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::test_data::{create_cont, ContOptions};
+
+    #[test]
+    fn test_content_json() {
+        let summary = create_cont("1", "2024-01-02", ContOptions { tags: &["rust"], rendered: Some("<p>summary</p>"), word_count: 10, ..Default::default() });
+        let full = create_cont("1", "2024-01-02", ContOptions { tags: &["rust"], rendered: Some("<p>summary</p><p>full</p>"), word_count: 10, ..Default::default() });
+        let json = serde_json::to_value(ApiContent::new(ApiKind::Post, &summary, Some(&full))).unwrap();
+
+        assert_eq!(json["id"], "1");
+        assert_eq!(json["slug"], "post-1");
+        assert_eq!(json["title"], "title-of-post-1");
+        assert_eq!(json["date"], "2024-01-02T05:06:07");
+        assert_eq!(json["tags"], serde_json::json!(["rust"]));
+        assert_eq!(json["summary"], "<p>summary</p>");
+        assert_eq!(json["html"], "<p>summary</p><p>full</p>");
+        assert_eq!(json["reading_time"], 1);
+        assert_eq!(json["links"]["html"], "/view/post-1/");
+        assert_eq!(json["links"]["json"], "/api/posts/post-1");
+        assert_eq!(json["file_name"], Value::Null);
+        assert_eq!(json["rendered"], Value::Null);
+
+        let json = serde_json::to_value(ApiContent::new(ApiKind::Page, &summary, None)).unwrap();
+        assert_eq!(json["links"]["html"], "/page/post-1/");
+        assert!(json.get("html").is_none());
+    }
+
+    #[test]
+    fn test_post_list_json() {
+        let contents: Vec<Arc<Content>> = (1..=5)
+            .map(|i| create_cont(&i.to_string(), &format!("2024-01-0{}", i), ContOptions::default()))
+            .collect();
+        let paginator = Paginator::from(&contents, 2);
+
+        let json = serde_json::to_value(ApiPostList::new(&paginator, 3, 2, Some("rust"))).unwrap();
+        assert_eq!(json["page"], 3);
+        assert_eq!(json["page_count"], 3);
+        assert_eq!(json["total_count"], 5);
+        assert_eq!(json["tag"], "rust");
+        assert_eq!(json["posts"].as_array().unwrap().len(), 1);
+        assert_eq!(json["posts"][0]["slug"], "post-5");
+
+        let json = serde_json::to_value(ApiPostList::new(&paginator, 4, 2, None)).unwrap();
+        assert_eq!(json["posts"].as_array().unwrap().len(), 0);
+        assert!(json.get("tag").is_none());
+    }
+
+    #[test]
+    fn test_tags() {
+        let tag_map: HashMap<String, i32> = [("web", 1), ("rust", 3), ("c++", 1)].iter()
+            .map(|(k, v)| (k.to_string(), *v))
+            .collect();
+        let tags = api_tags(&tag_map);
+        let names: Vec<&str> = tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["rust", "c++", "web"]);
+        assert_eq!(tags[1].links.html, "/list/c++/");
+        assert_eq!(tags[1].links.json, "/api/posts?tag=c%2B%2B");
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::{fs, io};
+
+use serde::Deserialize;
+
+use crate::text_utils::slugify;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AuthorLink {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Author {
+    /// Key of the author in the authors file. Used in the url /author/{id}/
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub bio: String,
+    pub avatar: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub links: Vec<AuthorLink>,
+}
+
+/// Authors described in the authors file. E.g.
+/// ```toml
+/// [thiago]
+/// name = "Thiago Guedes"
+/// bio = "Software developer"
+/// avatar = "/public/thiago.jpg"
+/// links = [{ name = "GitHub", url = "https://github.com/thiagomg" }]
+/// ```
+#[derive(Default)]
+pub struct AuthorRegistry {
+    authors: HashMap<String, Author>,
+}
+
+impl AuthorRegistry {
+    pub fn empty() -> Self {
+        AuthorRegistry::default()
+    }
+
+    pub fn from_file(file_name: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(file_name)
+            .map_err(|e| io::Error::new(e.kind(), format!("Error opening authors file {}: {}", file_name.to_str().unwrap(), e)))?;
+        Self::from_toml(&content)
+    }
+
+    pub fn from_toml(content: &str) -> io::Result<Self> {
+        let mut authors: HashMap<String, Author> = match toml::from_str(content) {
+            Ok(authors) => authors,
+            Err(e) => return Err(io::Error::new(ErrorKind::InvalidData, format!("Error parsing authors file: {}", e))),
+        };
+
+        for (id, author) in authors.iter_mut() {
+            author.id = id.clone();
+        }
+
+        Ok(AuthorRegistry { authors })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Author> {
+        self.authors.get(id)
+    }
+
+    /// Find the author by id or by display name.
+    /// Authors not described in the authors file only have a name
+    pub fn resolve(&self, name: &str) -> Author {
+        if let Some(author) = self.authors.get(name) {
+            return author.clone();
+        }
+
+        if let Some(author) = self.authors.values().find(|a| a.name == name) {
+            return author.clone();
+        }
+
+        Author {
+            id: slugify(name),
+            name: name.to_string(),
+            bio: "".to_string(),
+            avatar: None,
+            email: None,
+            links: vec![],
+        }
+    }
+
+    pub fn resolve_all(&self, names: &[String]) -> Vec<Author> {
+        names.iter().map(|name| self.resolve(name)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTHORS: &str = r##"
+[thiago]
+name = "Thiago Guedes"
+bio = "Writes code"
+avatar = "/public/thiago.jpg"
+links = [{ name = "GitHub", url = "https://github.com/thiagomg" }]
+
+[joe]
+name = "Joe Sephine"
+"##;
+
+    #[test]
+    fn test_parse_authors() {
+        let registry = AuthorRegistry::from_toml(AUTHORS).unwrap();
+        let thiago = registry.get("thiago").unwrap();
+        assert_eq!(thiago.id, "thiago");
+        assert_eq!(thiago.name, "Thiago Guedes");
+        assert_eq!(thiago.bio, "Writes code");
+        assert_eq!(thiago.avatar, Some("/public/thiago.jpg".to_string()));
+        assert_eq!(thiago.links, vec![AuthorLink { name: "GitHub".to_string(), url: "https://github.com/thiagomg".to_string() }]);
+
+        let joe = registry.get("joe").unwrap();
+        assert_eq!(joe.bio, "");
+        assert!(joe.links.is_empty());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let registry = AuthorRegistry::from_toml(AUTHORS).unwrap();
+        assert_eq!(registry.resolve("thiago").name, "Thiago Guedes");
+        assert_eq!(registry.resolve("Joe Sephine").id, "joe");
+
+        let unknown = registry.resolve("Ana Lúcia");
+        assert_eq!(unknown.id, "ana_lucia");
+        assert_eq!(unknown.name, "Ana Lúcia");
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        let res = AuthorRegistry::from_toml("[thiago]\nbio = \"no name\"");
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use spdlog::LevelFilter;
+
+use texted::config::{read_config, Config};
+use texted::content::content_file::ContentFile;
+use texted::content::content_format::ContentFormat;
+use texted::content::content_renderer::RenderOptions;
+use texted::content::format_registry::FormatRegistry;
+use texted::content::notebook_renderer::notebook_assets;
+use texted::content::parsing_utils::{parse_texted_header, remove_comments};
+use texted::content::wiki_links::WikiLinks;
+use texted::content::Content;
+use texted::post_list::{list_post_files, PostLink, PostListType};
+use texted::theme::Theme;
+
+use crate::{CheckArgs, CheckOutput};
+
+lazy_static! {
+    static ref IMAGE_REGEX: Regex = Regex::new(r#"<img[^>]*\ssrc="([^"]*)""#).unwrap();
+    static ref LINK_REGEX: Regex = Regex::new(r##"<a[^>]*\shref="/(view|page)/([^/"#?]+)"##).unwrap();
+    static ref BROKEN_WIKI_LINK_REGEX: Regex = Regex::new(r#"class="wiki-link broken" title="Broken link: ([^"]*)""#).unwrap();
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    /// Supported, but probably not intended. E.g. a post without header
+    Warning,
+}
+
+#[derive(Serialize, Debug)]
+struct Problem {
+    file: PathBuf,
+    severity: Severity,
+    /// header, comment, render, title, duplicate_id, duplicate_slug, image or link
+    kind: &'static str,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct Report {
+    checked_files: usize,
+    problems: Vec<Problem>,
+}
+
+/// Posts or pages, with the root directory of their files
+struct Section<'a> {
+    root_dir: &'a Path,
+    links: Vec<PostLink>,
+}
+
+pub fn check_cmd(args: CheckArgs) {
+    // Problems are in the report, not in the log of the renderers
+    spdlog::default_logger().set_level_filter(LevelFilter::Off);
+
+    let report = match read_config(&args.config).and_then(|config| check_content(&config)) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            exit(2);
+        }
+    };
+
+    match args.output {
+        CheckOutput::Human => print_report(&report),
+        CheckOutput::Json => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+    }
+    if report.problems.iter().any(|p| p.severity == Severity::Error) {
+        exit(1);
+    }
+}
+
+fn print_report(report: &Report) {
+    for problem in &report.problems {
+        let severity = match problem.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        println!("{}: {}: {} [{}]", problem.file.to_str().unwrap(), severity, problem.message, problem.kind);
+    }
+    let count = |severity| report.problems.iter().filter(|p| p.severity == severity).count();
+    println!("{} files checked: {} errors, {} warnings", report.checked_files, count(Severity::Error), count(Severity::Warning));
+}
+
+/// Renders all the posts and pages, like the server does, reporting what would fail or look broken
+fn check_content(config: &Config) -> io::Result<Report> {
+    let list_type = match config.defaults.index_base_name {
+        None => PostListType::AnyContentFile,
+        Some(ref base_name) => PostListType::IndexBaseName(base_name.clone()),
+    };
+    let mut formats = FormatRegistry::with_markdown(&config.markdown);
+    let posts = Section {
+        root_dir: &config.paths.posts_dir,
+        links: list_post_files(&config.paths.posts_dir, &list_type, &formats).map_err(io::Error::other)?,
+    };
+    let pages = Section {
+        root_dir: &config.paths.pages_dir,
+        links: list_post_files(&config.paths.pages_dir, &list_type, &formats).map_err(io::Error::other)?,
+    };
+
+    let post_links = link_map(&posts.links);
+    let page_links = link_map(&pages.links);
+    let wiki_links = WikiLinks::new(&post_links, &page_links, &formats);
+    formats.set_wiki_links(Arc::new(wiki_links));
+    let public_dirs = public_dirs(config);
+
+    let mut problems = vec![];
+    // Posts and pages have their own urls, so ids and url names only need to be unique in each of them
+    for section in [&posts, &pages] {
+        let mut ids: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        duplicate_slugs(&section.links, &mut problems);
+        for link in &section.links {
+            let Some((content_file, content)) = check_file(&formats, link, &mut problems) else {
+                continue;
+            };
+            check_images(&content, &content_file.raw_content, section.root_dir, &public_dirs, link, &mut problems);
+            check_links(&content, &post_links, &page_links, link, &mut problems);
+            ids.entry(content.header.id.0.clone()).or_default().push(link.post_path.clone());
+        }
+        duplicate_ids(ids, &mut problems);
+    }
+
+    problems.sort_by(|a, b| a.file.cmp(&b.file).then_with(|| a.kind.cmp(b.kind)));
+    Ok(Report {
+        checked_files: posts.links.len() + pages.links.len(),
+        problems,
+    })
+}
+
+fn link_map(links: &[PostLink]) -> HashMap<String, PathBuf> {
+    links.iter().map(|link| (link.post_name.clone(), link.post_path.clone())).collect()
+}
+
+/// Public directory of the configuration and of the theme, if any
+fn public_dirs(config: &Config) -> Vec<PathBuf> {
+    let mut dirs = vec![config.paths.public_dir.clone()];
+    if let (Some(theme), Some(themes_dir)) = (&config.theme, &config.paths.themes_dir) {
+        if let Ok(theme) = Theme::load(themes_dir, &theme.name) {
+            dirs.push(theme.public_dir());
+        }
+    }
+    dirs
+}
+
+fn problem(link: &PostLink, severity: Severity, kind: &'static str, message: String) -> Problem {
+    Problem { file: link.post_path.clone(), severity, kind, message }
+}
+
+/// Parses and renders the file, returning the file and its content if it can be rendered
+fn check_file(formats: &FormatRegistry, link: &PostLink, problems: &mut Vec<Problem>) -> Option<(ContentFile, Content)> {
+    let content_file = match ContentFile::from_file(link.post_name.clone(), link.post_path.clone()) {
+        Ok(content_file) => content_file,
+        Err(e) => {
+            problems.push(problem(link, Severity::Error, "render", format!("Unable to read the file: {}", e)));
+            return None;
+        }
+    };
+
+    // The markdown renderer generates a header when it cannot parse it, so it is checked before rendering
+    if content_file.format == ContentFormat::Texted {
+        match parse_texted_header(&content_file.file_path, content_file.raw_content.lines()) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                problems.push(problem(link, Severity::Warning, "header", "Missing header. The ID and DATE are generated from the file".to_string()));
+            }
+            Err(e) => problems.push(problem(link, Severity::Error, "header", e.to_string())),
+        }
+        if remove_comments(&content_file.raw_content).is_err() {
+            problems.push(problem(link, Severity::Error, "comment", "Comment <!-- without the closing -->".to_string()));
+            return None;
+        }
+    }
+
+    let content = match formats.render(&content_file, RenderOptions::FullContent) {
+        Ok(content) => content,
+        Err(e) => {
+            problems.push(problem(link, Severity::Error, "render", e.to_string()));
+            return None;
+        }
+    };
+    if content.title.trim().is_empty() {
+        problems.push(problem(link, Severity::Error, "title", "Missing title".to_string()));
+    }
+    Some((content_file, content))
+}
+
+/// Local images must exist in the directory of the post (e.g. posts_dir/{post}/image.png), in the notebook of the
+/// post or, for absolute paths, in the public directory
+fn check_images(content: &Content, raw_content: &str, root_dir: &Path, public_dirs: &[PathBuf], link: &PostLink, problems: &mut Vec<Problem>) {
+    let assets = notebook_assets(raw_content);
+    for caps in IMAGE_REGEX.captures_iter(&content.rendered) {
+        let src = &caps[1];
+        if src.contains("://") || src.starts_with("//") || src.starts_with("data:") {
+            continue;
+        }
+        let path = src.split(['?', '#']).next().unwrap_or(src);
+        let exists = match path.strip_prefix('/') {
+            Some(public_path) => public_dirs.iter().any(|dir| dir.join(public_path).is_file()),
+            None => root_dir.join(&link.post_name).join(path).is_file() || assets.contains_key(path),
+        };
+        if !exists {
+            problems.push(problem(link, Severity::Error, "image", format!("Image not found: {}", src)));
+        }
+    }
+}
+
+/// Links to /view/{post} and /page/{page}, including wiki links, must point to existing posts and pages
+fn check_links(content: &Content, post_links: &HashMap<String, PathBuf>, page_links: &HashMap<String, PathBuf>,
+               link: &PostLink, problems: &mut Vec<Problem>) {
+    for caps in LINK_REGEX.captures_iter(&content.rendered) {
+        let (kind, slug) = (&caps[1], &caps[2]);
+        let links = if kind == "view" { post_links } else { page_links };
+        if !links.contains_key(slug) {
+            problems.push(problem(link, Severity::Error, "link", format!("Broken link: /{}/{}", kind, slug)));
+        }
+    }
+    for caps in BROKEN_WIKI_LINK_REGEX.captures_iter(&content.rendered) {
+        problems.push(problem(link, Severity::Error, "link", format!("Broken wiki link: [[{}]]", &caps[1])));
+    }
+}
+
+/// Two files with the same url name, e.g. post.md and post/index.md. Only one of them is served
+fn duplicate_slugs(links: &[PostLink], problems: &mut Vec<Problem>) {
+    let mut by_slug: HashMap<&str, Vec<&PostLink>> = HashMap::new();
+    for link in links {
+        by_slug.entry(&link.post_name).or_default().push(link);
+    }
+    for (slug, mut links) in by_slug.into_iter().filter(|(_, links)| links.len() > 1) {
+        links.sort_by(|a, b| a.post_path.cmp(&b.post_path));
+        for link in &links {
+            let others: Vec<&str> = links.iter().filter(|other| other.post_path != link.post_path)
+                .map(|other| other.post_path.to_str().unwrap())
+                .collect();
+            problems.push(problem(link, Severity::Error, "duplicate_slug", format!("Url name {} also used by {}", slug, others.join(", "))));
+        }
+    }
+}
+
+fn duplicate_ids(ids: HashMap<String, Vec<PathBuf>>, problems: &mut Vec<Problem>) {
+    for (id, files) in ids.into_iter().filter(|(_, files)| files.len() > 1) {
+        for file in &files {
+            let others: Vec<&str> = files.iter().filter(|other| *other != file).map(|other| other.to_str().unwrap()).collect();
+            problems.push(Problem {
+                file: file.clone(),
+                severity: Severity::Error,
+                kind: "duplicate_id",
+                message: format!("ID {} also used by {}", id, others.join(", ")),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_data::temp_dir;
+
+    const HEADER: &str = "[ID]: # (1)\n[DATE]: # (2024-05-01 10:00:00.000)\n[AUTHOR]: # (thiago)\n\n";
+
+    fn create_dir() -> PathBuf {
+        let dir = temp_dir("check");
+        fs::create_dir_all(dir.join("posts/dir_post")).unwrap();
+        fs::create_dir_all(dir.join("pages")).unwrap();
+        fs::create_dir_all(dir.join("public")).unwrap();
+        dir
+    }
+
+    fn config(dir: &Path) -> Config {
+        let cfg = format!(r#"
+[personal]
+activity_start_year = 2000
+blog_start_date = 2024-04-22
+
+[paths]
+template_dir = "{0}/template"
+public_dir = "{0}/public"
+posts_dir = "{0}/posts"
+pages_dir = "{0}/pages"
+
+[defaults]
+page_size = 10
+rendering_cache_enabled = false
+
+[server]
+address = "127.0.0.1"
+port = 8001
+"#, dir.to_str().unwrap());
+        fs::write(dir.join("texted.toml"), cfg).unwrap();
+        read_config(&dir.join("texted.toml")).unwrap()
+    }
+
+    fn kinds(report: &Report, file: &str) -> Vec<&'static str> {
+        report.problems.iter().filter(|p| p.file.ends_with(file)).map(|p| p.kind).collect()
+    }
+
+    #[test]
+    fn test_check_content() {
+        let dir = create_dir();
+        let posts = dir.join("posts");
+        fs::write(posts.join("dir_post/index.md"), format!("{}# Dir post\n\n![ok](ok.png) ![logo](/logo.png) [About](/page/about/)", HEADER)).unwrap();
+        fs::write(posts.join("dir_post/ok.png"), "").unwrap();
+        fs::write(dir.join("public/logo.png"), "").unwrap();
+        fs::write(dir.join("pages/about.md"), "[ID]: # (about)\n[DATE]: # (2024-05-01 10:00:00.000)\n[AUTHOR]: # (thiago)\n\n# About\n").unwrap();
+        fs::write(posts.join("broken.md"), "[ID]: # (2)\n[DATE]: # (2024-05-01 10:00:00.000)\n[AUTHOR]: # (thiago)\n\n# Broken\n\n![missing](missing.png) [[nowhere]] [x](/view/nowhere/)").unwrap();
+        fs::write(posts.join("bad_date.md"), "[ID]: # (3)\n[DATE]: # (2024-13-01 10:00:00.000)\n[AUTHOR]: # (thiago)\n\n# Bad date\n").unwrap();
+        fs::write(posts.join("same_id.md"), format!("{}# Same id\n", HEADER)).unwrap();
+        fs::write(posts.join("no_title.md"), "[ID]: # (4)\n[DATE]: # (2024-05-01 10:00:00.000)\n[AUTHOR]: # (thiago)\n\nNo title").unwrap();
+        fs::write(posts.join("no_header.md"), "# No header\n\nText").unwrap();
+        fs::write(posts.join("unclosed.md"), "[ID]: # (5)\n[DATE]: # (2024-05-01 10:00:00.000)\n[AUTHOR]: # (thiago)\n\n# Unclosed\n\n<!-- more").unwrap();
+        fs::write(posts.join("dir_post.html"), "<!--\n[ID]: # (6)\n[DATE]: # (2024-05-01 10:00:00.000)\n[AUTHOR]: # (thiago)\n-->\n<h1>Same slug</h1>").unwrap();
+
+        let report = check_content(&config(&dir)).unwrap();
+        assert_eq!(report.checked_files, 9);
+        assert_eq!(kinds(&report, "dir_post/index.md"), vec!["duplicate_id", "duplicate_slug"]);
+        assert_eq!(kinds(&report, "same_id.md"), vec!["duplicate_id"]);
+        assert_eq!(kinds(&report, "dir_post.html"), vec!["duplicate_slug"]);
+        assert_eq!(kinds(&report, "broken.md"), vec!["image", "link", "link"]);
+        assert_eq!(kinds(&report, "bad_date.md"), vec!["header"]);
+        assert!(report.problems.iter().any(|p| p.message.starts_with("Invalid date 2024-13-01")));
+        assert_eq!(kinds(&report, "no_title.md"), vec!["title"]);
+        assert_eq!(kinds(&report, "no_header.md"), vec!["header"]);
+        assert_eq!(report.problems.iter().find(|p| p.file.ends_with("no_header.md")).unwrap().severity, Severity::Warning);
+        assert_eq!(kinds(&report, "unclosed.md"), vec!["comment"]);
+        assert!(kinds(&report, "about.md").is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
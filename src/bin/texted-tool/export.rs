@@ -0,0 +1,282 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use texted::config::{read_config, Config};
+use texted::content::content_file::ContentFile;
+use texted::content::content_format::ContentFormat;
+use texted::content::content_renderer::RenderOptions;
+use texted::content::format_registry::FormatRegistry;
+use texted::content::parsing_utils::{parse_texted_header, parse_title_html, parse_title_markdown, remaining_text};
+use texted::content::Content;
+use texted::post_list::{list_post_files, PostLink, PostListType};
+
+use crate::import::PostKind;
+use crate::{ExportArgs, ExportFormat};
+
+#[derive(Debug, Default)]
+struct ExportReport {
+    exported: Vec<PathBuf>,
+    /// Posts not exported, e.g. because they could not be rendered
+    warnings: Vec<String>,
+}
+
+pub fn export_cmd(args: ExportArgs) {
+    let res = read_config(&args.config).and_then(|config| export_content(&config, args.format, &args.output));
+    match res {
+        Ok(report) => {
+            for warning in &report.warnings {
+                println!("Warning: {}", warning);
+            }
+            println!("{} posts and pages exported to {}", report.exported.len(), args.output.to_str().unwrap());
+        }
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+fn export_content(config: &Config, format: ExportFormat, output: &Path) -> io::Result<ExportReport> {
+    let list_type = match config.defaults.index_base_name {
+        None => PostListType::AnyContentFile,
+        Some(ref base_name) => PostListType::IndexBaseName(base_name.clone()),
+    };
+    let formats = FormatRegistry::with_markdown(&config.markdown);
+    let summary_tag = config.defaults.summary_line_tag.clone().unwrap_or("<!-- more -->".to_string());
+
+    let mut report = ExportReport::default();
+    for (kind, root_dir) in [(PostKind::Post, &config.paths.posts_dir), (PostKind::Page, &config.paths.pages_dir)] {
+        for link in list_post_files(root_dir, &list_type, &formats).map_err(io::Error::other)? {
+            match export_post(&formats, &link, kind, root_dir, format, &summary_tag, output) {
+                Ok(file) => report.exported.push(file),
+                Err(e) => report.warnings.push(format!("Skipped {}: {}", link.post_path.to_str().unwrap(), e)),
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn export_post(formats: &FormatRegistry, link: &PostLink, kind: PostKind, root_dir: &Path, format: ExportFormat,
+               summary_tag: &str, output: &Path) -> io::Result<PathBuf> {
+    let content_file = ContentFile::from_file(link.post_name.clone(), link.post_path.clone())?;
+    let content = formats.render(&content_file, RenderOptions::FullContent)?;
+
+    // Markdown and html keep their source, the other formats are exported rendered
+    let (body, extension) = match content_file.format {
+        ContentFormat::Texted => (source_body(&content_file), "md"),
+        ContentFormat::Html => (source_body(&content_file), "html"),
+        _ => (content.rendered.clone(), "html"),
+    };
+    let divider = match format {
+        ExportFormat::Hugo | ExportFormat::Jekyll => "<!--more-->",
+        ExportFormat::Plain => "<!-- more -->",
+    };
+    let body = body.replace(summary_tag, divider);
+
+    let (post_file, assets_dir) = target_paths(format, kind, &content, extension, output);
+    fs::create_dir_all(post_file.parent().unwrap())?;
+    fs::write(&post_file, format!("{}\n{}\n", front_matter(format, kind, &content, body.contains(divider)), body.trim()))?;
+
+    // Directory posts have their assets in the directory, e.g. posts_dir/{post}/image.png
+    let post_dir = root_dir.join(&link.post_name);
+    if link.post_path.parent() == Some(post_dir.as_path()) {
+        copy_assets(&post_dir, &link.post_path, &assets_dir)?;
+    }
+    Ok(post_file)
+}
+
+/// Source of the body, after the header and the title
+fn source_body(content_file: &ContentFile) -> String {
+    let lines = content_file.raw_content.lines();
+    let (lines, maybe_line) = match parse_texted_header(&content_file.file_path, lines.clone()) {
+        Ok((_, lines, maybe_line)) => (lines, maybe_line),
+        // Posts without header
+        Err(_) => {
+            let mut lines = lines;
+            let maybe_line = lines.next();
+            (lines, maybe_line)
+        }
+    };
+    let (title, mut title_lines, _) = match content_file.format {
+        ContentFormat::Html => parse_title_html(lines.clone(), maybe_line),
+        _ => parse_title_markdown(lines.clone(), maybe_line),
+    };
+    match title.is_empty() {
+        true => remaining_text(maybe_line, lines),
+        false => remaining_text(title_lines.next(), title_lines),
+    }
+}
+
+/// File of the post and directory of its assets. Hugo bundles and plain posts have the assets alongside; Jekyll
+/// copies them as static files at the url of the post, so relative references still work
+fn target_paths(format: ExportFormat, kind: PostKind, content: &Content, extension: &str, output: &Path) -> (PathBuf, PathBuf) {
+    let slug = &content.link;
+    let post_file = match (format, kind) {
+        (ExportFormat::Hugo, PostKind::Post) => output.join("content/posts").join(slug).join(format!("index.{}", extension)),
+        (ExportFormat::Hugo, PostKind::Page) => output.join("content").join(slug).join(format!("index.{}", extension)),
+        (ExportFormat::Jekyll, PostKind::Post) => {
+            output.join("_posts").join(format!("{}-{}.{}", content.header.date.format("%Y-%m-%d"), slug, extension))
+        }
+        (ExportFormat::Jekyll, PostKind::Page) => output.join(format!("{}.{}", slug, extension)),
+        (ExportFormat::Plain, PostKind::Post) => output.join("posts").join(slug).join(format!("index.{}", extension)),
+        (ExportFormat::Plain, PostKind::Page) => output.join("pages").join(slug).join(format!("index.{}", extension)),
+    };
+    let assets_dir = match format {
+        ExportFormat::Jekyll => output.join(texted_url(kind, slug).trim_matches('/')),
+        _ => post_file.parent().unwrap().to_path_buf(),
+    };
+    (post_file, assets_dir)
+}
+
+fn texted_url(kind: PostKind, slug: &str) -> String {
+    match kind {
+        PostKind::Post => format!("/view/{}/", slug),
+        PostKind::Page => format!("/page/{}/", slug),
+    }
+}
+
+/// YAML front matter with the header of the post. Hugo and Jekyll keep the texted url of the post
+fn front_matter(format: ExportFormat, kind: PostKind, content: &Content, has_divider: bool) -> String {
+    let header = &content.header;
+    let mut buf = String::new();
+
+    let _ = writeln!(&mut buf, "---");
+    let _ = writeln!(&mut buf, "title: {}", yaml_string(&content.title));
+    let _ = writeln!(&mut buf, "date: {}", header.date.format("%Y-%m-%dT%H:%M:%S"));
+    if header.updated > header.date {
+        let key = match format {
+            ExportFormat::Hugo => "lastmod",
+            ExportFormat::Jekyll => "last_modified_at",
+            ExportFormat::Plain => "updated",
+        };
+        let _ = writeln!(&mut buf, "{}: {}", key, header.updated.format("%Y-%m-%dT%H:%M:%S"));
+    }
+    match header.authors.as_slice() {
+        [] => {}
+        [author] => { let _ = writeln!(&mut buf, "author: {}", yaml_string(author)); }
+        authors => { let _ = writeln!(&mut buf, "authors: {}", yaml_list(authors)); }
+    }
+    if !header.tags.is_empty() {
+        let _ = writeln!(&mut buf, "tags: {}", yaml_list(&header.tags));
+    }
+    if let Some(ref series) = header.series {
+        // Series is a taxonomy in Hugo, so it is a list
+        match format {
+            ExportFormat::Hugo => { let _ = writeln!(&mut buf, "series: {}", yaml_list(std::slice::from_ref(series))); }
+            _ => { let _ = writeln!(&mut buf, "series: {}", yaml_string(series)); }
+        }
+    }
+    if let Some(part) = header.series_part {
+        let _ = writeln!(&mut buf, "series_part: {}", part);
+    }
+    let _ = writeln!(&mut buf, "id: {}", yaml_string(&header.id.0));
+    match format {
+        ExportFormat::Hugo => { let _ = writeln!(&mut buf, "url: {}", texted_url(kind, &content.link)); }
+        ExportFormat::Jekyll => {
+            let layout = if kind == PostKind::Post { "post" } else { "page" };
+            let _ = writeln!(&mut buf, "layout: {}", layout);
+            let _ = writeln!(&mut buf, "permalink: {}", texted_url(kind, &content.link));
+            if has_divider {
+                let _ = writeln!(&mut buf, "excerpt_separator: \"<!--more-->\"");
+            }
+        }
+        ExportFormat::Plain => { let _ = writeln!(&mut buf, "slug: {}", content.link); }
+    }
+    let _ = writeln!(&mut buf, "---");
+    buf
+}
+
+fn yaml_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn yaml_list(items: &[String]) -> String {
+    format!("[{}]", items.iter().map(|item| yaml_string(item)).collect::<Vec<_>>().join(", "))
+}
+
+/// Copies the files of the post directory, except the post itself
+fn copy_assets(dir: &Path, post_file: &Path, assets_dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            copy_assets(&path, post_file, &assets_dir.join(entry.file_name()))?;
+        } else if path != post_file {
+            fs::create_dir_all(assets_dir)?;
+            fs::copy(&path, assets_dir.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::temp_dir;
+
+    fn create_dir() -> PathBuf {
+        let dir = temp_dir("export");
+        fs::create_dir_all(dir.join("posts/dir_post")).unwrap();
+        fs::create_dir_all(dir.join("pages")).unwrap();
+        dir
+    }
+
+    fn config(dir: &Path) -> Config {
+        let cfg = format!(r#"
+[personal]
+activity_start_year = 2000
+blog_start_date = 2024-04-22
+
+[paths]
+template_dir = "{0}/template"
+public_dir = "{0}/public"
+posts_dir = "{0}/posts"
+pages_dir = "{0}/pages"
+
+[defaults]
+page_size = 10
+rendering_cache_enabled = false
+summary_line_tag = "<!-- cut -->"
+
+[server]
+address = "127.0.0.1"
+port = 8001
+"#, dir.to_str().unwrap());
+        fs::write(dir.join("texted.toml"), cfg).unwrap();
+        read_config(&dir.join("texted.toml")).unwrap()
+    }
+
+    #[test]
+    fn test_export() {
+        let dir = create_dir();
+        fs::write(dir.join("posts/dir_post/index.md"), "<!--\n[ID]: # (1)\n[DATE]: # (2024-05-01 10:00:00.000)\n\
+[UPDATED]: # (2024-05-01 10:00:00.000)\n[AUTHOR]: # (Thiago, Ana)\n[TAGS]: # (rust web)\n[SERIES]: # (Intro)\n-->\n\n\
+# Dir \"post\"\n\nSummary\n<!-- cut -->\n![x](x.png)\n").unwrap();
+        fs::write(dir.join("posts/dir_post/x.png"), "png").unwrap();
+        fs::write(dir.join("pages/about.html"), "<!--\n[ID]: # (about)\n[DATE]: # (2024-05-02 10:00:00.000)\n\
+[UPDATED]: # (2024-05-02 10:00:00.000)\n[AUTHOR]: # (Thiago)\n-->\n<h1>About</h1>\n<p>Me</p>\n").unwrap();
+        let config = config(&dir);
+
+        let out = dir.join("hugo");
+        let report = export_content(&config, ExportFormat::Hugo, &out).unwrap();
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+        assert_eq!(report.exported, vec![out.join("content/posts/dir_post/index.md"), out.join("content/about/index.html")]);
+        assert_eq!(fs::read_to_string(out.join("content/posts/dir_post/index.md")).unwrap(), "---\ntitle: \"Dir \\\"post\\\"\"\n\
+date: 2024-05-01T10:00:00\nauthors: [\"Thiago\", \"Ana\"]\ntags: [\"rust\", \"web\"]\nseries: [\"Intro\"]\nid: \"1\"\n\
+url: /view/dir_post/\n---\n\nSummary\n<!--more-->\n![x](x.png)\n");
+        assert!(out.join("content/posts/dir_post/x.png").is_file());
+        assert_eq!(fs::read_to_string(out.join("content/about/index.html")).unwrap(), "---\ntitle: \"About\"\n\
+date: 2024-05-02T10:00:00\nauthor: \"Thiago\"\nid: \"about\"\nurl: /page/about/\n---\n\n<p>Me</p>\n");
+
+        let out = dir.join("jekyll");
+        let report = export_content(&config, ExportFormat::Jekyll, &out).unwrap();
+        assert_eq!(report.exported, vec![out.join("_posts/2024-05-01-dir_post.md"), out.join("about.html")]);
+        let post = fs::read_to_string(out.join("_posts/2024-05-01-dir_post.md")).unwrap();
+        assert!(post.contains("series: \"Intro\"\nid: \"1\"\nlayout: post\npermalink: /view/dir_post/\nexcerpt_separator: \"<!--more-->\"\n---\n"), "{}", post);
+        assert!(out.join("view/dir_post/x.png").is_file());
+
+        let out = dir.join("plain");
+        export_content(&config, ExportFormat::Plain, &out).unwrap();
+        let post = fs::read_to_string(out.join("posts/dir_post/index.md")).unwrap();
+        assert!(post.ends_with("slug: dir_post\n---\n\nSummary\n<!-- more -->\n![x](x.png)\n"), "{}", post);
+        assert!(out.join("posts/dir_post/x.png").is_file());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+use std::io;
+use std::io::ErrorKind;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use lazy_static::lazy_static;
+use regex::Regex;
+use toml::{Table, Value};
+
+lazy_static! {
+    static ref DATE_REGEX: Regex = Regex::new(r"^(\d{4})-(\d{1,2})-(\d{1,2})(?:[T ](\d{1,2}):(\d{2})(?::(\d{2}))?)?").unwrap();
+}
+
+/// Front matter of Hugo and Jekyll posts: YAML between `---` or TOML between `+++`. Returns the values and the body
+pub fn split_front_matter(raw_content: &str) -> io::Result<(Table, &str)> {
+    let raw_content = raw_content.trim_start_matches('\u{feff}');
+    let Some(delimiter) = ["---", "+++"].into_iter().find(|delimiter| raw_content.lines().next().is_some_and(|line| line.trim_end() == *delimiter)) else {
+        return Ok((Table::new(), raw_content));
+    };
+
+    let after_start = &raw_content[raw_content.find('\n').map_or(raw_content.len(), |pos| pos + 1)..];
+    let mut end = 0;
+    let mut body_start = None;
+    for line in after_start.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            body_start = Some(end + line.len());
+            break;
+        }
+        end += line.len();
+    }
+    let Some(body_start) = body_start else {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("Front matter without the closing {}", delimiter)));
+    };
+
+    let front_matter = &after_start[..end];
+    let values = match delimiter {
+        "+++" => front_matter.parse::<Table>().map_err(|e| io::Error::new(ErrorKind::InvalidData, format!("Invalid TOML front matter: {}", e)))?,
+        _ => parse_yaml(front_matter),
+    };
+    Ok((values, &after_start[body_start..]))
+}
+
+/// Minimal YAML: `key: value`, `key: [a, b]` and `- item` lists. Nested maps are ignored
+fn parse_yaml(yaml: &str) -> Table {
+    let mut values = Table::new();
+    let mut list_key: Option<String> = None;
+    for line in yaml.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let indented = line.starts_with([' ', '\t']);
+        if let Some(item) = line.trim_start().strip_prefix("- ").filter(|_| list_key.is_some()) {
+            if let Some(Value::Array(list)) = values.get_mut(list_key.as_ref().unwrap()) {
+                list.push(Value::String(unquote(item)));
+            }
+            continue;
+        }
+        if indented {
+            continue;
+        }
+        list_key = None;
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_string();
+        let value = strip_comment(value).trim();
+        let value = if value.is_empty() {
+            // Values in the next lines, e.g. a list
+            list_key = Some(key.clone());
+            Value::Array(vec![])
+        } else if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Value::Array(items.split(',').map(unquote).filter(|item| !item.is_empty()).map(Value::String).collect())
+        } else if value == "true" || value == "false" {
+            Value::Boolean(value == "true")
+        } else {
+            Value::String(unquote(value))
+        };
+        values.insert(key, value);
+    }
+    values
+}
+
+fn strip_comment(value: &str) -> &str {
+    match value.find(" #") {
+        Some(pos) if !value.trim_start().starts_with(['"', '\'']) => &value[..pos],
+        _ => value,
+    }
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(unquoted) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return unquoted.to_string();
+        }
+    }
+    value.to_string()
+}
+
+/// Text of a string, number or date value
+pub fn text(values: &Table, key: &str) -> Option<String> {
+    match values.get(key)? {
+        Value::String(text) => Some(text.clone()),
+        Value::Datetime(date) => Some(date.to_string()),
+        Value::Integer(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// Items of a list, or of a text separated by spaces or commas (Jekyll allows `tags: rust web`)
+pub fn list(values: &Table, key: &str) -> Vec<String> {
+    match values.get(key) {
+        Some(Value::Array(items)) => items.iter().filter_map(|item| item.as_str()).map(|item| item.to_string()).collect(),
+        Some(Value::String(items)) => items.split([',', ' ']).map(|item| item.trim()).filter(|item| !item.is_empty()).map(|item| item.to_string()).collect(),
+        _ => vec![],
+    }
+}
+
+pub fn flag(values: &Table, key: &str) -> Option<bool> {
+    match values.get(key)? {
+        Value::Boolean(flag) => Some(*flag),
+        Value::String(flag) => Some(flag == "true"),
+        _ => None,
+    }
+}
+
+/// Date and time as written, ignoring the time zone. E.g. 2024-05-01, 2024-05-01T10:00:00+02:00 or 2024-05-01 10:00:00 +0200
+pub fn parse_date(date: &str) -> Option<NaiveDateTime> {
+    let caps = DATE_REGEX.captures(date.trim())?;
+    let number = |index: usize| caps.get(index).map_or(Some(0), |n| n.as_str().parse::<u32>().ok());
+    let date = NaiveDate::from_ymd_opt(caps[1].parse().ok()?, number(2)?, number(3)?)?;
+    let time = NaiveTime::from_hms_opt(number(4)?, number(5)?, number(6)?)?;
+    Some(NaiveDateTime::new(date, time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml() {
+        let raw = "---\ntitle: \"Hello: world\"\ndate: 2024-05-01 10:00:00 +0200\ntags: [rust, 'web']\ncategories:\n  - blog\n  - news\ndraft: false # not yet\nparams:\n  nested: ignored\n---\n\nBody\n";
+        let (values, body) = split_front_matter(raw).unwrap();
+        assert_eq!(body, "\nBody\n");
+        assert_eq!(text(&values, "title").unwrap(), "Hello: world");
+        assert_eq!(list(&values, "tags"), vec!["rust", "web"]);
+        assert_eq!(list(&values, "categories"), vec!["blog", "news"]);
+        assert_eq!(flag(&values, "draft"), Some(false));
+        assert!(values.get("nested").is_none());
+        assert_eq!(parse_date(&text(&values, "date").unwrap()).unwrap().to_string(), "2024-05-01 10:00:00");
+    }
+
+    #[test]
+    fn test_toml() {
+        let raw = "+++\ntitle = \"Hello\"\ndate = 2024-05-01T10:30:00Z\naliases = [\"/old/\"]\ndraft = true\n+++\nBody";
+        let (values, body) = split_front_matter(raw).unwrap();
+        assert_eq!(body, "Body");
+        assert_eq!(parse_date(&text(&values, "date").unwrap()).unwrap().to_string(), "2024-05-01 10:30:00");
+        assert_eq!(list(&values, "aliases"), vec!["/old/"]);
+        assert_eq!(flag(&values, "draft"), Some(true));
+
+        let (values, body) = split_front_matter("No front matter").unwrap();
+        assert!(values.is_empty());
+        assert_eq!(body, "No front matter");
+        assert!(split_front_matter("---\ntitle: x\n").is_err());
+        assert_eq!(list(&Table::from_iter([("tags".to_string(), Value::String("rust web".to_string()))]), "tags"), vec!["rust", "web"]);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2024-05-01").unwrap().to_string(), "2024-05-01 00:00:00");
+        assert_eq!(parse_date("2024-05-01T10:30").unwrap().to_string(), "2024-05-01 10:30:00");
+        assert!(parse_date("2024-13-01").is_none());
+        assert!(parse_date("yesterday").is_none());
+    }
+}
@@ -0,0 +1,281 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref TOKEN_REGEX: Regex = Regex::new(r#"(?s)<!--(.*?)-->|<(/?)([a-zA-Z][a-zA-Z0-9]*)((?:[^>"']|"[^"]*"|'[^']*')*)>"#).unwrap();
+    static ref SPACES_REGEX: Regex = Regex::new(r"[ \t\r]+").unwrap();
+    static ref NEWLINE_REGEX: Regex = Regex::new(r" ?\n ?").unwrap();
+    static ref LANGUAGE_REGEX: Regex = Regex::new(r"language-([\w+#-]+)").unwrap();
+}
+
+/// Elements without closing tag
+const VOID_ELEMENTS: [&str; 8] = ["br", "hr", "img", "input", "meta", "source", "wbr", "link"];
+/// Elements without markdown syntax, kept as html blocks
+const HTML_BLOCKS: [&str; 14] = ["table", "iframe", "video", "audio", "object", "embed", "script", "style", "form", "dl", "details", "svg", "canvas", "noscript"];
+/// Inline elements without markdown syntax, kept as html
+const HTML_INLINES: [&str; 7] = ["sup", "sub", "u", "mark", "abbr", "kbd", "small"];
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    /// Html kept as it is
+    Raw(String),
+    Element { name: String, attrs: String, children: Vec<Node> },
+}
+
+/// Converts html, e.g. the content of WordPress posts, into markdown. Elements without markdown syntax,
+/// like tables, are kept as html. `<!--more-->` becomes the texted summary tag `<!-- more -->`
+pub fn html_to_markdown(html: &str) -> String {
+    let markdown = tidy(&render_nodes(&parse(html), false));
+    format!("{}\n", markdown.trim())
+}
+
+/// Html tree of the fragment. Closing tags without an open element are ignored
+fn parse(html: &str) -> Vec<Node> {
+    // Name, attributes, start in the html and children of the open elements
+    let mut stack: Vec<(String, String, usize, Vec<Node>)> = vec![("".to_string(), "".to_string(), 0, vec![])];
+    let mut last = 0;
+    for caps in TOKEN_REGEX.captures_iter(html) {
+        let token = caps.get(0).unwrap();
+        if token.start() > last {
+            stack.last_mut().unwrap().3.push(Node::Text(html[last..token.start()].to_string()));
+        }
+        last = token.end();
+
+        if let Some(comment) = caps.get(1) {
+            // WordPress block comments (<!-- wp:paragraph -->) and other comments are dropped
+            if comment.as_str().trim() == "more" {
+                stack.last_mut().unwrap().3.push(Node::Raw("\n\n<!-- more -->\n\n".to_string()));
+            }
+            continue;
+        }
+
+        let name = caps[3].to_lowercase();
+        let attrs = caps[4].trim_end_matches('/').to_string();
+        if &caps[2] == "/" {
+            let Some(pos) = stack.iter().rposition(|(open, ..)| *open == name).filter(|pos| *pos > 0) else {
+                continue;
+            };
+            while stack.len() > pos {
+                close_element(&mut stack, html, token.end());
+            }
+        } else if VOID_ELEMENTS.contains(&name.as_str()) || token.as_str().ends_with("/>") {
+            stack.last_mut().unwrap().3.push(Node::Element { name, attrs, children: vec![] });
+        } else {
+            // A new paragraph or list item closes the previous one
+            if (name == "p" || name == "li") && stack.last().unwrap().0 == name {
+                close_element(&mut stack, html, token.start());
+            }
+            stack.push((name, attrs, token.start(), vec![]));
+        }
+    }
+    if last < html.len() {
+        stack.last_mut().unwrap().3.push(Node::Text(html[last..].to_string()));
+    }
+    while stack.len() > 1 {
+        close_element(&mut stack, html, html.len());
+    }
+    stack.pop().unwrap().3
+}
+
+fn close_element(stack: &mut Vec<(String, String, usize, Vec<Node>)>, html: &str, end: usize) {
+    let (name, attrs, start, children) = stack.pop().unwrap();
+    let node = if HTML_BLOCKS.contains(&name.as_str()) {
+        Node::Raw(format!("\n\n{}\n\n", html[start..end].trim()))
+    } else if HTML_INLINES.contains(&name.as_str()) {
+        Node::Raw(html[start..end].to_string())
+    } else {
+        Node::Element { name, attrs, children }
+    };
+    stack.last_mut().unwrap().3.push(node);
+}
+
+fn render_nodes(nodes: &[Node], in_link: bool) -> String {
+    let mut res = String::new();
+    for node in nodes {
+        let rendered = render_node(node, in_link);
+        if rendered.starts_with('\n') {
+            res.truncate(res.trim_end_matches([' ', '\t']).len());
+        }
+        // Spaces between block elements would become indentation
+        if res.is_empty() || res.ends_with('\n') {
+            res.push_str(rendered.trim_start_matches([' ', '\t']));
+        } else {
+            res.push_str(&rendered);
+        }
+    }
+    res
+}
+
+fn render_node(node: &Node, in_link: bool) -> String {
+    let (name, attrs, children) = match node {
+        Node::Text(text) => return NEWLINE_REGEX.replace_all(&SPACES_REGEX.replace_all(text, " "), "\n").to_string(),
+        Node::Raw(html) => return html.clone(),
+        Node::Element { name, attrs, children } => (name.as_str(), attrs.as_str(), children),
+    };
+    let inline = || render_nodes(children, in_link).trim().to_string();
+    match name {
+        "p" => format!("\n\n{}\n\n", inline()),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level: usize = name[1..].parse().unwrap();
+            format!("\n\n{} {}\n\n", "#".repeat(level), inline().replace('\n', " "))
+        }
+        "br" => "  \n".to_string(),
+        "hr" => "\n\n---\n\n".to_string(),
+        "strong" | "b" => wrap("**", &inline()),
+        "em" | "i" => wrap("*", &inline()),
+        "del" | "s" | "strike" => wrap("~~", &inline()),
+        "code" => wrap("`", &decode_entities(&text_of(children))),
+        "a" => match attr(attrs, "href") {
+            Some(href) if !in_link => format!("[{}]({})", render_nodes(children, true).trim(), href),
+            _ => inline(),
+        },
+        "img" => format!("![{}]({})", attr(attrs, "alt").unwrap_or_default(), attr(attrs, "src").unwrap_or_default()),
+        "figcaption" => format!("\n\n{}\n\n", wrap("*", &inline())),
+        "ul" | "ol" => format!("\n\n{}\n\n", render_list(name == "ol", children)),
+        "blockquote" => {
+            let quote: Vec<String> = tidy(&render_nodes(children, in_link)).trim().lines()
+                .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                .collect();
+            format!("\n\n{}\n\n", quote.join("\n"))
+        }
+        "pre" => {
+            let language = LANGUAGE_REGEX.captures(attrs)
+                .or_else(|| children.iter().find_map(|child| match child {
+                    Node::Element { name, attrs, .. } if name == "code" => LANGUAGE_REGEX.captures(attrs),
+                    _ => None,
+                }))
+                .map_or("", |caps| caps.get(1).unwrap().as_str());
+            format!("\n\n```{}\n{}\n```\n\n", language, decode_entities(&text_of(children)).trim_matches('\n'))
+        }
+        _ => render_nodes(children, in_link),
+    }
+}
+
+fn render_list(ordered: bool, items: &[Node]) -> String {
+    let mut lines = vec![];
+    let items = items.iter().filter_map(|item| match item {
+        Node::Element { name, children, .. } if name == "li" => Some(children),
+        _ => None,
+    });
+    for (index, children) in items.enumerate() {
+        let marker = if ordered { format!("{}. ", index + 1) } else { "- ".to_string() };
+        // Items are tight: paragraphs inside them are separated by a single line break
+        let text = tidy(&render_nodes(children, false)).trim().replace("\n\n", "\n");
+        for (line_index, line) in text.lines().enumerate() {
+            match line_index {
+                0 => lines.push(format!("{}{}", marker, line)),
+                _ => lines.push(format!("{}{}", " ".repeat(marker.len()), line)),
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+fn wrap(mark: &str, text: &str) -> String {
+    match text.is_empty() {
+        true => "".to_string(),
+        false => format!("{}{}{}", mark, text, mark),
+    }
+}
+
+/// Text of the nodes, without the tags
+fn text_of(nodes: &[Node]) -> String {
+    nodes.iter().map(|node| match node {
+        Node::Text(text) | Node::Raw(text) => text.clone(),
+        Node::Element { name, children, .. } if name == "br" && children.is_empty() => "\n".to_string(),
+        Node::Element { children, .. } => text_of(children),
+    }).collect()
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#039;", "'")
+        .replace("&amp;", "&")
+}
+
+fn attr(attrs: &str, name: &str) -> Option<String> {
+    let attr_regex = Regex::new(&format!(r#"(?i)(?:^|\s){}\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#, regex::escape(name))).unwrap();
+    let caps = attr_regex.captures(attrs)?;
+    caps.get(1).or(caps.get(2)).or(caps.get(3)).map(|value| value.as_str().to_string())
+}
+
+/// Removes the spaces of empty lines and repeated empty lines, except in code blocks
+fn tidy(markdown: &str) -> String {
+    let mut lines: Vec<&str> = vec![];
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let empty = line.trim().is_empty();
+        if !in_code && empty && lines.last().is_some_and(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(if empty && !in_code { "" } else { line });
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let html = r#"<!-- wp:heading --><h2>Intro</h2><!-- /wp:heading -->
+<p>Some <strong>bold</strong> and <a href="https://texted.dev">a <em>link</em></a>.<br/>Next line</p>
+<!--more-->
+<ul><li>One</li><li>Two<ol><li>Nested</li></ol></li></ul>
+<blockquote><p>Quote</p><p>More</p></blockquote>
+<pre class="wp-block-code"><code class="language-rust">fn main() {
+    println!("&lt;hi&gt;");
+}</code></pre>
+<figure><img src="https://example.com/a.png" alt="A"/><figcaption>Caption</figcaption></figure>
+<table><tr><td>cell</td></tr></table>"#;
+        assert_eq!(html_to_markdown(html), r#"## Intro
+
+Some **bold** and [a *link*](https://texted.dev).  
+Next line
+
+<!-- more -->
+
+- One
+- Two
+  1. Nested
+
+> Quote
+>
+> More
+
+```rust
+fn main() {
+    println!("<hi>");
+}
+```
+
+![A](https://example.com/a.png)
+
+*Caption*
+
+<table><tr><td>cell</td></tr></table>
+"#);
+    }
+
+    #[test]
+    fn test_classic_editor() {
+        // The classic editor separates paragraphs with empty lines, without <p>
+        let html = "First paragraph with <em>style</em>\n\nSecond one <sup>1</sup>\n\n<p>Unclosed";
+        assert_eq!(html_to_markdown(html), "First paragraph with *style*\n\nSecond one <sup>1</sup>\n\nUnclosed\n");
+    }
+
+    #[test]
+    fn test_attr() {
+        assert_eq!(attr(r#" class="a" href='/x' data-src=y"#, "href"), Some("/x".to_string()));
+        assert_eq!(attr(r#" data-src=y src="z""#, "src"), Some("z".to_string()));
+        assert_eq!(attr(r#" class="a""#, "href"), None);
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt::Write;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+use texted::config::{read_config, Config};
+
+use crate::post::url_name;
+use crate::static_site::{import_static_site, Generator};
+use crate::wordpress::import_wordpress;
+use crate::{ImportArgs, ImportSource};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BodyFormat {
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostKind {
+    Post,
+    Page,
+}
+
+/// Post or page read from another blog engine, written as a texted directory post
+#[derive(Debug)]
+pub struct ImportedPost {
+    pub kind: PostKind,
+    /// Name of the directory, also the url name of the post
+    pub slug: String,
+    pub title: String,
+    pub date: NaiveDateTime,
+    pub authors: Vec<String>,
+    pub tags: Vec<String>,
+    /// Body without the title
+    pub body: String,
+    pub format: BodyFormat,
+    /// Files copied to the post directory: source and file name in the post
+    pub assets: Vec<(PathBuf, String)>,
+    /// Urls of the post in the old blog, e.g. /2019/05/hello-world/
+    pub old_urls: Vec<String>,
+}
+
+/// Where and how the imported posts are written
+pub struct ImportTarget {
+    pub posts_dir: PathBuf,
+    pub pages_dir: PathBuf,
+    /// Base name of the post file, index by default
+    pub index_base_name: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: Vec<PathBuf>,
+    /// Items not imported or imported with problems, e.g. a draft or a missing image
+    pub warnings: Vec<String>,
+    /// Old url and new url
+    pub redirects: Vec<(String, String)>,
+}
+
+impl ImportTarget {
+    pub fn from_config(config: &Config) -> Self {
+        ImportTarget {
+            posts_dir: config.paths.posts_dir.clone(),
+            pages_dir: config.paths.pages_dir.clone(),
+            index_base_name: config.defaults.index_base_name.clone().unwrap_or("index".to_string()),
+        }
+    }
+}
+
+impl ImportedPost {
+    /// Url of the post in texted
+    pub fn url(&self) -> String {
+        match self.kind {
+            PostKind::Post => format!("/view/{}/", self.slug),
+            PostKind::Page => format!("/page/{}/", self.slug),
+        }
+    }
+}
+
+pub fn import_cmd(args: ImportArgs) {
+    let res = read_config(&args.config).and_then(|config| {
+        let target = ImportTarget::from_config(&config);
+        match args.source {
+            ImportSource::Wordpress { export, uploads, keep_html, redirects } => {
+                import_wordpress(&export, uploads.as_deref(), keep_html, &target)
+                    .and_then(|report| write_redirects(&report.redirects, &redirects).map(|_| report))
+            }
+            ImportSource::Hugo { dir, drafts, redirects } => {
+                import_static_site(Generator::Hugo, &dir, drafts, &target)
+                    .and_then(|report| write_redirects(&report.redirects, &redirects).map(|_| report))
+            }
+            ImportSource::Jekyll { dir, drafts, redirects } => {
+                import_static_site(Generator::Jekyll, &dir, drafts, &target)
+                    .and_then(|report| write_redirects(&report.redirects, &redirects).map(|_| report))
+            }
+        }
+    });
+
+    match res {
+        Ok(report) => {
+            for warning in &report.warnings {
+                println!("Warning: {}", warning);
+            }
+            println!("{} posts and pages imported, {} warnings", report.imported.len(), report.warnings.len());
+        }
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+/// Dated url name for posts, e.g. 20240229_hello_world, and plain url name for pages, e.g. about
+pub fn import_slug(kind: PostKind, name: &str, date: &NaiveDateTime) -> String {
+    let name = url_name(&name.replace(['-', '_'], " ")).trim_matches('_').to_lowercase();
+    match kind {
+        PostKind::Post => format!("{}_{}", date.format("%Y%m%d"), name),
+        PostKind::Page => name,
+    }
+}
+
+/// Writes the post directory with its assets. Existing posts are not replaced
+pub fn write_post(post: &ImportedPost, target: &ImportTarget) -> io::Result<PathBuf> {
+    let root_dir = match post.kind {
+        PostKind::Post => &target.posts_dir,
+        PostKind::Page => &target.pages_dir,
+    };
+    let post_dir = root_dir.join(&post.slug);
+    if post_dir.exists() {
+        return Err(io::Error::new(ErrorKind::AlreadyExists, format!("{} already exists", post_dir.to_str().unwrap())));
+    }
+    fs::create_dir_all(&post_dir)?;
+
+    let extension = match post.format {
+        BodyFormat::Markdown => "md",
+        BodyFormat::Html => "html",
+    };
+    let post_file = post_dir.join(format!("{}.{}", target.index_base_name, extension));
+    fs::write(&post_file, render_post(post, &Uuid::new_v4().to_string()))?;
+    for (source, file_name) in &post.assets {
+        // Files of page bundles keep their directories, e.g. images/dino.png
+        let asset_file = post_dir.join(file_name);
+        fs::create_dir_all(asset_file.parent().unwrap())?;
+        fs::copy(source, asset_file)?;
+    }
+    Ok(post_file)
+}
+
+fn render_post(post: &ImportedPost, id: &str) -> String {
+    let mut buf = String::new();
+
+    let _ = writeln!(&mut buf, "<!--");
+    let _ = writeln!(&mut buf, "[ID]: # ({})", id);
+    let _ = writeln!(&mut buf, "[DATE]: # ({})", post.date.format("%Y-%m-%d %H:%M:%S%.3f"));
+    let _ = writeln!(&mut buf, "[AUTHOR]: # ({})", post.authors.join(", "));
+    // Tags are separated by spaces, so the spaces inside them become dashes
+    let tags: Vec<String> = post.tags.iter().map(|tag| tag.split_whitespace().collect::<Vec<_>>().join("-")).collect();
+    let _ = writeln!(&mut buf, "[TAGS]: # ({})", tags.join(" "));
+    let _ = writeln!(&mut buf, "-->");
+    let _ = writeln!(&mut buf);
+    match post.format {
+        BodyFormat::Markdown => { let _ = writeln!(&mut buf, "# {}", post.title); }
+        BodyFormat::Html => { let _ = writeln!(&mut buf, "<h1>{}</h1>", post.title); }
+    }
+    let _ = writeln!(&mut buf);
+    buf.push_str(post.body.trim());
+    buf.push('\n');
+    buf
+}
+
+/// Writes the old urls and their texted urls as a TOML table, e.g. `"/2019/05/hello/" = "/view/20190501_hello/"`
+pub fn write_redirects(redirects: &[(String, String)], file: &Path) -> io::Result<()> {
+    if redirects.is_empty() {
+        return Ok(());
+    }
+    let mut table = toml::Table::new();
+    for (old_url, new_url) in redirects {
+        table.insert(old_url.clone(), toml::Value::String(new_url.clone()));
+    }
+    let content = toml::to_string(&table).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    fs::write(file, format!("# Old urls and their texted urls\n{}", content))?;
+    println!("Redirects written to {}", file.to_str().unwrap());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn date() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2019, 5, 1).unwrap().and_hms_opt(10, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn test_render_post() {
+        let post = ImportedPost {
+            kind: PostKind::Post,
+            slug: import_slug(PostKind::Post, "hello-world", &date()),
+            title: "Hello world".to_string(),
+            date: date(),
+            authors: vec!["Thiago".to_string()],
+            tags: vec!["rust".to_string(), "web dev".to_string()],
+            body: "Text\n\n".to_string(),
+            format: BodyFormat::Markdown,
+            assets: vec![],
+            old_urls: vec![],
+        };
+        assert_eq!(post.url(), "/view/20190501_hello_world/");
+        assert_eq!(render_post(&post, "1"), "<!--\n[ID]: # (1)\n[DATE]: # (2019-05-01 10:30:00.000)\n[AUTHOR]: # (Thiago)\n\
+[TAGS]: # (rust web-dev)\n-->\n\n# Hello world\n\nText\n");
+        assert_eq!(import_slug(PostKind::Page, "About_me", &date()), "about_me");
+    }
+}
@@ -1,14 +1,30 @@
 use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
 
-use clap::{arg, Parser, ValueEnum};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
 
 use crate::bootstrap::bootstrap_cmd;
+use crate::check::check_cmd;
+use crate::export::export_cmd;
+use crate::import::import_cmd;
+use crate::metrics::metrics_cmd;
 use crate::post::post_cmd;
+use crate::theme::theme_cmd;
 
 mod test_data;
 mod decompress;
 mod post;
 mod bootstrap;
+mod theme;
+mod check;
+mod import;
+mod wordpress;
+mod html_to_markdown;
+mod front_matter;
+mod static_site;
+mod export;
+mod metrics;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -17,6 +33,16 @@ enum Args {
     Post(PostArgs),
     /// Bootstrap a new blog
     Bootstrap(BootstrapArgs),
+    /// Install, list and switch themes
+    Theme(ThemeArgs),
+    /// Check the posts and pages for problems, e.g. invalid headers or broken links
+    Check(CheckArgs),
+    /// Import posts and pages from other blog engines
+    Import(ImportArgs),
+    /// Export posts and pages as markdown with front matter, e.g. for Hugo or Jekyll
+    Export(ExportArgs),
+    /// Reports of the access metrics written by the server
+    Metrics(MetricsArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -43,6 +69,205 @@ struct BootstrapArgs {
     out_dir: String,
 }
 
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct ThemeArgs {
+    /// Texted configuration file
+    #[arg(short, long, default_value = "texted.toml")]
+    config: PathBuf,
+
+    /// Directory of the installed themes. If empty, themes_dir of the configuration is used
+    #[arg(short, long)]
+    themes_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: ThemeCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ThemeCommand {
+    /// Installs a theme from a directory or a .tar.gz archive
+    Install {
+        /// Theme directory or archive. If empty, the built-in theme is installed
+        source: Option<PathBuf>,
+
+        /// Replaces the theme if it is already installed
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Lists the installed themes. The current theme is marked with *
+    List,
+    /// Sets the theme used by the blog in the configuration file
+    Switch {
+        /// Name of the installed theme
+        name: String,
+    },
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct CheckArgs {
+    /// Texted configuration file
+    #[arg(short, long, default_value = "texted.toml")]
+    config: PathBuf,
+
+    /// Report format. The exit code is 1 if there is any error
+    #[arg(short, long, value_enum, default_value_t = CheckOutput::Human)]
+    output: CheckOutput,
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct ImportArgs {
+    /// Texted configuration file. Posts and pages are written to its posts_dir and pages_dir
+    #[arg(short, long, default_value = "texted.toml")]
+    config: PathBuf,
+
+    #[command(subcommand)]
+    source: ImportSource,
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportSource {
+    /// Imports the published posts and pages of a WordPress export file (WXR)
+    Wordpress {
+        /// Export file, from Tools > Export in the WordPress admin
+        export: PathBuf,
+
+        /// Copy of wp-content/uploads. The media of the posts found in it is copied to the posts
+        #[arg(short, long)]
+        uploads: Option<PathBuf>,
+
+        /// Keeps the content as html posts instead of converting it to markdown
+        #[arg(long)]
+        keep_html: bool,
+
+        /// File mapping the old permalinks to the texted urls
+        #[arg(short, long, default_value = "redirects.toml")]
+        redirects: PathBuf,
+    },
+    /// Imports the content of a Hugo site, with its page bundles and static files
+    Hugo {
+        /// Site directory, with the content and static directories
+        dir: PathBuf,
+
+        /// Also imports the drafts
+        #[arg(long)]
+        drafts: bool,
+
+        /// File mapping the old urls and aliases to the texted urls
+        #[arg(short, long, default_value = "redirects.toml")]
+        redirects: PathBuf,
+    },
+    /// Imports the posts (_posts) and pages of a Jekyll site
+    Jekyll {
+        /// Site directory, with the _posts directory
+        dir: PathBuf,
+
+        /// Also imports the drafts (_drafts) and the unpublished posts
+        #[arg(long)]
+        drafts: bool,
+
+        /// File mapping the old permalinks and redirect_from urls to the texted urls
+        #[arg(short, long, default_value = "redirects.toml")]
+        redirects: PathBuf,
+    },
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct ExportArgs {
+    /// Texted configuration file
+    #[arg(short, long, default_value = "texted.toml")]
+    config: PathBuf,
+
+    /// Layout and front matter of the exported files
+    #[arg(short, long)]
+    format: ExportFormat,
+
+    /// Directory where the posts and pages are written
+    #[arg(short, long, default_value = "export")]
+    output: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct MetricsArgs {
+    /// Texted configuration file, with the location of the metrics
+    #[arg(short, long, default_value = "texted.toml")]
+    config: PathBuf,
+
+    #[command(subcommand)]
+    command: MetricsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum MetricsCommand {
+    /// Total and unique visits grouped by day, week, post, tag or api
+    Report {
+        /// Metrics file or directory. If empty, location of the [metrics] configuration
+        #[arg(short, long)]
+        location: Option<PathBuf>,
+
+        /// First day included, e.g. 2024-05-01
+        #[arg(long)]
+        from: Option<NaiveDate>,
+
+        /// Last day included
+        #[arg(long)]
+        to: Option<NaiveDate>,
+
+        #[arg(short, long, value_enum, default_value_t = MetricsGroupBy::Post)]
+        group_by: MetricsGroupBy,
+
+        /// Only the rows with the most visits
+        #[arg(short, long)]
+        top: Option<usize>,
+
+        #[arg(short, long, value_enum, default_value_t = MetricsOutput::Table)]
+        output: MetricsOutput,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum MetricsGroupBy {
+    /// All the requests per day
+    Day,
+    /// All the requests per ISO week
+    Week,
+    /// Views of each post
+    Post,
+    /// Views of the posts with each tag
+    Tag,
+    /// Requests to each api: view, page, list, archive, author, series, index and rss
+    Api,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum MetricsOutput {
+    /// Aligned columns
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum ExportFormat {
+    /// Page bundles in content/, keeping the texted urls
+    Hugo,
+    /// Posts in _posts/ and pages in the site directory, keeping the texted urls
+    Jekyll,
+    /// Directory posts with a YAML front matter, for other tools
+    Plain,
+}
+
+#[derive(Clone, Debug, ValueEnum)]
+enum CheckOutput {
+    /// One line per problem
+    Human,
+    /// JSON object with the problems, e.g. for CI
+    Json,
+}
 
 #[derive(Clone, Debug, ValueEnum)]
 enum PostOutput {
@@ -66,6 +291,11 @@ fn main() {
     match args {
         Args::Post(args) => post_cmd(args),
         Args::Bootstrap(args) => bootstrap_cmd(args),
+        Args::Theme(args) => theme_cmd(args),
+        Args::Check(args) => check_cmd(args),
+        Args::Import(args) => import_cmd(args),
+        Args::Export(args) => export_cmd(args),
+        Args::Metrics(args) => metrics_cmd(args),
     };
 }
 
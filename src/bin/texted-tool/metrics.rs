@@ -0,0 +1,268 @@
+use std::collections::{HashMap, HashSet};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use spdlog::LevelFilter;
+
+use texted::config::{read_config, Config};
+use texted::content::content_file::ContentFile;
+use texted::content::content_renderer::RenderOptions;
+use texted::content::format_registry::FormatRegistry;
+use texted::post_list::{list_post_files, PostListType};
+
+use crate::{MetricsArgs, MetricsCommand, MetricsGroupBy, MetricsOutput};
+
+lazy_static! {
+    static ref RECORD_REGEX: Regex = Regex::new(r"(?s)\[SOR\]([^|]*)\|(.*?)\[EOR\]").unwrap();
+}
+
+/// Time slot of one metric, as written by the server: `[SOR]{date} {time}|{json}[EOR]`
+#[derive(Deserialize, Debug)]
+struct MetricRecord {
+    /// Api of the request: view, page, list, archive, author, series, index or rss
+    key: String,
+    /// Post name, page name, tag, etc., depending on the api
+    value: String,
+    total: u64,
+    unique_total: u64,
+    /// Missing when the server does not store the origins
+    #[serde(default)]
+    origins: Vec<String>,
+    stats_date_start: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct ReportRow {
+    name: String,
+    total: u64,
+    /// Distinct origins in all the time slots of the row. Without the origins, the sum of the unique
+    /// visits of each time slot
+    unique: u64,
+}
+
+pub fn metrics_cmd(args: MetricsArgs) {
+    // The tag report renders the posts, which should not log
+    spdlog::default_logger().set_level_filter(LevelFilter::Off);
+
+    let res = read_config(&args.config).and_then(|config| match args.command {
+        MetricsCommand::Report { location, from, to, group_by, top, output } => {
+            let location = location.unwrap_or_else(|| metrics_location(&config));
+            let records = read_records(&location)?;
+            let records: Vec<MetricRecord> = records.into_iter()
+                .filter(|r| from.is_none_or(|from| r.stats_date_start.date_naive() >= from))
+                .filter(|r| to.is_none_or(|to| r.stats_date_start.date_naive() <= to))
+                .collect();
+            let post_tags = match group_by {
+                MetricsGroupBy::Tag => read_post_tags(&config)?,
+                _ => HashMap::new(),
+            };
+            let mut rows = group_records(&records, group_by, &post_tags);
+            if let Some(top) = top {
+                rows.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+                rows.truncate(top);
+            }
+            Ok(format_report(&rows, group_by, output))
+        }
+    });
+
+    match res {
+        Ok(report) => print!("{}", report),
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+/// Location of the metrics of the configuration, or the default one of the server
+fn metrics_location(config: &Config) -> PathBuf {
+    match config.metrics.as_ref().and_then(|metrics| metrics.location.clone()) {
+        Some(location) => location,
+        None => dirs::cache_dir().unwrap().join("Texted").join("metrics").join("metrics.log"),
+    }
+}
+
+/// Records of the metric file and of its rotated files, e.g. metrics.log and metrics_2024-05-01.log.
+/// The location can also be a directory with the files
+fn read_records(location: &Path) -> io::Result<Vec<MetricRecord>> {
+    let files: Vec<PathBuf> = if location.is_dir() {
+        fs::read_dir(location)?.flatten().map(|entry| entry.path()).filter(|path| path.is_file()).collect()
+    } else {
+        let stem = location.file_stem().unwrap_or_default().to_str().unwrap().to_string();
+        let dir = location.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let entries = fs::read_dir(dir)
+            .map_err(|e| io::Error::new(e.kind(), format!("Error reading the metrics in {}: {}", dir.to_str().unwrap(), e)))?;
+        entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension() == location.extension())
+            .filter(|path| path.file_stem().and_then(|s| s.to_str()).is_some_and(|s| s == stem || s.starts_with(&format!("{}_", stem))))
+            .collect()
+    };
+
+    let mut records = vec![];
+    for file in files {
+        let content = fs::read_to_string(&file)?;
+        records.extend(parse_records(&content).map_err(|e| io::Error::new(ErrorKind::InvalidData, format!("{}: {}", file.to_str().unwrap(), e)))?);
+    }
+    Ok(records)
+}
+
+fn parse_records(content: &str) -> serde_json::Result<Vec<MetricRecord>> {
+    RECORD_REGEX.captures_iter(content)
+        .map(|caps| serde_json::from_str(&caps[2]))
+        .collect()
+}
+
+/// Tags of each post, by post name
+fn read_post_tags(config: &Config) -> io::Result<HashMap<String, Vec<String>>> {
+    let list_type = match config.defaults.index_base_name {
+        None => PostListType::AnyContentFile,
+        Some(ref base_name) => PostListType::IndexBaseName(base_name.clone()),
+    };
+    let formats = FormatRegistry::with_markdown(&config.markdown);
+    let mut post_tags = HashMap::new();
+    for link in list_post_files(&config.paths.posts_dir, &list_type, &formats).map_err(io::Error::other)? {
+        let content = ContentFile::from_file(link.post_name.clone(), link.post_path.clone())
+            .and_then(|content_file| formats.render(&content_file, RenderOptions::FullContent));
+        if let Ok(content) = content {
+            post_tags.insert(link.post_name, content.header.tags);
+        }
+    }
+    Ok(post_tags)
+}
+
+/// Totals by day, week or api of all the requests, and by post or tag of the post views.
+/// Days and weeks are in chronological order, the others by total
+fn group_records(records: &[MetricRecord], group_by: MetricsGroupBy, post_tags: &HashMap<String, Vec<String>>) -> Vec<ReportRow> {
+    let mut groups: HashMap<String, (u64, HashSet<&str>, u64)> = HashMap::new();
+    for record in records {
+        let date = record.stats_date_start.date_naive();
+        let names = match group_by {
+            MetricsGroupBy::Day => vec![date.to_string()],
+            MetricsGroupBy::Week => vec![week_name(date)],
+            MetricsGroupBy::Api => vec![record.key.clone()],
+            MetricsGroupBy::Post if record.key == "view" => vec![record.value.clone()],
+            MetricsGroupBy::Tag if record.key == "view" => post_tags.get(&record.value).cloned().unwrap_or_default(),
+            MetricsGroupBy::Post | MetricsGroupBy::Tag => vec![],
+        };
+        for name in names {
+            let (total, origins, unique_without_origins) = groups.entry(name).or_default();
+            *total += record.total;
+            origins.extend(record.origins.iter().map(|origin| origin.as_str()));
+            if record.origins.is_empty() {
+                *unique_without_origins += record.unique_total;
+            }
+        }
+    }
+
+    let mut rows: Vec<ReportRow> = groups.into_iter()
+        .map(|(name, (total, origins, unique_without_origins))| ReportRow { name, total, unique: origins.len() as u64 + unique_without_origins })
+        .collect();
+    match group_by {
+        MetricsGroupBy::Day | MetricsGroupBy::Week => rows.sort_by(|a, b| a.name.cmp(&b.name)),
+        _ => rows.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name))),
+    }
+    rows
+}
+
+/// ISO week, e.g. 2024-W18
+fn week_name(date: NaiveDate) -> String {
+    let week = date.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+fn format_report(rows: &[ReportRow], group_by: MetricsGroupBy, output: MetricsOutput) -> String {
+    let column = match group_by {
+        MetricsGroupBy::Day => "day",
+        MetricsGroupBy::Week => "week",
+        MetricsGroupBy::Post => "post",
+        MetricsGroupBy::Tag => "tag",
+        MetricsGroupBy::Api => "api",
+    };
+    match output {
+        MetricsOutput::Table => {
+            let width = rows.iter().map(|row| row.name.chars().count()).chain([column.len()]).max().unwrap();
+            let mut table = format!("{:<width$}  {:>10}  {:>10}\n", column, "total", "unique", width = width);
+            for row in rows {
+                table.push_str(&format!("{:<width$}  {:>10}  {:>10}\n", row.name, row.total, row.unique, width = width));
+            }
+            table
+        }
+        MetricsOutput::Csv => {
+            let mut csv = format!("{},total,unique\n", column);
+            for row in rows {
+                csv.push_str(&format!("{},{},{}\n", csv_field(&row.name), row.total, row.unique));
+            }
+            csv
+        }
+        MetricsOutput::Json => format!("{}\n", serde_json::to_string_pretty(rows).unwrap()),
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    match text.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::temp_dir;
+
+    const LOG: &str = r#"[SOR]2024-05-01 10:01:00.000|{"key":"view","value":"hello","unique_total":2,"total":3,"origins":["1.1.1.1","2.2.2.2"],"stats_date_start":"2024-05-01T10:00:00Z","stats_date_end":"2024-05-01T10:01:00Z"}[EOR]
+[SOR]2024-05-01 10:02:00.000|{"key":"view","value":"hello","unique_total":1,"total":1,"origins":["1.1.1.1"],"stats_date_start":"2024-05-01T10:01:00Z","stats_date_end":"2024-05-01T10:02:00Z"}[EOR]
+[SOR]2024-05-02 08:01:00.000|{"key":"view","value":"other","unique_total":1,"total":1,"origins":["3.3.3.3"],"stats_date_start":"2024-05-02T08:00:00Z","stats_date_end":"2024-05-02T08:01:00Z"}[EOR]
+[SOR]2024-05-06 08:01:00.000|{"key":"rss","value":"","unique_total":1,"total":2,"origins":["3.3.3.3"],"stats_date_start":"2024-05-06T08:00:00Z","stats_date_end":"2024-05-06T08:01:00Z"}[EOR]
+"#;
+
+    fn row(name: &str, total: u64, unique: u64) -> ReportRow {
+        ReportRow { name: name.to_string(), total, unique }
+    }
+
+    #[test]
+    fn test_group_records() {
+        let records = parse_records(LOG).unwrap();
+        assert_eq!(records.len(), 4);
+        let no_tags = HashMap::new();
+
+        assert_eq!(group_records(&records, MetricsGroupBy::Post, &no_tags), vec![row("hello", 4, 2), row("other", 1, 1)]);
+        assert_eq!(group_records(&records, MetricsGroupBy::Api, &no_tags), vec![row("view", 5, 3), row("rss", 2, 1)]);
+        assert_eq!(group_records(&records, MetricsGroupBy::Day, &no_tags), vec![row("2024-05-01", 4, 2), row("2024-05-02", 1, 1), row("2024-05-06", 2, 1)]);
+        assert_eq!(group_records(&records, MetricsGroupBy::Week, &no_tags), vec![row("2024-W18", 5, 3), row("2024-W19", 2, 1)]);
+
+        let post_tags = HashMap::from([
+            ("hello".to_string(), vec!["rust".to_string(), "web".to_string()]),
+            ("other".to_string(), vec!["rust".to_string()]),
+        ]);
+        assert_eq!(group_records(&records, MetricsGroupBy::Tag, &post_tags), vec![row("rust", 5, 3), row("web", 4, 2)]);
+        // Records written without the origins add their unique visits
+        let without_origins = r#"[SOR]2024-05-01 10:03:00.000|{"key":"view","value":"hello","unique_total":2,"total":2,"stats_date_start":"2024-05-01T10:02:00Z","stats_date_end":"2024-05-01T10:03:00Z"}[EOR]"#;
+        let mut records = records;
+        records.extend(parse_records(without_origins).unwrap());
+        assert_eq!(group_records(&records, MetricsGroupBy::Post, &no_tags), vec![row("hello", 6, 4), row("other", 1, 1)]);
+    }
+
+    #[test]
+    fn test_format_report() {
+        let rows = vec![row("hello, world", 4, 2), row("other", 1, 1)];
+        assert_eq!(format_report(&rows, MetricsGroupBy::Post, MetricsOutput::Table),
+                   "post               total      unique\nhello, world           4           2\nother                  1           1\n");
+        assert_eq!(format_report(&rows, MetricsGroupBy::Post, MetricsOutput::Csv), "post,total,unique\n\"hello, world\",4,2\nother,1,1\n");
+        assert!(format_report(&rows, MetricsGroupBy::Post, MetricsOutput::Json).contains("\"name\": \"other\""));
+        assert!(parse_records("[SOR]2024-05-01 10:01:00.000|{bad}[EOR]").is_err());
+    }
+
+    #[test]
+    fn test_read_records() {
+        let dir = temp_dir("metrics");
+        fs::write(dir.join("metrics_2024-05-01.log"), LOG).unwrap();
+        fs::write(dir.join("metrics.log"), LOG).unwrap();
+        fs::write(dir.join("server.log"), LOG).unwrap();
+        assert_eq!(read_records(&dir.join("metrics.log")).unwrap().len(), 8);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -50,6 +50,14 @@ fn render_body() -> String {
 }
 
 fn post_url_from_title(title: &str, date: &NaiveDate) -> String {
+    let url = url_name(title);
+    let date = date.format("%Y%m%d");
+
+    format!("{}_{}", date, url)
+}
+
+/// Lowercase title with `_` instead of spaces, e.g. "Post title - ábaco" -> "post_title_abaco"
+pub fn url_name(title: &str) -> String {
     let alpha_chars: String = title.chars()
         .filter(|&c| c.is_alphanumeric() || c == ' ')
         .map(|c| if c == ' ' { '_' } else { c })
@@ -66,10 +74,7 @@ fn post_url_from_title(title: &str, date: &NaiveDate) -> String {
         prev_char = Some(c);
     }
 
-    let url = unidecode::unidecode(&url);
-    let date = date.format("%Y%m%d");
-
-    format!("{}_{}", date, url)
+    unidecode::unidecode(&url)
 }
 
 pub fn post_cmd(args: PostArgs) {
@@ -130,7 +135,7 @@ mod tests {
         let name = "Thiago";
         let date = "2024-02-27 06:20:53.000";
         let title = "This is a title";
-        let header = render_header(&id, &name, &date, Some(title));
+        let header = render_header(id, name, date, Some(title));
 
         assert_eq!(header, POST_DATA);
     }
@@ -138,7 +143,7 @@ mod tests {
     #[test]
     fn test_url_from_title() {
         //let date = Utc::now();
-        let date = NaiveDate::from_ymd_opt(2024, 02, 29).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
         let title = "Post title of mine ábaco - dir2";
         let url = post_url_from_title(title, &date);
         assert_eq!(url, "20240229_post_title_of_mine_abaco_dir2");
@@ -0,0 +1,413 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{fs, io};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use toml::Table;
+
+use texted::util::os_helper::get_name;
+
+use crate::front_matter::{flag, list, parse_date, split_front_matter, text};
+use crate::import::{import_slug, write_post, BodyFormat, ImportReport, ImportTarget, ImportedPost, PostKind};
+
+lazy_static! {
+    static ref MORE_REGEX: Regex = Regex::new(r"<!--\s*more\s*-->").unwrap();
+    static ref HUGO_SHORTCODE_REGEX: Regex = Regex::new(r"\{\{[<%]\s*/?\s*([\w./-]+)").unwrap();
+    static ref HUGO_GIST_REGEX: Regex = Regex::new(r"\{\{<\s*gist\s+([\w-]+)\s+(\w+)").unwrap();
+    static ref LIQUID_HIGHLIGHT_REGEX: Regex = Regex::new(r"\{%-?\s*highlight\s+([\w+#-]+)[^%]*%\}").unwrap();
+    static ref LIQUID_END_HIGHLIGHT_REGEX: Regex = Regex::new(r"\{%-?\s*endhighlight\s*-?%\}").unwrap();
+    static ref LIQUID_RAW_REGEX: Regex = Regex::new(r"\{%-?\s*(?:end)?raw\s*-?%\}").unwrap();
+    static ref LIQUID_RAW_BLOCK_REGEX: Regex = Regex::new(r"(?s)\{%-?\s*raw\s*-?%\}.*?\{%-?\s*endraw\s*-?%\}").unwrap();
+    static ref LIQUID_TAG_REGEX: Regex = Regex::new(r"\{%-?\s*(\w+)|\{\{-?\s*([\w.]+)").unwrap();
+    static ref JEKYLL_FILE_REGEX: Regex = Regex::new(r"^(\d{4}-\d{2}-\d{2})-(.+)$").unwrap();
+    static ref ABSOLUTE_REF_REGEX: Regex = Regex::new(r#"(\]\(|src=["'])(/[^)"'\s]+)"#).unwrap();
+}
+
+/// Shortcodes of Hugo with a texted shortcode of the same name
+const SUPPORTED_SHORTCODES: [&str; 3] = ["youtube", "gist", "figure"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Generator {
+    Hugo,
+    Jekyll,
+}
+
+/// Markdown file of the site to import
+#[derive(Debug)]
+struct SourceFile {
+    path: PathBuf,
+    kind: PostKind,
+    /// Directory of a Hugo page bundle, whose files are copied with the post
+    bundle_dir: Option<PathBuf>,
+    /// Hugo section, e.g. posts for content/posts/hello.md
+    section: String,
+}
+
+/// Imports the posts and pages of a Hugo or Jekyll site. Drafts are skipped unless with_drafts is set
+pub fn import_static_site(generator: Generator, site_dir: &Path, with_drafts: bool, target: &ImportTarget) -> io::Result<ImportReport> {
+    let files = match generator {
+        Generator::Hugo => hugo_files(site_dir)?,
+        Generator::Jekyll => jekyll_files(site_dir, with_drafts)?,
+    };
+    let static_dir = match generator {
+        Generator::Hugo => site_dir.join("static"),
+        Generator::Jekyll => site_dir.to_path_buf(),
+    };
+
+    let mut report = ImportReport::default();
+    for file in files {
+        let file_name = file.path.to_str().unwrap().to_string();
+        let post = match to_post(generator, &file, &static_dir, with_drafts, &mut report.warnings) {
+            Ok(Some(post)) => post,
+            Ok(None) => continue,
+            Err(e) => {
+                report.warnings.push(format!("Skipped {}: {}", file_name, e));
+                continue;
+            }
+        };
+        match write_post(&post, target) {
+            Ok(post_file) => {
+                report.redirects.extend(post.old_urls.iter().map(|old_url| (old_url.clone(), post.url())));
+                report.imported.push(post_file);
+            }
+            Err(e) => report.warnings.push(format!("Skipped {}: {}", file_name, e)),
+        }
+    }
+    Ok(report)
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext == "md" || ext == "markdown")
+}
+
+fn sorted_entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| io::Error::new(e.kind(), format!("Error reading {}: {}", dir.to_str().unwrap(), e)))?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    Ok(entries)
+}
+
+fn bundle_index(dir: &Path) -> Option<PathBuf> {
+    ["index.md", "index.markdown"].iter().map(|name| dir.join(name)).find(|path| path.is_file())
+}
+
+/// Files of content/ (or of the directory itself): top level files and bundles are pages, the ones in sections are posts.
+/// List pages (_index.md) are skipped
+fn hugo_files(site_dir: &Path) -> io::Result<Vec<SourceFile>> {
+    let content_dir = match site_dir.join("content") {
+        dir if dir.is_dir() => dir,
+        _ => site_dir.to_path_buf(),
+    };
+    let mut files = vec![];
+    for path in sorted_entries(&content_dir)? {
+        if path.is_dir() {
+            match bundle_index(&path) {
+                Some(index) => files.push(SourceFile { path: index, kind: PostKind::Page, bundle_dir: Some(path), section: "".to_string() }),
+                None => {
+                    let section = path.file_name().unwrap().to_str().unwrap().to_string();
+                    hugo_section_files(&path, &section, &mut files)?;
+                }
+            }
+        } else if is_markdown(&path) && !path.file_stem().unwrap().to_str().unwrap().starts_with('_') {
+            files.push(SourceFile { path, kind: PostKind::Page, bundle_dir: None, section: "".to_string() });
+        }
+    }
+    Ok(files)
+}
+
+fn hugo_section_files(dir: &Path, section: &str, files: &mut Vec<SourceFile>) -> io::Result<()> {
+    for path in sorted_entries(dir)? {
+        if path.is_dir() {
+            match bundle_index(&path) {
+                Some(index) => files.push(SourceFile { path: index, kind: PostKind::Post, bundle_dir: Some(path), section: section.to_string() }),
+                None => hugo_section_files(&path, section, files)?,
+            }
+        } else if is_markdown(&path) && !path.file_stem().unwrap().to_str().unwrap().starts_with('_') {
+            files.push(SourceFile { path, kind: PostKind::Post, bundle_dir: None, section: section.to_string() });
+        }
+    }
+    Ok(())
+}
+
+/// Posts of _posts/ (and _drafts/), and pages: markdown files in the site directory, except the README
+fn jekyll_files(site_dir: &Path, with_drafts: bool) -> io::Result<Vec<SourceFile>> {
+    let mut files = vec![];
+    let mut post_dirs = vec![site_dir.join("_posts")];
+    if with_drafts {
+        post_dirs.push(site_dir.join("_drafts"));
+    }
+    for dir in post_dirs.iter().filter(|dir| dir.is_dir()) {
+        jekyll_post_files(dir, &mut files)?;
+    }
+    for path in sorted_entries(site_dir)? {
+        let name = path.file_stem().unwrap_or_default().to_str().unwrap().to_lowercase();
+        if is_markdown(&path) && !["readme", "index", "404", "changelog", "license"].contains(&name.as_str()) {
+            files.push(SourceFile { path, kind: PostKind::Page, bundle_dir: None, section: "".to_string() });
+        }
+    }
+    Ok(files)
+}
+
+fn jekyll_post_files(dir: &Path, files: &mut Vec<SourceFile>) -> io::Result<()> {
+    for path in sorted_entries(dir)? {
+        if path.is_dir() {
+            jekyll_post_files(&path, files)?;
+        } else if is_markdown(&path) {
+            files.push(SourceFile { path, kind: PostKind::Post, bundle_dir: None, section: "".to_string() });
+        }
+    }
+    Ok(())
+}
+
+fn to_post(generator: Generator, file: &SourceFile, static_dir: &Path, with_drafts: bool, warnings: &mut Vec<String>) -> io::Result<Option<ImportedPost>> {
+    let raw_content = fs::read_to_string(&file.path)?;
+    let (values, body) = split_front_matter(&raw_content)?;
+    let file_name = file.path.to_str().unwrap();
+
+    let draft = match generator {
+        Generator::Hugo => flag(&values, "draft").unwrap_or(false),
+        Generator::Jekyll => !flag(&values, "published").unwrap_or(true),
+    };
+    if draft && !with_drafts {
+        warnings.push(format!("Skipped draft {}", file_name));
+        return Ok(None);
+    }
+
+    // Name of the post in the old urls: bundle directory or file name, without the Jekyll date
+    let stem = match file.bundle_dir {
+        Some(ref dir) => dir.file_name().unwrap().to_str().unwrap().to_string(),
+        None => file.path.file_stem().unwrap().to_str().unwrap().to_string(),
+    };
+    let (file_date, stem) = match JEKYLL_FILE_REGEX.captures(&stem) {
+        Some(caps) if generator == Generator::Jekyll => (parse_date(&caps[1]), caps[2].to_string()),
+        _ => (None, stem),
+    };
+    let date = text(&values, "date").or(text(&values, "publishDate")).and_then(|date| parse_date(&date))
+        .or(file_date)
+        .unwrap_or_else(|| file_modified(&file.path));
+    let name = text(&values, "slug").unwrap_or(stem.clone());
+    let title = text(&values, "title").unwrap_or(stem.replace(['-', '_'], " "));
+
+    let mut authors = list(&values, "authors");
+    if authors.is_empty() {
+        authors = text(&values, "author").map_or(vec![get_name()], |author| vec![author]);
+    }
+    let mut tags: Vec<String> = vec![];
+    for tag in list(&values, "tags").into_iter().chain(list(&values, "categories")) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    let body = MORE_REGEX.replace_all(body, "<!-- more -->");
+    let (body, unsupported) = match generator {
+        Generator::Hugo => convert_hugo(&body),
+        Generator::Jekyll => convert_jekyll(&body),
+    };
+    if !unsupported.is_empty() {
+        warnings.push(format!("{} uses unsupported shortcodes or tags: {}", file_name, unsupported.into_iter().collect::<Vec<_>>().join(", ")));
+    }
+    let (body, mut assets) = map_static_assets(&body, static_dir);
+    if let Some(ref bundle_dir) = file.bundle_dir {
+        bundle_assets(bundle_dir, bundle_dir, &file.path, &mut assets)?;
+    }
+
+    let old_urls = match generator {
+        Generator::Hugo => hugo_urls(&values, file, &name),
+        Generator::Jekyll => jekyll_urls(&values, file, &name, &date),
+    };
+
+    Ok(Some(ImportedPost {
+        kind: file.kind,
+        slug: import_slug(file.kind, &name, &date),
+        title,
+        date,
+        authors,
+        tags,
+        body,
+        format: BodyFormat::Markdown,
+        assets,
+        old_urls,
+    }))
+}
+
+fn file_modified(path: &Path) -> NaiveDateTime {
+    let modified: DateTime<Utc> = fs::metadata(path).and_then(|md| md.modified()).unwrap_or(SystemTime::now()).into();
+    modified.naive_utc()
+}
+
+/// Converts the gist shortcode, returning the shortcodes without a texted equivalent
+fn convert_hugo(body: &str) -> (String, BTreeSet<String>) {
+    let body = HUGO_GIST_REGEX.replace_all(body, "{{< gist $1/$2").to_string();
+    let unsupported = HUGO_SHORTCODE_REGEX.captures_iter(&body)
+        .map(|caps| caps[1].to_string())
+        .filter(|name| !SUPPORTED_SHORTCODES.contains(&name.as_str()))
+        .collect();
+    (body, unsupported)
+}
+
+/// Converts highlight blocks to fenced code and removes raw tags, returning the other liquid tags
+fn convert_jekyll(body: &str) -> (String, BTreeSet<String>) {
+    let body = LIQUID_HIGHLIGHT_REGEX.replace_all(body, "```$1");
+    let body = LIQUID_END_HIGHLIGHT_REGEX.replace_all(&body, "```");
+    let unsupported = LIQUID_TAG_REGEX.captures_iter(&LIQUID_RAW_BLOCK_REGEX.replace_all(&body, ""))
+        .filter_map(|caps| caps.get(1).or(caps.get(2)).map(|name| name.as_str().to_string()))
+        .collect();
+    (LIQUID_RAW_REGEX.replace_all(&body, "").to_string(), unsupported)
+}
+
+/// Absolute references to files of the static directory, e.g. `/images/dino.png`, become files of the post
+fn map_static_assets(body: &str, static_dir: &Path) -> (String, Vec<(PathBuf, String)>) {
+    let mut assets: Vec<(PathBuf, String)> = vec![];
+    let body = ABSOLUTE_REF_REGEX.replace_all(body, |caps: &Captures| {
+        let source = static_dir.join(caps[2].trim_start_matches('/'));
+        if !source.is_file() || source.components().any(|c| c.as_os_str() == "..") {
+            return caps[0].to_string();
+        }
+        let file_name = match assets.iter().find(|(path, _)| *path == source) {
+            Some((_, file_name)) => file_name.clone(),
+            None => {
+                let base_name = source.file_name().unwrap().to_str().unwrap().to_string();
+                let file_name = match assets.iter().any(|(_, name)| *name == base_name) {
+                    true => caps[2].trim_start_matches('/').replace('/', "_"),
+                    false => base_name,
+                };
+                assets.push((source, file_name.clone()));
+                file_name
+            }
+        };
+        format!("{}{}", &caps[1], file_name)
+    }).to_string();
+    (body, assets)
+}
+
+/// Files of the page bundle, keeping their path inside it
+fn bundle_assets(bundle_dir: &Path, dir: &Path, index: &Path, assets: &mut Vec<(PathBuf, String)>) -> io::Result<()> {
+    for path in sorted_entries(dir)? {
+        if path.is_dir() {
+            bundle_assets(bundle_dir, &path, index, assets)?;
+        } else if path != index {
+            let file_name = path.strip_prefix(bundle_dir).unwrap().to_str().unwrap().to_string();
+            if !assets.iter().any(|(_, name)| *name == file_name) {
+                assets.push((path, file_name));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `url`, aliases and the default url of Hugo: /{section}/{name}/ for posts and /{name}/ for pages
+fn hugo_urls(values: &Table, file: &SourceFile, name: &str) -> Vec<String> {
+    let name = name.to_lowercase().replace(' ', "-");
+    let mut urls = vec![match text(values, "url") {
+        Some(url) => url,
+        None if file.section.is_empty() => format!("/{}/", name),
+        None => format!("/{}/{}/", file.section, name),
+    }];
+    urls.extend(list(values, "aliases"));
+    urls
+}
+
+/// Permalink, redirect_from and the default url of Jekyll: /{categories}/{yyyy}/{mm}/{dd}/{name}.html for posts
+/// and /{name}.html for pages
+fn jekyll_urls(values: &Table, file: &SourceFile, name: &str, date: &NaiveDateTime) -> Vec<String> {
+    let default_url = match file.kind {
+        PostKind::Post => {
+            let categories: String = list(values, "categories").iter().map(|c| format!("/{}", c)).collect();
+            format!("{}/{}/{}.html", categories, date.format("%Y/%m/%d"), name)
+        }
+        PostKind::Page => format!("/{}.html", name),
+    };
+    let mut urls = vec![match text(values, "permalink") {
+        // Permalinks with placeholders, like /:title/, are not urls
+        Some(permalink) if !permalink.contains(':') => permalink,
+        _ => default_url,
+    }];
+    urls.extend(list(values, "redirect_from"));
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::temp_dir;
+
+    fn target(dir: &Path) -> ImportTarget {
+        ImportTarget { posts_dir: dir.join("out/posts"), pages_dir: dir.join("out/pages"), index_base_name: "index".to_string() }
+    }
+
+    #[test]
+    fn test_hugo() {
+        let dir = temp_dir("hugo");
+        let content = dir.join("site/content");
+        fs::create_dir_all(content.join("posts/bundle/images")).unwrap();
+        fs::create_dir_all(dir.join("site/static/img")).unwrap();
+        fs::write(content.join("_index.md"), "---\ntitle: Home\n---\n").unwrap();
+        fs::write(content.join("about.md"), "+++\ntitle = \"About\"\ndate = 2024-01-01\n+++\nMe").unwrap();
+        fs::write(content.join("posts/first.md"), "---\ntitle: First\ndate: 2024-05-01T10:00:00+02:00\n\
+tags: [rust]\ncategories: [blog]\nauthor: Thiago\naliases: [/old/first/]\n---\nIntro\n<!--more-->\n\
+![Dino](/img/dino.png)\n{{< gist thiago 123 >}}\n{{< tweet user=\"x\" id=\"1\" >}}\n").unwrap();
+        fs::write(content.join("posts/draft.md"), "---\ntitle: Draft\ndraft: true\n---\n").unwrap();
+        fs::write(content.join("posts/bundle/index.md"), "---\ntitle: Bundle\ndate: 2024-06-01\nslug: my-bundle\n---\n![x](images/x.png)").unwrap();
+        fs::write(content.join("posts/bundle/images/x.png"), "png").unwrap();
+        fs::write(dir.join("site/static/img/dino.png"), "png").unwrap();
+
+        let report = import_static_site(Generator::Hugo, &dir.join("site"), false, &target(&dir)).unwrap();
+        let out = dir.join("out");
+        assert_eq!(report.imported, vec![out.join("pages/about/index.md"), out.join("posts/20240601_my_bundle/index.md"), out.join("posts/20240501_first/index.md")]);
+        assert_eq!(report.warnings.len(), 2, "{:?}", report.warnings);
+        assert!(report.warnings[1].ends_with("uses unsupported shortcodes or tags: tweet"), "{:?}", report.warnings);
+        assert_eq!(report.redirects, vec![
+            ("/about/".to_string(), "/page/about/".to_string()),
+            ("/posts/my-bundle/".to_string(), "/view/20240601_my_bundle/".to_string()),
+            ("/posts/first/".to_string(), "/view/20240501_first/".to_string()),
+            ("/old/first/".to_string(), "/view/20240501_first/".to_string()),
+        ]);
+
+        let post = fs::read_to_string(out.join("posts/20240501_first/index.md")).unwrap();
+        assert!(post.contains("[DATE]: # (2024-05-01 10:00:00.000)\n[AUTHOR]: # (Thiago)\n[TAGS]: # (rust blog)\n"), "{}", post);
+        assert!(post.ends_with("# First\n\nIntro\n<!-- more -->\n![Dino](dino.png)\n{{< gist thiago/123 >}}\n{{< tweet user=\"x\" id=\"1\" >}}\n"), "{}", post);
+        assert!(out.join("posts/20240501_first/dino.png").is_file());
+        assert!(out.join("posts/20240601_my_bundle/images/x.png").is_file());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_jekyll() {
+        let dir = temp_dir("jekyll");
+        let site = dir.join("site");
+        fs::create_dir_all(site.join("_posts")).unwrap();
+        fs::create_dir_all(site.join("assets")).unwrap();
+        fs::write(site.join("README.md"), "# Readme").unwrap();
+        fs::write(site.join("about.md"), "---\nlayout: page\ntitle: About\npermalink: /about/\n---\nMe").unwrap();
+        fs::write(site.join("_posts/2024-05-01-hello-world.md"), "---\nlayout: post\ntitle: \"Hello\"\ncategories: dev news\n\
+tags: rust\nredirect_from:\n  - /hello/\n---\n{% highlight rust %}\nfn main() {}\n{% endhighlight %}\n\
+{% raw %}{{ x }}{% endraw %}\n{% include note.html %}\n<img src=\"/assets/a.png\">").unwrap();
+        fs::write(site.join("_posts/2024-05-02-unpublished.md"), "---\ntitle: Unpublished\npublished: false\n---\n").unwrap();
+        fs::write(site.join("assets/a.png"), "png").unwrap();
+
+        let report = import_static_site(Generator::Jekyll, &site, false, &target(&dir)).unwrap();
+        let out = dir.join("out");
+        assert_eq!(report.imported, vec![out.join("posts/20240501_hello_world/index.md"), out.join("pages/about/index.md")]);
+        assert_eq!(report.warnings.len(), 2, "{:?}", report.warnings);
+        assert!(report.warnings[0].ends_with("uses unsupported shortcodes or tags: include"), "{:?}", report.warnings);
+        assert_eq!(report.redirects, vec![
+            ("/dev/news/2024/05/01/hello-world.html".to_string(), "/view/20240501_hello_world/".to_string()),
+            ("/hello/".to_string(), "/view/20240501_hello_world/".to_string()),
+            ("/about/".to_string(), "/page/about/".to_string()),
+        ]);
+
+        let post = fs::read_to_string(out.join("posts/20240501_hello_world/index.md")).unwrap();
+        assert!(post.contains("[DATE]: # (2024-05-01 00:00:00.000)\n"), "{}", post);
+        assert!(post.contains("[TAGS]: # (rust dev news)\n"), "{}", post);
+        assert!(post.ends_with("# Hello\n\n```rust\nfn main() {}\n```\n{{ x }}\n{% include note.html %}\n<img src=\"a.png\">\n"), "{}", post);
+        assert!(out.join("posts/20240501_hello_world/a.png").is_file());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -9,3 +9,12 @@ pub(crate) const POST_DATA: &str = r#"<!--
 # This is a title
 
 "#;
+
+/// Empty directory for the files of a test, removed and created again on each run
+#[cfg(test)]
+pub(crate) fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("texted-tool-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
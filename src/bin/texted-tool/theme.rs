@@ -0,0 +1,244 @@
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::{fs, io};
+
+use flate2::read::GzDecoder;
+use tar::Archive;
+use toml_edit::{value, DocumentMut, Item, Table};
+
+use texted::config::read_config;
+use texted::theme::{list_themes, Theme, THEME_MANIFEST, THEME_PUBLIC_DIR, THEME_TEMPLATE_DIR};
+
+use crate::decompress::decompress_files;
+use crate::{ThemeArgs, ThemeCommand};
+
+const TEXTED_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub fn theme_cmd(args: ThemeArgs) {
+    let res = match args.command {
+        ThemeCommand::Install { source, force } => {
+            get_themes_dir(&args.config, args.themes_dir.as_deref())
+                .and_then(|themes_dir| install_theme(source.as_deref(), &themes_dir, force))
+                .map(|theme| println!("Theme {} {} installed in {}", theme.manifest.name, theme.manifest.version, theme.dir.to_str().unwrap()))
+        }
+        ThemeCommand::List => {
+            get_themes_dir(&args.config, args.themes_dir.as_deref())
+                .and_then(|themes_dir| print_themes(&args.config, &themes_dir))
+        }
+        ThemeCommand::Switch { name } => {
+            get_themes_dir(&args.config, args.themes_dir.as_deref())
+                .and_then(|themes_dir| switch_theme(&args.config, &themes_dir, &name, args.themes_dir.is_some()))
+                .map(|_| println!("Theme switched to {}. Restart texted to apply it", name))
+        }
+    };
+
+    if let Err(e) = res {
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+}
+
+/// themes_dir argument or, if not set, themes_dir of the configuration
+fn get_themes_dir(cfg_path: &PathBuf, themes_dir: Option<&Path>) -> io::Result<PathBuf> {
+    if let Some(themes_dir) = themes_dir {
+        return Ok(themes_dir.to_path_buf());
+    }
+    match read_config(cfg_path)?.paths.themes_dir {
+        Some(themes_dir) => Ok(themes_dir),
+        None => Err(io::Error::new(ErrorKind::InvalidInput, format!("themes_dir is not set in [paths] of {}. Please use --themes-dir", cfg_path.to_str().unwrap()))),
+    }
+}
+
+/// Installs the theme of a directory or a .tar.gz archive. Without source, the built-in theme is installed
+fn install_theme(source: Option<&Path>, themes_dir: &Path, force: bool) -> io::Result<Theme> {
+    fs::create_dir_all(themes_dir)?;
+    let source = match source {
+        Some(source) if source.is_dir() => return install_from_dir(source, themes_dir, force),
+        Some(source) => Some(source),
+        None => None,
+    };
+
+    // Archives are unpacked to a temporary directory inside themes_dir
+    let unpack_dir = themes_dir.join(format!(".install-{}", std::process::id()));
+    let res = unpack_theme(source, &unpack_dir)
+        .and_then(|theme_dir| install_from_dir(&theme_dir, themes_dir, force));
+    let _ = fs::remove_dir_all(&unpack_dir);
+    res
+}
+
+/// Unpacks the archive, returning the directory of the theme manifest
+fn unpack_theme(archive: Option<&Path>, unpack_dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(unpack_dir)?;
+    match archive {
+        Some(archive) => {
+            let file = File::open(archive)
+                .map_err(|e| io::Error::new(e.kind(), format!("Error opening theme {}: {}", archive.to_str().unwrap(), e)))?;
+            Archive::new(GzDecoder::new(file)).unpack(unpack_dir)?;
+        }
+        None => decompress_files(&unpack_dir.to_path_buf())?,
+    }
+
+    if unpack_dir.join(THEME_MANIFEST).is_file() {
+        return Ok(unpack_dir.to_path_buf());
+    }
+    // Archives usually have a single directory with the theme name
+    for entry in fs::read_dir(unpack_dir)? {
+        let path = entry?.path();
+        if path.join(THEME_MANIFEST).is_file() {
+            return Ok(path);
+        }
+    }
+    Err(io::Error::new(ErrorKind::NotFound, format!("{} not found in theme archive", THEME_MANIFEST)))
+}
+
+/// Copies the manifest, templates and public files of the theme to themes_dir/name
+fn install_from_dir(source: &Path, themes_dir: &Path, force: bool) -> io::Result<Theme> {
+    let theme = Theme::from_dir(source)?;
+    theme.check_version(TEXTED_VERSION)?;
+
+    let name = &theme.manifest.name;
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("Invalid theme name: {}", name)));
+    }
+    if !theme.template_dir().is_dir() {
+        return Err(io::Error::new(ErrorKind::NotFound, format!("Theme {} has no {} directory", name, THEME_TEMPLATE_DIR)));
+    }
+
+    let target_dir = themes_dir.join(name);
+    if target_dir.exists() {
+        if !force {
+            return Err(io::Error::new(ErrorKind::AlreadyExists, format!("Theme {} is already installed. Use --force to replace it", name)));
+        }
+        fs::remove_dir_all(&target_dir)?;
+    }
+
+    fs::create_dir_all(&target_dir)?;
+    fs::copy(source.join(THEME_MANIFEST), target_dir.join(THEME_MANIFEST))?;
+    copy_dir(&theme.template_dir(), &target_dir.join(THEME_TEMPLATE_DIR))?;
+    if theme.public_dir().is_dir() {
+        copy_dir(&theme.public_dir(), &target_dir.join(THEME_PUBLIC_DIR))?;
+    }
+
+    Theme::from_dir(&target_dir)
+}
+
+fn copy_dir(source: &Path, target: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let path = entry?.path();
+        let target_path = target.join(path.file_name().unwrap());
+        if path.is_dir() {
+            copy_dir(&path, &target_path)?;
+        } else {
+            fs::copy(&path, &target_path)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_themes(cfg_path: &PathBuf, themes_dir: &Path) -> io::Result<()> {
+    let current = read_config(cfg_path).ok()
+        .and_then(|cfg| cfg.theme)
+        .map(|theme| theme.name);
+
+    let themes = list_themes(themes_dir)?;
+    if themes.is_empty() {
+        println!("No themes installed in {}", themes_dir.to_str().unwrap());
+    }
+    for theme in themes {
+        let manifest = &theme.manifest;
+        let marker = if current.as_ref() == Some(&manifest.name) { "*" } else { " " };
+        let compatible = match theme.check_version(TEXTED_VERSION) {
+            Ok(_) => "".to_string(),
+            Err(_) => format!(" (requires texted {})", manifest.texted_version),
+        };
+        println!("{} {} {} - {}{}", marker, manifest.name, manifest.version, manifest.description, compatible);
+    }
+    Ok(())
+}
+
+fn switch_theme(cfg_path: &PathBuf, themes_dir: &Path, name: &str, set_themes_dir: bool) -> io::Result<()> {
+    Theme::load(themes_dir, name)?;
+
+    let cfg_content = fs::read_to_string(cfg_path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Error opening configuration file {}: {}", cfg_path.to_str().unwrap(), e)))?;
+    let themes_dir = if set_themes_dir { Some(themes_dir) } else { None };
+    let cfg_content = set_theme(&cfg_content, name, themes_dir)?;
+    fs::write(cfg_path, cfg_content)
+}
+
+/// Sets the theme name in the configuration, keeping its comments and formatting
+fn set_theme(cfg_content: &str, name: &str, themes_dir: Option<&Path>) -> io::Result<String> {
+    let mut doc = match cfg_content.parse::<DocumentMut>() {
+        Ok(doc) => doc,
+        Err(e) => return Err(io::Error::new(ErrorKind::InvalidData, format!("Error parsing configuration file: {}", e))),
+    };
+
+    if !doc.contains_key("theme") {
+        doc.insert("theme", Item::Table(Table::new()));
+    }
+    doc["theme"]["name"] = value(name);
+    if let Some(themes_dir) = themes_dir {
+        doc["paths"]["themes_dir"] = value(themes_dir.to_str().unwrap());
+    }
+    Ok(doc.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::temp_dir;
+
+    const MANIFEST: &str = r#"
+name = "simple"
+version = "1.0.0"
+texted_version = "1.0"
+"#;
+
+    #[test]
+    fn test_install_from_dir() {
+        let dir = temp_dir("theme-install");
+        let source = dir.join("source");
+        fs::create_dir_all(source.join("template/layouts")).unwrap();
+        fs::create_dir_all(source.join("posts")).unwrap();
+        fs::write(source.join(THEME_MANIFEST), MANIFEST).unwrap();
+        fs::write(source.join("template/layouts/base.tpl"), "{{> content}}").unwrap();
+        let themes_dir = dir.join("themes");
+
+        let theme = install_theme(Some(&source), &themes_dir, false).unwrap();
+        assert_eq!(theme.dir, themes_dir.join("simple"));
+        assert!(themes_dir.join("simple/template/layouts/base.tpl").is_file());
+        assert!(!themes_dir.join("simple/posts").exists());
+
+        let err = install_theme(Some(&source), &themes_dir, false).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert!(install_theme(Some(&source), &themes_dir, true).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_install_builtin() {
+        let dir = temp_dir("theme-builtin");
+        let theme = install_theme(None, &dir, false).unwrap();
+        assert_eq!(theme.manifest.name, "default");
+        assert!(theme.template_dir().join("view.tpl").is_file());
+        assert!(theme.public_dir().is_dir());
+        assert_eq!(list_themes(&dir).unwrap().len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_set_theme() {
+        let cfg = "# Paths\n[paths]\ntemplate_dir = \"res/template\"\n\n[server]\nport = 8001\n";
+        let res = set_theme(cfg, "simple", None).unwrap();
+        assert!(res.starts_with(cfg));
+        assert!(res.ends_with("[theme]\nname = \"simple\"\n"), "{}", res);
+
+        let res = set_theme(&res, "other", Some(Path::new("/blog/themes"))).unwrap();
+        assert!(res.contains("themes_dir = \"/blog/themes\""));
+        assert!(res.contains("name = \"other\""));
+        assert!(!res.contains("simple"));
+    }
+}
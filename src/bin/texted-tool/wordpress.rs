@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use chrono::NaiveDateTime;
+use lazy_static::lazy_static;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex::{Captures, Regex};
+
+use crate::html_to_markdown::html_to_markdown;
+use crate::import::{import_slug, write_post, BodyFormat, ImportReport, ImportTarget, ImportedPost, PostKind};
+
+lazy_static! {
+    static ref UPLOAD_URL_REGEX: Regex = Regex::new(r#"(?:https?:)?//[^"'\s()<>]+/wp-content/uploads/([^"'\s()<>?#]+)"#).unwrap();
+    static ref CAPTION_REGEX: Regex = Regex::new(r"(?s)\[caption[^\]]*\](.*?)\[/caption\]").unwrap();
+    static ref CAPTION_MEDIA_REGEX: Regex = Regex::new(r"(?s)^\s*((?:<a[^>]*>)?\s*<img[^>]*>\s*(?:</a>)?)(.*)$").unwrap();
+    static ref HOST_REGEX: Regex = Regex::new(r"^https?://[^/]+").unwrap();
+}
+
+/// Item of the WordPress export: a post, page, attachment, menu item...
+#[derive(Debug, Default)]
+struct WpItem {
+    title: String,
+    link: String,
+    creator: String,
+    content: String,
+    post_id: String,
+    post_date: String,
+    post_name: String,
+    status: String,
+    post_type: String,
+    /// Names of the categories and tags
+    terms: Vec<String>,
+}
+
+/// Export of a WordPress site (WXR), from Tools > Export in the admin
+#[derive(Debug, Default)]
+struct WpExport {
+    /// Display name by login
+    authors: HashMap<String, String>,
+    items: Vec<WpItem>,
+}
+
+/// Imports the published posts and pages. Media under wp-content/uploads is copied from uploads_dir, if found there
+pub fn import_wordpress(export_file: &Path, uploads_dir: Option<&Path>, keep_html: bool, target: &ImportTarget) -> io::Result<ImportReport> {
+    let xml = fs::read_to_string(export_file)
+        .map_err(|e| io::Error::new(e.kind(), format!("Error opening {}: {}", export_file.to_str().unwrap(), e)))?;
+    let export = parse_export(&xml)?;
+
+    let mut report = ImportReport::default();
+    for item in &export.items {
+        let kind = match item.post_type.as_str() {
+            "post" => PostKind::Post,
+            "page" => PostKind::Page,
+            // Attachments, menu items, revisions...
+            _ => continue,
+        };
+        if item.status != "publish" {
+            report.warnings.push(format!("Skipped {} \"{}\" with status {}", item.post_type, item.title, item.status));
+            continue;
+        }
+
+        let post = match to_post(item, kind, &export.authors, uploads_dir, keep_html, &mut report.warnings) {
+            Ok(post) => post,
+            Err(e) => {
+                report.warnings.push(format!("Skipped {} \"{}\": {}", item.post_type, item.title, e));
+                continue;
+            }
+        };
+        match write_post(&post, target) {
+            Ok(post_file) => {
+                report.redirects.extend(post.old_urls.iter().map(|old_url| (old_url.clone(), post.url())));
+                report.imported.push(post_file);
+            }
+            Err(e) => report.warnings.push(format!("Skipped {} \"{}\": {}", item.post_type, item.title, e)),
+        }
+    }
+    Ok(report)
+}
+
+fn to_post(item: &WpItem, kind: PostKind, authors: &HashMap<String, String>, uploads_dir: Option<&Path>, keep_html: bool,
+           warnings: &mut Vec<String>) -> io::Result<ImportedPost> {
+    let date = NaiveDateTime::parse_from_str(&item.post_date, "%Y-%m-%d %H:%M:%S")
+        .map_err(|_| io::Error::new(ErrorKind::InvalidData, format!("Invalid date {}", item.post_date)))?;
+    // Non ascii names are percent encoded
+    let name = if item.post_name.is_empty() || item.post_name.contains('%') { &item.title } else { &item.post_name };
+    let slug = import_slug(kind, name, &date);
+
+    let (html, assets) = map_media(&captions_to_figures(&item.content), uploads_dir, &item.title, warnings);
+    let (body, format) = match keep_html {
+        true => (wpautop(&html), BodyFormat::Html),
+        false => (html_to_markdown(&html), BodyFormat::Markdown),
+    };
+
+    let mut old_urls = vec![];
+    if let Some(path) = url_path(&item.link) {
+        old_urls.push(path);
+    }
+    if !item.post_id.is_empty() {
+        old_urls.push(format!("/?p={}", item.post_id));
+    }
+
+    Ok(ImportedPost {
+        kind,
+        slug,
+        title: item.title.trim().to_string(),
+        date,
+        authors: vec![authors.get(&item.creator).unwrap_or(&item.creator).clone()],
+        tags: item.terms.iter().filter(|term| term.to_lowercase() != "uncategorized").cloned().collect(),
+        body,
+        format,
+        assets,
+        old_urls,
+    })
+}
+
+fn parse_export(xml: &str) -> io::Result<WpExport> {
+    let invalid = |e: quick_xml::Error| io::Error::new(ErrorKind::InvalidData, format!("Invalid WordPress export: {}", e));
+    let mut reader = Reader::from_str(xml);
+    let mut export = WpExport::default();
+    let mut item: Option<WpItem> = None;
+    let mut author: Option<(String, String)> = None;
+    let mut text = String::new();
+    let mut term_domain = String::new();
+
+    loop {
+        match reader.read_event().map_err(invalid)? {
+            Event::Start(e) => {
+                text.clear();
+                match e.name().as_ref() {
+                    b"item" => item = Some(WpItem::default()),
+                    b"wp:author" => author = Some((String::new(), String::new())),
+                    b"category" => term_domain = attribute(&e, "domain"),
+                    _ => {}
+                }
+            }
+            Event::Text(e) => text.push_str(&e.unescape().map_err(invalid)?),
+            Event::CData(e) => text.push_str(&e.decode().map_err(|e| invalid(e.into()))?),
+            Event::End(e) => {
+                let value = std::mem::take(&mut text);
+                let name = e.name();
+                if let Some(ref mut item) = item {
+                    match name.as_ref() {
+                        b"title" => item.title = value,
+                        b"link" => item.link = value,
+                        b"dc:creator" => item.creator = value,
+                        b"content:encoded" => item.content = value,
+                        b"wp:post_id" => item.post_id = value,
+                        b"wp:post_date" => item.post_date = value,
+                        b"wp:post_name" => item.post_name = value,
+                        b"wp:status" => item.status = value,
+                        b"wp:post_type" => item.post_type = value,
+                        b"category" if (term_domain == "category" || term_domain == "post_tag") && !item.terms.contains(&value) => {
+                            item.terms.push(value);
+                        }
+                        _ => {}
+                    }
+                } else if let Some(ref mut author) = author {
+                    match name.as_ref() {
+                        b"wp:author_login" => author.0 = value,
+                        b"wp:author_display_name" => author.1 = value,
+                        _ => {}
+                    }
+                }
+                match name.as_ref() {
+                    b"item" => export.items.extend(item.take()),
+                    b"wp:author" => {
+                        if let Some((login, display_name)) = author.take() {
+                            export.authors.insert(login, display_name);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(export)
+}
+
+fn attribute(element: &BytesStart, name: &str) -> String {
+    match element.try_get_attribute(name) {
+        Ok(Some(attr)) => attr.unescape_value().map(|v| v.to_string()).unwrap_or_default(),
+        _ => "".to_string(),
+    }
+}
+
+/// `[caption]<img/> Text[/caption]` of the classic editor becomes a figure with caption
+fn captions_to_figures(html: &str) -> String {
+    CAPTION_REGEX.replace_all(html, |caps: &Captures| {
+        match CAPTION_MEDIA_REGEX.captures(&caps[1]) {
+            Some(media) => format!("<figure>{}<figcaption>{}</figcaption></figure>", &media[1], media[2].trim()),
+            None => caps[1].to_string(),
+        }
+    }).to_string()
+}
+
+/// Replaces the urls of the uploads found in uploads_dir with their file name, returning the files to copy
+fn map_media(html: &str, uploads_dir: Option<&Path>, title: &str, warnings: &mut Vec<String>) -> (String, Vec<(PathBuf, String)>) {
+    let mut assets: Vec<(PathBuf, String)> = vec![];
+    let mut missing = 0;
+    let html = UPLOAD_URL_REGEX.replace_all(html, |caps: &Captures| {
+        let source = match uploads_dir {
+            Some(uploads_dir) => uploads_dir.join(&caps[1]),
+            None => return caps[0].to_string(),
+        };
+        if !source.is_file() {
+            missing += 1;
+            return caps[0].to_string();
+        }
+        if let Some((_, file_name)) = assets.iter().find(|(path, _)| *path == source) {
+            return file_name.clone();
+        }
+        // Uploads of different months can have the same name
+        let base_name = source.file_name().unwrap().to_str().unwrap().to_string();
+        let file_name = match assets.iter().any(|(_, name)| *name == base_name) {
+            true => caps[1].replace('/', "_"),
+            false => base_name,
+        };
+        assets.push((source, file_name.clone()));
+        file_name
+    }).to_string();
+
+    if missing > 0 {
+        warnings.push(format!("{} media files of \"{}\" not found in the uploads, keeping their urls", missing, title));
+    }
+    (html, assets)
+}
+
+/// Paragraphs of the classic editor, separated by empty lines, as `<p>`, like WordPress renders them
+fn wpautop(html: &str) -> String {
+    if html.contains("<p>") || html.contains("<!-- wp:") {
+        return html.to_string();
+    }
+    html.split("\n\n")
+        .map(|block| block.trim())
+        .filter(|block| !block.is_empty())
+        .map(|block| match block.starts_with('<') && !block.starts_with("<a ") && !block.starts_with("<strong") && !block.starts_with("<em") {
+            true => block.to_string(),
+            false => format!("<p>{}</p>", block.replace('\n', "<br />\n")),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Path of the permalink, e.g. /2019/05/hello-world/ for https://blog.example.com/2019/05/hello-world/
+fn url_path(link: &str) -> Option<String> {
+    let path = HOST_REGEX.replace(link.trim(), "").to_string();
+    match path.as_str() {
+        "" | "/" => None,
+        path => Some(path.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::temp_dir;
+
+    const EXPORT: &str = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:wp="http://wordpress.org/export/1.2/">
+<channel>
+    <title>Old blog</title>
+    <wp:author><wp:author_login><![CDATA[thiago]]></wp:author_login><wp:author_display_name><![CDATA[Thiago Silva]]></wp:author_display_name></wp:author>
+    <item>
+        <title>Hello &amp; welcome</title>
+        <link>https://blog.example.com/2019/05/hello-world/</link>
+        <dc:creator><![CDATA[thiago]]></dc:creator>
+        <content:encoded><![CDATA[First <strong>post</strong>
+
+[caption id="attachment_5" align="aligncenter" width="300"]<img src="https://blog.example.com/wp-content/uploads/2019/05/dino.png" alt="Dino" /> A dino[/caption]
+
+<img src="https://blog.example.com/wp-content/uploads/2019/05/missing.png" alt="Missing" />]]></content:encoded>
+        <wp:post_id>12</wp:post_id>
+        <wp:post_date><![CDATA[2019-05-01 10:30:00]]></wp:post_date>
+        <wp:post_name><![CDATA[hello-world]]></wp:post_name>
+        <wp:status><![CDATA[publish]]></wp:status>
+        <wp:post_type><![CDATA[post]]></wp:post_type>
+        <category domain="category" nicename="uncategorized"><![CDATA[Uncategorized]]></category>
+        <category domain="category" nicename="rust"><![CDATA[Rust]]></category>
+        <category domain="post_tag" nicename="web"><![CDATA[web]]></category>
+    </item>
+    <item>
+        <title>About</title>
+        <link>https://blog.example.com/about/</link>
+        <dc:creator><![CDATA[thiago]]></dc:creator>
+        <content:encoded><![CDATA[<!-- wp:paragraph --><p>About me</p><!-- /wp:paragraph -->]]></content:encoded>
+        <wp:post_id>2</wp:post_id>
+        <wp:post_date><![CDATA[2019-04-01 08:00:00]]></wp:post_date>
+        <wp:post_name><![CDATA[about]]></wp:post_name>
+        <wp:status><![CDATA[publish]]></wp:status>
+        <wp:post_type><![CDATA[page]]></wp:post_type>
+    </item>
+    <item>
+        <title>Draft</title>
+        <wp:post_date><![CDATA[0000-00-00 00:00:00]]></wp:post_date>
+        <wp:status><![CDATA[draft]]></wp:status>
+        <wp:post_type><![CDATA[post]]></wp:post_type>
+    </item>
+    <item>
+        <title>dino</title>
+        <wp:status><![CDATA[inherit]]></wp:status>
+        <wp:post_type><![CDATA[attachment]]></wp:post_type>
+    </item>
+</channel>
+</rss>"#;
+
+    fn create_dir() -> PathBuf {
+        let dir = temp_dir("wordpress");
+        fs::create_dir_all(dir.join("uploads/2019/05")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_export() {
+        let export = parse_export(EXPORT).unwrap();
+        assert_eq!(export.authors.get("thiago").unwrap(), "Thiago Silva");
+        assert_eq!(export.items.len(), 4);
+        let post = &export.items[0];
+        assert_eq!(post.title, "Hello & welcome");
+        assert_eq!(post.post_date, "2019-05-01 10:30:00");
+        assert_eq!(post.terms, vec!["Uncategorized", "Rust", "web"]);
+        assert!(post.content.starts_with("First <strong>post</strong>"));
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = create_dir();
+        fs::write(dir.join("uploads/2019/05/dino.png"), "png").unwrap();
+        fs::write(dir.join("export.xml"), EXPORT).unwrap();
+        let target = ImportTarget { posts_dir: dir.join("posts"), pages_dir: dir.join("pages"), index_base_name: "index".to_string() };
+
+        let report = import_wordpress(&dir.join("export.xml"), Some(&dir.join("uploads")), false, &target).unwrap();
+        assert_eq!(report.imported, vec![dir.join("posts/20190501_hello_world/index.md"), dir.join("pages/about/index.md")]);
+        assert_eq!(report.warnings.len(), 2, "{:?}", report.warnings);
+        assert_eq!(report.redirects, vec![
+            ("/2019/05/hello-world/".to_string(), "/view/20190501_hello_world/".to_string()),
+            ("/?p=12".to_string(), "/view/20190501_hello_world/".to_string()),
+            ("/about/".to_string(), "/page/about/".to_string()),
+            ("/?p=2".to_string(), "/page/about/".to_string()),
+        ]);
+
+        let post = fs::read_to_string(dir.join("posts/20190501_hello_world/index.md")).unwrap();
+        assert!(post.contains("[AUTHOR]: # (Thiago Silva)\n[TAGS]: # (Rust web)\n"), "{}", post);
+        assert!(post.ends_with("# Hello & welcome\n\nFirst **post**\n\n![Dino](dino.png)\n\n*A dino*\n\n\
+![Missing](https://blog.example.com/wp-content/uploads/2019/05/missing.png)\n"), "{}", post);
+        assert!(dir.join("posts/20190501_hello_world/dino.png").is_file());
+        assert!(fs::read_to_string(dir.join("pages/about/index.md")).unwrap().ends_with("# About\n\nAbout me\n"));
+
+        // Existing posts are not replaced
+        let report = import_wordpress(&dir.join("export.xml"), None, true, &target).unwrap();
+        assert!(report.imported.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_wpautop() {
+        assert_eq!(wpautop("One\nline\n\n<h2>Title</h2>\n\n<strong>Two</strong>"), "<p>One<br />\nline</p>\n<h2>Title</h2>\n<p><strong>Two</strong></p>");
+        assert_eq!(wpautop("<p>Gutenberg</p>"), "<p>Gutenberg</p>");
+    }
+}
@@ -0,0 +1,193 @@
+use std::io;
+use std::io::ErrorKind;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use ntex::http::HeaderMap;
+
+/// Network of the trusted proxies, e.g. 10.0.0.0/8. An address alone is a network of one address
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl FromStr for Cidr {
+    type Err = io::Error;
+
+    fn from_str(cidr: &str) -> Result<Self, Self::Err> {
+        let invalid = || io::Error::new(ErrorKind::InvalidInput, format!("Invalid trusted proxy {}, expected e.g. 10.0.0.0/8", cidr));
+        let (address, prefix) = match cidr.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (cidr.trim(), None),
+        };
+        let network = address.parse::<IpAddr>().map_err(|_| invalid())?.to_canonical();
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().ok().filter(|prefix| *prefix <= max_prefix).ok_or_else(invalid)?,
+            None => max_prefix,
+        };
+        Ok(Cidr { network, prefix })
+    }
+}
+
+impl Cidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Finds the address of the client of a request. The forwarding headers are only read when the request comes
+/// from a trusted proxy, so clients cannot choose their address
+#[derive(Default)]
+pub struct ClientAddressResolver {
+    trusted_proxies: Vec<Cidr>,
+}
+
+impl ClientAddressResolver {
+    pub fn new(trusted_proxies: &[String]) -> io::Result<Self> {
+        let trusted_proxies = trusted_proxies.iter().map(|cidr| cidr.parse()).collect::<io::Result<Vec<Cidr>>>()?;
+        Ok(ClientAddressResolver { trusted_proxies })
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    /// Address of the client connected to peer. The chain of `Forwarded`, or else `X-Forwarded-For` or `X-Real-IP`,
+    /// is walked from the right while the addresses are trusted proxies. The first address that is not a trusted
+    /// proxy is the client
+    pub fn resolve(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        let mut client = peer?.to_canonical();
+        if !self.is_trusted(client) {
+            return Some(client);
+        }
+
+        for node in forwarding_chain(headers).iter().rev() {
+            // A proxy can hide the address, e.g. for=unknown. The proxy is then the client known
+            let Some(ip) = parse_node(node) else {
+                break;
+            };
+            client = ip;
+            if !self.is_trusted(client) {
+                break;
+            }
+        }
+        Some(client)
+    }
+}
+
+/// Addresses added by the proxies, from the client to the last proxy
+fn forwarding_chain(headers: &HeaderMap) -> Vec<String> {
+    let values = |name: &str| -> Vec<String> {
+        headers.get_all(name)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect()
+    };
+
+    let forwarded: Vec<String> = values("forwarded").iter()
+        .map(|element| element.split(';')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+            .map_or(String::new(), |(_, node)| node.trim().trim_matches('"').to_string()))
+        .collect();
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+    let forwarded_for = values("x-forwarded-for");
+    if !forwarded_for.is_empty() {
+        return forwarded_for;
+    }
+    values("x-real-ip")
+}
+
+/// Address of a node, e.g. 192.0.2.60, 192.0.2.60:4711, [2001:db8::17] or [2001:db8::17]:4711
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let ip = node.parse::<IpAddr>().ok()
+        .or_else(|| node.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| node.strip_prefix('[')?.strip_suffix(']')?.parse::<IpAddr>().ok())?;
+    Some(ip.to_canonical())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ntex::http::header::{HeaderName, HeaderValue};
+
+    fn headers(values: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in values {
+            headers.append(HeaderName::from_static(name), HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn test_cidr() {
+        let cidr: Cidr = "10.0.0.0/8".parse().unwrap();
+        assert!(cidr.contains("10.1.2.3".parse().unwrap()));
+        assert!(cidr.contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+        assert!(!cidr.contains("::1".parse().unwrap()));
+
+        let cidr: Cidr = "2001:db8::/32".parse().unwrap();
+        assert!(cidr.contains("2001:db8:1::1".parse().unwrap()));
+        assert!(!cidr.contains("2001:db9::1".parse().unwrap()));
+        assert!("127.0.0.1".parse::<Cidr>().unwrap().contains("127.0.0.1".parse().unwrap()));
+        assert!("0.0.0.0/0".parse::<Cidr>().unwrap().contains("8.8.8.8".parse().unwrap()));
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("proxy.local".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let resolver = ClientAddressResolver::new(&["127.0.0.1".to_string(), "10.0.0.0/8".to_string()]).unwrap();
+
+        // Untrusted peers cannot choose their address
+        let spoofed = headers(&[("x-forwarded-for", "1.2.3.4")]);
+        assert_eq!(resolver.resolve(ip("203.0.113.9"), &spoofed), ip("203.0.113.9"));
+        assert_eq!(resolver.resolve(None, &spoofed), None);
+
+        // The address added by the client itself is skipped
+        let chain = headers(&[("x-forwarded-for", "1.2.3.4, 198.51.100.7"), ("x-forwarded-for", "10.0.0.2")]);
+        assert_eq!(resolver.resolve(ip("127.0.0.1"), &chain), ip("198.51.100.7"));
+
+        // Only trusted proxies in the chain: the leftmost address
+        let chain = headers(&[("x-forwarded-for", "10.0.0.3, 10.0.0.2")]);
+        assert_eq!(resolver.resolve(ip("127.0.0.1"), &chain), ip("10.0.0.3"));
+
+        let forwarded = headers(&[
+            ("forwarded", "for=192.0.2.60;proto=http, for=\"[2001:db8:cafe::17]:4711\";by=10.0.0.1"),
+            ("x-forwarded-for", "1.2.3.4"),
+        ]);
+        assert_eq!(resolver.resolve(ip("10.0.0.1"), &forwarded), ip("2001:db8:cafe::17"));
+
+        let hidden = headers(&[("forwarded", "for=192.0.2.60, for=unknown")]);
+        assert_eq!(resolver.resolve(ip("10.0.0.1"), &hidden), ip("10.0.0.1"));
+
+        let real_ip = headers(&[("x-real-ip", "198.51.100.7")]);
+        assert_eq!(resolver.resolve(ip("::ffff:127.0.0.1"), &real_ip), ip("198.51.100.7"));
+        assert_eq!(resolver.resolve(ip("127.0.0.1"), &HeaderMap::new()), ip("127.0.0.1"));
+
+        let resolver = ClientAddressResolver::default();
+        assert_eq!(resolver.resolve(ip("127.0.0.1"), &real_ip), ip("127.0.0.1"));
+        assert!(ClientAddressResolver::new(&["10.0.0.0/x".to_string()]).is_err());
+    }
+}
@@ -4,6 +4,7 @@ use std::{env, fs, io};
 
 use serde::Deserialize;
 
+use crate::content::markdown_extensions::MarkdownOptions;
 use crate::util::toml_date::TomlDate;
 
 #[derive(Deserialize)]
@@ -12,6 +13,9 @@ pub struct Paths {
     pub public_dir: PathBuf,
     pub posts_dir: PathBuf,
     pub pages_dir: PathBuf,
+    pub authors_file: Option<PathBuf>,
+    /// Directory where the themes are installed
+    pub themes_dir: Option<PathBuf>,
 }
 
 #[derive(Deserialize)]
@@ -28,12 +32,25 @@ pub struct Defaults {
     pub summary_line_tag: Option<String>,
     pub page_size: u32,
     pub rendering_cache_enabled: bool,
+    pub related_post_count: Option<usize>,
+    pub related_text_similarity: Option<bool>,
+    pub recent_post_count: Option<usize>,
+    /// Number of posts in site.popular_posts and site.popular_posts_week. Default is 5
+    pub popular_post_count: Option<usize>,
 }
 
 #[derive(Deserialize)]
 pub struct Server {
     pub address: String,
     pub port: u16,
+    /// Serves the Prometheus metrics at /metrics, with the blog
+    #[serde(default)]
+    pub metrics_endpoint: bool,
+    /// Serves /metrics on this address instead, e.g. 127.0.0.1:9100. Enables the endpoint
+    pub metrics_address: Option<String>,
+    /// Proxies whose forwarding headers are used to find the client address, e.g. ["127.0.0.1", "10.0.0.0/8"]
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -57,6 +74,32 @@ pub enum LogLevel {
 pub struct Metrics {
     pub location: Option<PathBuf>,
     pub time_slot_secs: Option<i64>,
+    /// File keeping the view counters of the posts. Default is view_counts.json in the directory of location
+    pub view_counts_file: Option<PathBuf>,
+    /// How the visitor addresses are anonymized before being aggregated. Default is none
+    #[serde(default)]
+    pub anonymize_origins: OriginAnonymization,
+    /// Bits kept of the IPv4 addresses when truncating them. Default is 24
+    pub ipv4_prefix: Option<u8>,
+    /// Bits kept of the IPv6 addresses when truncating them. Default is 48
+    pub ipv6_prefix: Option<u8>,
+    /// Writes the origins of each time slot in the metric files. Default is true
+    pub store_origins: Option<bool>,
+    /// Metric files older than this number of days are deleted. Default keeps all the files
+    pub retention_days: Option<u32>,
+}
+
+#[derive(Deserialize, Default, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OriginAnonymization {
+    /// Addresses are kept as received
+    #[default]
+    None,
+    /// Only the network prefix of the addresses is kept, e.g. 203.0.113.0/24
+    Truncate,
+    /// Addresses are replaced by a salted hash. The salt is random and replaced every day, only the salt of the
+    /// current day is kept with the metric files
+    Hash,
 }
 
 #[derive(Deserialize)]
@@ -67,6 +110,12 @@ pub struct RssFeed {
     pub page_size: u32,
 }
 
+#[derive(Deserialize)]
+pub struct ThemeConfig {
+    /// Installed theme used for the templates and public files not found locally
+    pub name: String,
+}
+
 #[derive(Deserialize)]
 pub struct Config {
     pub personal: Personal,
@@ -76,6 +125,13 @@ pub struct Config {
     pub log: Option<Log>,
     pub metrics: Option<Metrics>,
     pub rss_feed: Option<RssFeed>,
+    pub theme: Option<ThemeConfig>,
+    /// Markdown extensions of all the posts. Only the footnotes are enabled by default
+    #[serde(default)]
+    pub markdown: MarkdownOptions,
+    /// Values available in the templates as {{site.*}}
+    #[serde(default)]
+    pub site: toml::Table,
 }
 
 fn parse_path(path: PathBuf) -> PathBuf {
@@ -106,6 +162,8 @@ pub fn read_config(cfg_path: &PathBuf) -> io::Result<Config> {
         public_dir: parse_path(cfg.paths.public_dir),
         posts_dir: parse_path(cfg.paths.posts_dir),
         pages_dir: parse_path(cfg.paths.pages_dir),
+        authors_file: cfg.paths.authors_file.map(parse_path),
+        themes_dir: cfg.paths.themes_dir.map(parse_path),
     };
 
     Ok(cfg)
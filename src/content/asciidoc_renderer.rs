@@ -0,0 +1,459 @@
+use std::collections::HashMap;
+use std::io;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::content::content_file::ContentFile;
+use crate::content::content_renderer::{ContentRenderer, RenderOptions};
+use crate::content::parsing_utils::{count_words_html, escape_html, extract_content, header_from_attributes, image_src, link_urls, parse_texted_header, reading_time, remaining_text};
+use crate::content::Content;
+
+lazy_static! {
+    static ref ATTRIBUTE_REGEX: Regex = Regex::new(r"^:([\w-]+):\s*(.*)$").unwrap();
+    static ref DATE_REGEX: Regex = Regex::new(r"\d{4}-\d{2}-\d{2}( \d{2}:\d{2}:\d{2})?").unwrap();
+    static ref EMAIL_REGEX: Regex = Regex::new(r"\s*<[^>]*>").unwrap();
+    static ref HEADING_REGEX: Regex = Regex::new(r"^(={1,6}) (.+)$").unwrap();
+    static ref LIST_REGEX: Regex = Regex::new(r"^(\*{1,5}|-|\.{1,5}|\d+\.) (.+)$").unwrap();
+    static ref ADMONITION_REGEX: Regex = Regex::new(r"^(NOTE|TIP|IMPORTANT|WARNING|CAUTION): (.*)$").unwrap();
+    static ref BLOCK_IMAGE_REGEX: Regex = Regex::new(r"^image::([^\[]+)\[([^\]]*)\]$").unwrap();
+    static ref CODE_REGEX: Regex = Regex::new(r"`([^`]+)`").unwrap();
+    static ref IMAGE_REGEX: Regex = Regex::new(r"image:([^\s\[:][^\s\[]*)\[([^\]]*)\]").unwrap();
+    static ref LINK_REGEX: Regex = Regex::new(r"(?:link:([^\s\[]+)|(https?://[^\s\[]+))\[([^\]]*)\]").unwrap();
+    static ref XREF_REGEX: Regex = Regex::new(r"&lt;&lt;([\w-]+)(?:,\s*(.+?))?&gt;&gt;").unwrap();
+    static ref STRONG_REGEX: Regex = Regex::new(r"\*\*(.+?)\*\*|\B\*(\S(?:[^*]*?\S)?)\*\B").unwrap();
+    static ref EMPHASIS_REGEX: Regex = Regex::new(r"__(.+?)__|\b_(\S(?:[^_]*?\S)?)_\b").unwrap();
+}
+
+/// Renders AsciiDoc posts. The header is either the texted header in comments, e.g. `// [DATE]: # (...)`,
+/// or the document header: title, author line, revision line and attributes such as `:revdate:` and `:keywords:`
+pub struct AsciiDocRenderer {}
+
+impl ContentRenderer for AsciiDocRenderer {
+    fn render(&self, content_file: &ContentFile, render_options: RenderOptions) -> io::Result<Content> {
+        let link = content_file.link.clone();
+        let (texted_header, text) = match parse_texted_header(&content_file.file_path, content_file.raw_content.lines()) {
+            Ok((header, lines, maybe_line)) => (Some(header), remaining_text(maybe_line, lines)),
+            Err(_) => (None, content_file.raw_content.clone()),
+        };
+
+        let document = parse_document(&text);
+        let header = match texted_header {
+            Some(header) => header,
+            None => header_from_attributes(&content_file.file_path, &document.attributes)?,
+        };
+        let word_count = count_words_html(to_html(&document.body, None).lines());
+        let content = extract_content(document.body.lines(), &render_options);
+
+        let prefix: Option<&str> = match render_options {
+            RenderOptions::PreviewOnly(ref _preview_opt, ref img_prefix) => Some(img_prefix.0.as_str()),
+            RenderOptions::FullContent => None,
+        };
+
+        Ok(Content {
+            header,
+            link,
+            title: document.title,
+            rendered: to_html(&content, prefix),
+            word_count,
+            reading_time: reading_time(word_count),
+        })
+    }
+}
+
+struct Document {
+    title: String,
+    attributes: HashMap<String, String>,
+    body: String,
+}
+
+/// Splits the document header (title, author, revision and attributes) from the body
+fn parse_document(text: &str) -> Document {
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.peek() {
+        let line = line.trim();
+        if line.is_empty() || (line.starts_with("//") && line != "////") {
+            lines.next();
+        } else {
+            break;
+        }
+    }
+
+    let mut title = String::new();
+    if let Some(line) = lines.peek() {
+        if let Some(doc_title) = line.strip_prefix("= ") {
+            title = doc_title.trim().to_string();
+            lines.next();
+        }
+    }
+
+    // The header ends in the first blank line
+    let mut attributes = HashMap::new();
+    let mut header_line = 0;
+    while let Some(line) = lines.peek() {
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(caps) = ATTRIBUTE_REGEX.captures(line) {
+            attributes.insert(caps[1].to_lowercase(), caps[2].trim().to_string());
+        } else if line.starts_with("//") {
+            // Comments are allowed in the header
+        } else if title.is_empty() || header_line > 1 {
+            break;
+        } else if header_line == 0 {
+            let authors = EMAIL_REGEX.replace_all(line, "").replace(';', ",");
+            attributes.entry("author".to_string()).or_insert(authors);
+            header_line += 1;
+        } else {
+            if let Some(date) = DATE_REGEX.find(line) {
+                attributes.entry("revdate".to_string()).or_insert(date.as_str().to_string());
+            }
+            header_line += 1;
+        }
+        lines.next();
+    }
+    while lines.next_if(|line| line.trim().is_empty()).is_some() {}
+
+    Document {
+        title,
+        attributes,
+        body: lines.collect::<Vec<_>>().join("\n"),
+    }
+}
+
+/// Converts the AsciiDoc blocks to html: headings, paragraphs, lists, admonitions, images and delimited blocks
+fn to_html(src: &str, img_prefix: Option<&str>) -> String {
+    let lines: Vec<&str> = src.lines().collect();
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = vec![];
+    let mut admonition: Option<String> = None;
+    let mut lists: Vec<&str> = vec![];
+    let mut in_list_item = false;
+    let mut block_style: Option<String> = None;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_end();
+        i += 1;
+
+        if line == "////" {
+            while i < lines.len() && lines[i].trim_end() != "////" {
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+        if line.starts_with("//") || line == "+" {
+            continue;
+        }
+        if line.is_empty() {
+            flush_paragraph(&mut html, &mut paragraph, &mut admonition, img_prefix);
+            in_list_item = false;
+            continue;
+        }
+
+        if let Some(delimiter) = delimiter(line) {
+            let mut block = vec![];
+            while i < lines.len() && lines[i].trim_end() != line {
+                block.push(lines[i]);
+                i += 1;
+            }
+            i += 1;
+            flush_paragraph(&mut html, &mut paragraph, &mut admonition, img_prefix);
+            close_lists(&mut html, &mut lists);
+            let style = block_style.take().unwrap_or_default();
+            html.push_str(&delimited_block(delimiter, &style, &block.join("\n"), img_prefix));
+            continue;
+        }
+
+        if !paragraph.is_empty() {
+            paragraph.push(line);
+            continue;
+        }
+
+        if line.starts_with("[[") && line.ends_with("]]") {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            block_style = Some(line[1..line.len() - 1].to_string());
+            continue;
+        }
+
+        if let Some(caps) = LIST_REGEX.captures(line) {
+            let marker = &caps[1];
+            let (tag, depth) = match marker.chars().next() {
+                Some('*') => ("ul", marker.len()),
+                Some('-') => ("ul", 1),
+                Some('.') => ("ol", marker.len()),
+                _ => ("ol", 1),
+            };
+            list_item(&mut html, &mut lists, tag, depth);
+            html.push_str(&inline(&caps[2], img_prefix));
+            in_list_item = true;
+            continue;
+        }
+        if in_list_item {
+            html.push(' ');
+            html.push_str(&inline(line.trim(), img_prefix));
+            continue;
+        }
+        close_lists(&mut html, &mut lists);
+
+        if let Some(caps) = HEADING_REGEX.captures(line) {
+            let level = caps[1].len();
+            html.push_str(&format!("<h{}>{}</h{}>\n", level, inline(caps[2].trim(), img_prefix), level));
+        } else if line == "'''" {
+            html.push_str("<hr />\n");
+        } else if line == "<<<" {
+            // Page breaks are ignored
+        } else if let Some(caps) = BLOCK_IMAGE_REGEX.captures(line) {
+            html.push_str(&format!("<p>{}</p>\n", image_tag(&caps[1], &caps[2], img_prefix)));
+        } else if line.starts_with('.') && line.len() > 1 && !line[1..].starts_with(['.', ' ']) {
+            html.push_str(&format!("<div class=\"title\">{}</div>\n", inline(&line[1..], img_prefix)));
+        } else if let Some(caps) = ADMONITION_REGEX.captures(line) {
+            admonition = Some(caps[1].to_lowercase());
+            paragraph.push(caps.get(2).unwrap().as_str());
+        } else {
+            admonition = block_style.take().filter(|s| is_admonition(s)).map(|s| s.to_lowercase());
+            paragraph.push(line);
+        }
+        block_style = None;
+    }
+
+    flush_paragraph(&mut html, &mut paragraph, &mut admonition, img_prefix);
+    close_lists(&mut html, &mut lists);
+    html
+}
+
+/// Delimiter of a block, e.g. ---- for listings or ____ for quotes
+fn delimiter(line: &str) -> Option<char> {
+    let first = line.chars().next()?;
+    if line.len() >= 4 && "-._=*+".contains(first) && line.chars().all(|c| c == first) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+fn delimited_block(delimiter: char, style: &str, block: &str, img_prefix: Option<&str>) -> String {
+    match delimiter {
+        '-' | '.' => {
+            let mut style = style.split(',').map(|s| s.trim());
+            match (style.next(), style.next()) {
+                (Some("source"), Some(lang)) => format!("<pre><code class=\"language-{}\">{}</code></pre>\n", escape_html(lang), escape_html(block)),
+                _ => format!("<pre><code>{}</code></pre>\n", escape_html(block)),
+            }
+        }
+        '_' => format!("<blockquote>\n{}</blockquote>\n", to_html(block, img_prefix)),
+        '+' => format!("{}\n", block),
+        '*' => format!("<aside>\n{}</aside>\n", to_html(block, img_prefix)),
+        _ if is_admonition(style) => format!("<div class=\"admonition {}\">\n{}</div>\n", style.to_lowercase(), to_html(block, img_prefix)),
+        _ => format!("<div class=\"example\">\n{}</div>\n", to_html(block, img_prefix)),
+    }
+}
+
+fn is_admonition(style: &str) -> bool {
+    matches!(style, "NOTE" | "TIP" | "IMPORTANT" | "WARNING" | "CAUTION")
+}
+
+fn flush_paragraph(html: &mut String, paragraph: &mut Vec<&str>, admonition: &mut Option<String>, img_prefix: Option<&str>) {
+    if paragraph.is_empty() {
+        return;
+    }
+    let text = paragraph.iter()
+        .map(|line| match line.strip_suffix(" +") {
+            Some(line) => format!("{}<br />", inline(line, img_prefix)),
+            None => inline(line, img_prefix),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    match admonition.take() {
+        Some(kind) => html.push_str(&format!("<div class=\"admonition {}\">\n<p>{}</p>\n</div>\n", kind, text)),
+        None => html.push_str(&format!("<p>{}</p>\n", text)),
+    }
+    paragraph.clear();
+}
+
+/// Opens a list item, opening or closing the lists to reach its depth
+fn list_item(html: &mut String, lists: &mut Vec<&'static str>, tag: &'static str, depth: usize) {
+    if depth > lists.len() {
+        while lists.len() < depth {
+            html.push_str(&format!("<{}>\n", tag));
+            lists.push(tag);
+        }
+    } else {
+        while lists.len() > depth {
+            html.push_str(&format!("</li>\n</{}>\n", lists.pop().unwrap()));
+        }
+        html.push_str("</li>\n");
+        if lists.last() != Some(&tag) {
+            html.push_str(&format!("</{}>\n<{}>\n", lists.pop().unwrap(), tag));
+            lists.push(tag);
+        }
+    }
+    html.push_str("<li>");
+}
+
+fn close_lists(html: &mut String, lists: &mut Vec<&str>) {
+    while let Some(tag) = lists.pop() {
+        html.push_str(&format!("</li>\n</{}>\n", tag));
+    }
+}
+
+fn image_tag(src: &str, attributes: &str, img_prefix: Option<&str>) -> String {
+    let alt = attributes.split(',').next().unwrap_or("").trim();
+    format!("<img src=\"{}\" alt=\"{}\" />", image_src(img_prefix, src.trim()), alt)
+}
+
+/// Inline formatting. The text of code spans is not formatted
+fn inline(text: &str, img_prefix: Option<&str>) -> String {
+    let mut html = String::new();
+    let mut last = 0;
+    for caps in CODE_REGEX.captures_iter(text) {
+        let code = caps.get(0).unwrap();
+        html.push_str(&format_text(&text[last..code.start()], img_prefix));
+        html.push_str(&format!("<code>{}</code>", escape_html(&caps[1])));
+        last = code.end();
+    }
+    html.push_str(&format_text(&text[last..], img_prefix));
+    html
+}
+
+fn format_text(text: &str, img_prefix: Option<&str>) -> String {
+    let text = escape_html(text);
+    let text = IMAGE_REGEX.replace_all(&text, |caps: &Captures| image_tag(&caps[1], &caps[2], img_prefix));
+    let text = LINK_REGEX.replace_all(&text, |caps: &Captures| {
+        let href = caps.get(1).or(caps.get(2)).unwrap().as_str();
+        let label = match caps[3].trim() {
+            "" => href,
+            label => label,
+        };
+        format!("<a href=\"{}\">{}</a>", href, label)
+    });
+    let text = XREF_REGEX.replace_all(&text, |caps: &Captures| {
+        let label = caps.get(2).map_or(&caps[1], |l| l.as_str());
+        format!("<a href=\"#{}\">{}</a>", &caps[1], label)
+    });
+    let text = link_urls(&text);
+    let text = STRONG_REGEX.replace_all(&text, |caps: &Captures| {
+        format!("<strong>{}</strong>", caps.get(1).or(caps.get(2)).unwrap().as_str())
+    });
+    let text = EMPHASIS_REGEX.replace_all(&text, |caps: &Captures| {
+        format!("<em>{}</em>", caps.get(1).or(caps.get(2)).unwrap().as_str())
+    });
+    text.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::content::content_format::ContentFormat;
+    use crate::content::content_renderer::{BreakTag, ImagePrefix, MaxLineCount, PreviewOptions};
+
+    const POST_DATA_ADOC: &str = r#"= Writing in AsciiDoc
+Thiago Guedes <thiago@example.com>
+v1.0, 2024-05-01
+:keywords: asciidoc, tutorial
+
+Texted renders *AsciiDoc* posts, with `code` and _emphasis_.
+See https://asciidoc.org[AsciiDoc] for the syntax.
+
+// <!-- more -->
+
+== Lists
+
+* First
+** Nested
+* Second
+
+. One
+. Two
+
+[source,rust]
+----
+fn main() { println!("<hi>"); }
+----
+
+NOTE: Admonitions are supported.
+
+image::dino.jpg[Dino, 200]
+"#;
+
+    fn content_file(raw_content: &str) -> ContentFile {
+        ContentFile {
+            link: "asciidoc".to_string(),
+            file_path: PathBuf::from("res/posts/new_post.md"),
+            format: ContentFormat::AsciiDoc,
+            raw_content: raw_content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_document_header() {
+        let content = AsciiDocRenderer {}.render(&content_file(POST_DATA_ADOC), RenderOptions::FullContent).unwrap();
+        assert_eq!(content.title, "Writing in AsciiDoc");
+        assert_eq!(content.header.author, "Thiago Guedes");
+        assert_eq!(content.header.tags, vec!["asciidoc", "tutorial"]);
+        assert_eq!(content.header.date.to_string(), "2024-05-01 00:00:00");
+
+        let texted_header = format!("// [ID]: # (abc)\n// [DATE]: # (2024-02-12 22:54:00.000)\n// [AUTHOR]: # (joe)\n\n{}", POST_DATA_ADOC);
+        let content = AsciiDocRenderer {}.render(&content_file(&texted_header), RenderOptions::FullContent).unwrap();
+        assert_eq!(content.title, "Writing in AsciiDoc");
+        assert_eq!(content.header.id.0, "abc");
+        assert_eq!(content.header.author, "joe");
+        assert!(content.header.tags.is_empty());
+    }
+
+    #[test]
+    fn test_full_content() {
+        let content = AsciiDocRenderer {}.render(&content_file(POST_DATA_ADOC), RenderOptions::FullContent).unwrap();
+        assert_eq!(content.rendered, r#"<p>Texted renders <strong>AsciiDoc</strong> posts, with <code>code</code> and <em>emphasis</em>.
+See <a href="https://asciidoc.org">AsciiDoc</a> for the syntax.</p>
+<h2>Lists</h2>
+<ul>
+<li>First<ul>
+<li>Nested</li>
+</ul>
+</li>
+<li>Second</li>
+</ul>
+<ol>
+<li>One</li>
+<li>Two</li>
+</ol>
+<pre><code class="language-rust">fn main() { println!(&quot;&lt;hi&gt;&quot;); }</code></pre>
+<div class="admonition note">
+<p>Admonitions are supported.</p>
+</div>
+<p><img src="dino.jpg" alt="Dino" /></p>
+"#);
+        assert_eq!(content.word_count, 21);
+    }
+
+    #[test]
+    fn test_preview() {
+        let preview_opt = PreviewOptions { max_line_count: None, tag_based: BreakTag("<!-- more -->".to_string()) };
+        let options = RenderOptions::PreviewOnly(preview_opt, ImagePrefix("/view/asciidoc".to_string()));
+        let content = AsciiDocRenderer {}.render(&content_file(POST_DATA_ADOC), options).unwrap();
+        assert_eq!(content.rendered, r#"<p>Texted renders <strong>AsciiDoc</strong> posts, with <code>code</code> and <em>emphasis</em>.
+See <a href="https://asciidoc.org">AsciiDoc</a> for the syntax.</p>
+"#);
+
+        let preview_opt = PreviewOptions { max_line_count: Some(MaxLineCount(1)), tag_based: BreakTag("<!-- more -->".to_string()) };
+        let options = RenderOptions::PreviewOnly(preview_opt, ImagePrefix("/view/asciidoc".to_string()));
+        let content = AsciiDocRenderer {}.render(&content_file("= Title\n\nimage::dino.jpg[]\n\nText"), options).unwrap();
+        assert_eq!(content.rendered, "<p><img src=\"/view/asciidoc/dino.jpg\" alt=\"\" /></p>\n");
+    }
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(inline("a *b* and **c**d", None), "a <strong>b</strong> and <strong>c</strong>d");
+        assert_eq!(inline("snake_case_name and _em_", None), "snake_case_name and <em>em</em>");
+        assert_eq!(inline("`*not bold*` <<intro,Intro>>", None), "<code>*not bold*</code> <a href=\"#intro\">Intro</a>");
+        assert_eq!(inline("link:/list/rust/[Rust posts] image:icon.png[Icon]", Some("/view/post")),
+                   "<a href=\"/list/rust/\">Rust posts</a> <img src=\"/view/post/icon.png\" alt=\"Icon\" />");
+    }
+}
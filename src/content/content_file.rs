@@ -29,10 +29,7 @@ impl ContentFile {
     }
 
     fn guess_type(file_name: &Path) -> Option<ContentFormat> {
-        match file_name.to_str().unwrap() {
-            x if x.ends_with(".md") => Some(ContentFormat::Texted),
-            x if x.ends_with(".html") || x.ends_with(".htm") => Some(ContentFormat::Html),
-            _ => None,
-        }
+        let extension = file_name.extension()?.to_str()?;
+        Some(ContentFormat::from_extension(extension))
     }
 }
\ No newline at end of file
@@ -2,4 +2,22 @@
 pub enum ContentFormat {
     Texted,
     Html,
+    AsciiDoc,
+    ReStructuredText,
+    Notebook,
+    /// Format of a renderer registered by the library user, named after the file extension
+    Custom(String),
+}
+
+impl ContentFormat {
+    pub fn from_extension(extension: &str) -> Self {
+        match extension.to_lowercase().as_str() {
+            "md" => ContentFormat::Texted,
+            "html" | "htm" => ContentFormat::Html,
+            "adoc" | "asciidoc" => ContentFormat::AsciiDoc,
+            "rst" => ContentFormat::ReStructuredText,
+            "ipynb" => ContentFormat::Notebook,
+            x => ContentFormat::Custom(x.to_string()),
+        }
+    }
 }
@@ -24,6 +24,7 @@ pub enum RenderOptions {
     FullContent,
 }
 
-pub trait ContentRenderer {
-    fn render(content_file: &ContentFile, render_options: RenderOptions) -> io::Result<Content>;
+/// Renders a content file format to html. Renderers are registered by file extension in FormatRegistry
+pub trait ContentRenderer: Send + Sync {
+    fn render(&self, content_file: &ContentFile, render_options: RenderOptions) -> io::Result<Content>;
 }
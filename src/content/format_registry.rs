@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::content::asciidoc_renderer::AsciiDocRenderer;
+use crate::content::content_file::ContentFile;
+use crate::content::content_renderer::{ContentRenderer, RenderOptions};
+use crate::content::html_renderer::HtmlRenderer;
+use crate::content::markdown_extensions::MarkdownOptions;
+use crate::content::notebook_renderer::NotebookRenderer;
+use crate::content::rst_renderer::RstRenderer;
+use crate::content::shortcodes::Shortcodes;
+use crate::content::texted_renderer::TextedRenderer;
+use crate::content::wiki_links::WikiLinks;
+use crate::content::Content;
+
+/// Renderers of the content files, by file extension. E.g. `md` is rendered by TextedRenderer
+///
+/// Library users can add their own formats, or replace the default ones:
+/// ```ignore
+/// let mut formats = FormatRegistry::with_markdown(&config.markdown);
+/// formats.register("txt", Arc::new(PlainTextRenderer {}));
+/// server_run_with_formats(config, formats).await
+/// ```
+#[derive(Clone)]
+pub struct FormatRegistry {
+    renderers: HashMap<String, Arc<dyn ContentRenderer>>,
+    /// Shortcodes expanded in all the formats, e.g. `{{< youtube id >}}`
+    shortcodes: Arc<Shortcodes>,
+    /// Wiki links of the rendered content are resolved when set
+    wiki_links: Option<Arc<WikiLinks>>,
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        FormatRegistry::with_markdown(&MarkdownOptions::default())
+    }
+}
+
+impl FormatRegistry {
+    /// Markdown, html, AsciiDoc, reStructuredText and Jupyter notebook formats, rendering markdown with options
+    pub fn with_markdown(options: &MarkdownOptions) -> Self {
+        let mut registry = FormatRegistry::empty();
+        registry.register("md", Arc::new(TextedRenderer { options: options.clone() }));
+        let html = Arc::new(HtmlRenderer {});
+        registry.register("html", html.clone());
+        registry.register("htm", html);
+        let asciidoc = Arc::new(AsciiDocRenderer {});
+        registry.register("adoc", asciidoc.clone());
+        registry.register("asciidoc", asciidoc);
+        registry.register("rst", Arc::new(RstRenderer {}));
+        registry.register("ipynb", Arc::new(NotebookRenderer { options: options.clone() }));
+        registry
+    }
+
+    pub fn empty() -> Self {
+        FormatRegistry {
+            renderers: HashMap::new(),
+            shortcodes: Arc::new(Shortcodes::default()),
+            wiki_links: None,
+        }
+    }
+
+    /// Renders the files with extension using renderer, replacing the previous renderer if any
+    pub fn register(&mut self, extension: &str, renderer: Arc<dyn ContentRenderer>) {
+        self.renderers.insert(extension.trim_start_matches('.').to_lowercase(), renderer);
+    }
+
+    pub fn get(&self, file_name: &Path) -> Option<&dyn ContentRenderer> {
+        let extension = file_name.extension()?.to_str()?.to_lowercase();
+        self.renderers.get(&extension).map(|r| r.as_ref())
+    }
+
+    /// True if there is a renderer for the extension of file_name
+    pub fn is_supported(&self, file_name: &Path) -> bool {
+        self.get(file_name).is_some()
+    }
+
+    /// Replaces the built-in shortcodes with shortcodes also containing the user ones
+    pub fn set_shortcodes(&mut self, shortcodes: Arc<Shortcodes>) {
+        self.shortcodes = shortcodes;
+    }
+
+    /// Resolves the wiki links, e.g. `[[post_slug]]`, of all the formats
+    pub fn set_wiki_links(&mut self, wiki_links: Arc<WikiLinks>) {
+        self.wiki_links = Some(wiki_links);
+    }
+
+    pub fn render(&self, content_file: &ContentFile, render_options: RenderOptions) -> io::Result<Content> {
+        let Some(renderer) = self.get(&content_file.file_path) else {
+            return Err(io::Error::new(ErrorKind::Unsupported, format!("No renderer for the file {}", content_file.file_path.to_str().unwrap())));
+        };
+        let img_prefix = match render_options {
+            RenderOptions::PreviewOnly(_, ref img_prefix) => Some(img_prefix.0.clone()),
+            RenderOptions::FullContent => None,
+        };
+        // Shortcodes are replaced after rendering, so their html is not escaped
+        let expanded = self.shortcodes.expand(content_file, img_prefix.as_deref());
+        let mut content = renderer.render(&expanded.content_file, render_options)?;
+        content.rendered = expanded.replace_placeholders(&content.rendered);
+        if let Some(ref wiki_links) = self.wiki_links {
+            content.rendered = wiki_links.resolve(&content.rendered, &content_file.link);
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::content::content_format::ContentFormat;
+    use crate::content::parsing_utils::generate_header_from_file;
+
+    struct UpperRenderer {}
+
+    impl ContentRenderer for UpperRenderer {
+        fn render(&self, content_file: &ContentFile, _render_options: RenderOptions) -> io::Result<Content> {
+            Ok(Content {
+                header: generate_header_from_file(&content_file.file_path)?,
+                link: content_file.link.clone(),
+                title: "Upper".to_string(),
+                rendered: content_file.raw_content.to_uppercase(),
+                word_count: 0,
+                reading_time: 0,
+            })
+        }
+    }
+
+    #[test]
+    fn test_default_formats() {
+        let registry = FormatRegistry::default();
+        assert!(registry.is_supported(Path::new("posts/post.md")));
+        assert!(registry.is_supported(Path::new("posts/post.HTML")));
+        assert!(registry.is_supported(Path::new("posts/post.htm")));
+        assert!(!registry.is_supported(Path::new("posts/post.txt")));
+        assert!(!registry.is_supported(Path::new("posts/README")));
+        assert!(!FormatRegistry::empty().is_supported(Path::new("posts/post.md")));
+    }
+
+    #[test]
+    fn test_custom_format() {
+        let mut registry = FormatRegistry::default();
+        registry.register(".toml", Arc::new(UpperRenderer {}));
+
+        let content_file = ContentFile {
+            link: "post".to_string(),
+            file_path: PathBuf::from("Cargo.toml"),
+            format: ContentFormat::from_extension("toml"),
+            raw_content: "plain text".to_string(),
+        };
+        let content = registry.render(&content_file, RenderOptions::FullContent).unwrap();
+        assert_eq!(content.rendered, "PLAIN TEXT");
+        assert_eq!(content_file.format, ContentFormat::Custom("toml".to_string()));
+
+        let content_file = ContentFile { file_path: PathBuf::from("post.rtf"), ..content_file };
+        let err = registry.render(&content_file, RenderOptions::FullContent).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn test_wiki_links() {
+        let mut registry = FormatRegistry::default();
+        registry.register("toml", Arc::new(TextedRenderer::default()));
+        let post_links = HashMap::from([("other_post".to_string(), PathBuf::from("other.md"))]);
+        let wiki_links = WikiLinks::new(&post_links, &HashMap::new(), &registry);
+        registry.set_wiki_links(Arc::new(wiki_links));
+
+        let content_file = ContentFile {
+            link: "post".to_string(),
+            file_path: PathBuf::from("Cargo.toml"),
+            format: ContentFormat::from_extension("toml"),
+            raw_content: "# Title\n\nSee [[other_post|the other post]] and `[[other_post]]`".to_string(),
+        };
+        let content = registry.render(&content_file, RenderOptions::FullContent).unwrap();
+        assert_eq!(content.rendered, r#"<p>See <a class="wiki-link" href="/view/other_post/">the other post</a> and <code>[[other_post]]</code></p>
+"#);
+    }
+}
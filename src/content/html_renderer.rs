@@ -1,26 +1,21 @@
 use std::io;
-use std::io::ErrorKind;
 
 use regex::Regex;
 
 use crate::content::content_file::ContentFile;
-use crate::content::content_format::ContentFormat;
 use crate::content::content_renderer::{ContentRenderer, ImagePrefix, RenderOptions};
-use crate::content::parsing_utils::{extract_content, parse_texted_header, parse_title_html};
+use crate::content::parsing_utils::{count_words_html, extract_content, parse_texted_header, parse_title_html, reading_time};
 use crate::content::Content;
 
 pub struct HtmlRenderer {}
 
 impl ContentRenderer for HtmlRenderer {
-    fn render(content_file: &ContentFile, render_options: RenderOptions) -> io::Result<Content> {
-        if content_file.format != ContentFormat::Html {
-            return Err(io::Error::new(ErrorKind::InvalidData, format!("Unsupported format: {:?}", content_file.format)));
-        }
-
+    fn render(&self, content_file: &ContentFile, render_options: RenderOptions) -> io::Result<Content> {
         let link = content_file.link.clone();
         // The header is the same for HTML, but always living in an HTML comment block in the top of the file
         let (header, lines, maybe_line) = parse_texted_header(&content_file.file_path, content_file.raw_content.lines())?;
         let (title, lines, _title_line) = parse_title_html(lines, maybe_line);
+        let word_count = count_words_html(lines.clone());
         let content = extract_content(lines, &render_options);
 
         let rendered = match render_options {
@@ -33,6 +28,8 @@ impl ContentRenderer for HtmlRenderer {
             link,
             title,
             rendered,
+            word_count,
+            reading_time: reading_time(word_count),
         })
     }
 }
@@ -65,6 +62,7 @@ impl HtmlRenderer {
 
 #[cfg(test)]
 mod tests {
+    use crate::content::content_format::ContentFormat;
     use std::path::PathBuf;
 
     use crate::test_data::POST_DATA_HTML;
@@ -80,7 +78,7 @@ mod tests {
             format: ContentFormat::Html,
             raw_content: POST_DATA_HTML.to_string(),
         };
-        let content = HtmlRenderer::render(&content, RenderOptions::FullContent).unwrap();
+        let content = HtmlRenderer {}.render(&content, RenderOptions::FullContent).unwrap();
         assert_eq!(content.rendered, r##"<p>How to be a great software engineer?</p>
 <p>Someone asked me this question today and I didn’t have an answer. After thinking for a while, I came up with a list of what I try to do myself.</p>
 <p>Disclaimer: I don't think I am a great engineer, but I would love to have listened to that myself when I started my career, over 20 years ago.</p>
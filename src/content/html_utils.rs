@@ -0,0 +1,61 @@
+use std::borrow::Cow;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref CODE_REGEX: Regex = Regex::new(r"(?is)<pre[\s>].*?</pre>|<code[\s>].*?</code>").unwrap();
+    static ref TAG_REGEX: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
+/// Applies transform to the html, except inside `<pre>` and `<code>`
+pub fn outside_code(html: &str, transform: impl FnMut(&str) -> String) -> String {
+    outside(&CODE_REGEX, html, transform)
+}
+
+/// Applies transform to the text between the html tags, leaving the tags untouched
+pub fn outside_tags(html: &str, transform: impl FnMut(&str) -> String) -> String {
+    outside(&TAG_REGEX, html, transform)
+}
+
+/// Html without `<pre>` and `<code>` blocks, each replaced by replacement
+pub fn remove_code<'a>(html: &'a str, replacement: &str) -> Cow<'a, str> {
+    CODE_REGEX.replace_all(html, replacement)
+}
+
+/// Text of the html, each tag replaced by replacement
+pub fn strip_tags<'a>(html: &'a str, replacement: &str) -> Cow<'a, str> {
+    TAG_REGEX.replace_all(html, replacement)
+}
+
+fn outside(regex: &Regex, html: &str, mut transform: impl FnMut(&str) -> String) -> String {
+    let mut res = String::new();
+    let mut last = 0;
+    for skipped in regex.find_iter(html) {
+        res.push_str(&transform(&html[last..skipped.start()]));
+        res.push_str(skipped.as_str());
+        last = skipped.end();
+    }
+    res.push_str(&transform(&html[last..]));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outside_code() {
+        let html = r#"<p>a "b"</p><pre><code class="rust">let a = "b";</code></pre><p><code>a</code> a</p>"#;
+        assert_eq!(outside_code(html, |text| text.replace('a', "x")),
+                   r#"<p>x "b"</p><pre><code class="rust">let a = "b";</code></pre><p><code>a</code> x</p>"#);
+        assert_eq!(remove_code(html, " "), r#"<p>a "b"</p> <p>  a</p>"#);
+    }
+
+    #[test]
+    fn test_outside_tags() {
+        let html = r#"<p class="a">a <strong>b</strong></p>"#;
+        assert_eq!(outside_tags(html, |text| text.to_uppercase()), r#"<p class="a">A <strong>B</strong></p>"#);
+        assert_eq!(strip_tags(html, ""), "a b");
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use markdown::Options;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use spdlog::warn;
+
+use crate::content::html_utils::{outside_code, outside_tags, strip_tags};
+use crate::text_utils::slugify;
+
+lazy_static! {
+    static ref ADMONITION_REGEX: Regex = Regex::new(r"<blockquote>\s*<p>\[!(NOTE|TIP|IMPORTANT|WARNING|CAUTION)\][ \t]*\n?").unwrap();
+    static ref BLOCKQUOTE_REGEX: Regex = Regex::new(r"<blockquote>|</blockquote>").unwrap();
+    static ref PARAGRAPH_REGEX: Regex = Regex::new(r"(?s)<p>(.*?)</p>").unwrap();
+    static ref HEADING_REGEX: Regex = Regex::new(r"(?s)<h([1-6])>(.*?)</h[1-6]>").unwrap();
+}
+
+/// Markdown extensions, set in the `[markdown]` section of the configuration and overridable per post with the
+/// MARKDOWN header, e.g. `[MARKDOWN]: # (heading_ids=on, footnotes=off)`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MarkdownOptions {
+    /// `[^1]` references and `[^1]: text` definitions, rendered as a notes section at the end of the post
+    pub footnotes: bool,
+    /// Callout blocks: `> [!NOTE]`, `> [!TIP]`, `> [!IMPORTANT]`, `> [!WARNING]` and `> [!CAUTION]`
+    pub admonitions: bool,
+    /// A term line followed by `: definition` lines
+    pub definition_lists: bool,
+    /// Ids in the headings, so they can be linked, e.g. `<h2 id="getting_started">`
+    pub heading_ids: bool,
+    /// Typographic quotes, dashes (`--` and `---`) and ellipsis (`...`)
+    pub smart_punctuation: bool,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        MarkdownOptions {
+            footnotes: true,
+            admonitions: false,
+            definition_lists: false,
+            heading_ids: false,
+            smart_punctuation: false,
+        }
+    }
+}
+
+impl MarkdownOptions {
+    /// Options changed by the MARKDOWN header value, e.g. `heading_ids=on, footnotes=off`. Invalid entries are logged
+    pub fn with_overrides(&self, overrides: &str) -> Self {
+        let mut options = self.clone();
+        for entry in overrides.split([',', ' ']).map(|entry| entry.trim()).filter(|entry| !entry.is_empty()) {
+            let (name, value) = entry.split_once('=').unwrap_or((entry, "on"));
+            let value = match value.trim().to_lowercase().as_str() {
+                "on" | "true" | "yes" => true,
+                "off" | "false" | "no" => false,
+                _ => {
+                    warn!("Invalid value in the markdown option {}", entry);
+                    continue;
+                }
+            };
+            match name.trim() {
+                "footnotes" => options.footnotes = value,
+                "admonitions" => options.admonitions = value,
+                "definition_lists" => options.definition_lists = value,
+                "heading_ids" => options.heading_ids = value,
+                "smart_punctuation" => options.smart_punctuation = value,
+                name => warn!("Unknown markdown option {}", name),
+            }
+        }
+        options
+    }
+
+    /// GitHub flavored markdown, with or without footnotes. The notes section has a visible title
+    pub fn parse_options(&self) -> Options {
+        let mut options = Options::gfm();
+        options.parse.constructs.gfm_footnote_definition = self.footnotes;
+        options.parse.constructs.gfm_label_start_footnote = self.footnotes;
+        options.compile.gfm_footnote_label = Some("Notes".to_string());
+        options.compile.gfm_footnote_label_attributes = Some("class=\"footnotes-title\"".to_string());
+        options
+    }
+
+    /// Applies the enabled extensions to the html rendered by the markdown parser
+    pub fn apply(&self, html: String) -> String {
+        let mut html = html;
+        if self.admonitions {
+            html = admonitions(&html);
+        }
+        if self.definition_lists {
+            html = outside_code(&html, definition_lists);
+        }
+        if self.heading_ids {
+            html = heading_ids(&html);
+        }
+        if self.smart_punctuation {
+            html = outside_code(&html, smart_punctuation);
+        }
+        html
+    }
+}
+
+/// Blockquotes starting with `[!KIND]` become `<div class="admonition kind">` with a title
+fn admonitions(html: &str) -> String {
+    let mut res = String::new();
+    let mut remaining = html;
+    while let Some(caps) = ADMONITION_REGEX.captures(remaining) {
+        let start = caps.get(0).unwrap();
+        let after = &remaining[start.end()..];
+        let Some(end) = closing_blockquote(after) else {
+            break;
+        };
+        let kind = caps[1].to_lowercase();
+        let mut title = kind.clone();
+        title[..1].make_ascii_uppercase();
+
+        res.push_str(&remaining[..start.start()]);
+        res.push_str(&format!("<div class=\"admonition {}\">\n<p class=\"admonition-title\">{}</p>\n", kind, title));
+        // The kind alone in the first paragraph leaves it empty
+        let body = after[..end].strip_prefix("</p>").unwrap_or(&format!("<p>{}", &after[..end])).to_string();
+        res.push_str(&admonitions(body.trim_start_matches('\n')));
+        res.push_str("</div>");
+        remaining = &after[end + "</blockquote>".len()..];
+    }
+    res.push_str(remaining);
+    res
+}
+
+/// Position of the `</blockquote>` closing an already open blockquote
+fn closing_blockquote(html: &str) -> Option<usize> {
+    let mut depth = 1;
+    for tag in BLOCKQUOTE_REGEX.find_iter(html) {
+        depth += if tag.as_str() == "<blockquote>" { 1 } else { -1 };
+        if depth == 0 {
+            return Some(tag.start());
+        }
+    }
+    None
+}
+
+/// Paragraphs with term lines followed by `: definition` lines become definition lists
+fn definition_lists(html: &str) -> String {
+    let res = PARAGRAPH_REGEX.replace_all(html, |caps: &Captures| {
+        let lines: Vec<&str> = caps[1].lines().collect();
+        let Some(first_def) = lines.iter().position(|line| line.starts_with(": ")) else {
+            return caps[0].to_string();
+        };
+        if first_def == 0 {
+            return caps[0].to_string();
+        }
+
+        let mut list = "<dl>\n".to_string();
+        for term in &lines[..first_def] {
+            list.push_str(&format!("<dt>{}</dt>\n", term.trim()));
+        }
+        let mut definitions: Vec<String> = vec![];
+        for line in &lines[first_def..] {
+            match line.strip_prefix(": ") {
+                Some(definition) => definitions.push(definition.trim().to_string()),
+                // Continuation of the previous definition
+                None => definitions.last_mut().unwrap().push_str(&format!("\n{}", line)),
+            }
+        }
+        for definition in definitions {
+            list.push_str(&format!("<dd>{}</dd>\n", definition));
+        }
+        list.push_str("</dl>");
+        list
+    });
+    res.replace("</dl>\n<dl>\n", "")
+}
+
+/// Adds a unique id, based on the text, to the headings without attributes
+fn heading_ids(html: &str) -> String {
+    let mut used: HashMap<String, usize> = HashMap::new();
+    HEADING_REGEX.replace_all(html, |caps: &Captures| {
+        let level = &caps[1];
+        let text = &caps[2];
+        let plain_text = strip_tags(text, "")
+            .replace("&quot;", "")
+            .replace("&amp;", "and")
+            .replace("&lt;", "")
+            .replace("&gt;", "");
+        let slug = slugify(&plain_text);
+        let base = match slug.split('_').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("_") {
+            id if id.is_empty() => "section".to_string(),
+            id => id,
+        };
+        let count = used.entry(base.clone()).or_insert(0);
+        let id = match *count {
+            0 => base,
+            n => format!("{}-{}", base, n),
+        };
+        *count += 1;
+        format!("<h{} id=\"{}\">{}</h{}>", level, id, text, level)
+    }).to_string()
+}
+
+/// Typographic quotes, dashes and ellipsis in the text, leaving the tags untouched
+fn smart_punctuation(html: &str) -> String {
+    // Quotes are opening at the start of the text or after a space or opening bracket
+    let mut prev = ' ';
+    outside_tags(html, |text| smart_text(text, &mut prev))
+}
+
+fn smart_text(text: &str, prev: &mut char) -> String {
+    let text = text.replace("---", "\u{2014}")
+        .replace("--", "\u{2013}")
+        .replace("...", "\u{2026}")
+        .replace("&quot;", "\"");
+    let mut res = String::new();
+    for c in text.chars() {
+        let opening = prev.is_whitespace() || "([{\u{2014}\u{2013}".contains(*prev);
+        match c {
+            '"' if opening => res.push('\u{201c}'),
+            '"' => res.push('\u{201d}'),
+            '\'' if opening => res.push('\u{2018}'),
+            '\'' => res.push('\u{2019}'),
+            c => res.push(c),
+        }
+        *prev = c;
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(md: &str, options: &MarkdownOptions) -> String {
+        options.apply(markdown::to_html_with_options(md, &options.parse_options()).unwrap())
+    }
+
+    fn all_enabled() -> MarkdownOptions {
+        MarkdownOptions::default().with_overrides("admonitions, definition_lists=on heading_ids=true smart_punctuation=yes")
+    }
+
+    #[test]
+    fn test_overrides() {
+        let options = all_enabled();
+        assert!(options.footnotes && options.admonitions && options.definition_lists && options.heading_ids && options.smart_punctuation);
+        let options = options.with_overrides("footnotes=off, heading_ids=no, unknown=on, admonitions=maybe");
+        assert!(!options.footnotes && !options.heading_ids && options.admonitions);
+    }
+
+    #[test]
+    fn test_footnotes() {
+        let md = "Text[^1]\n\n[^1]: The note";
+        assert!(render(md, &MarkdownOptions::default()).contains(r#"<section data-footnotes="" class="footnotes"><h2 id="footnote-label" class="footnotes-title">Notes</h2>"#));
+        let options = MarkdownOptions::default().with_overrides("footnotes=off");
+        assert_eq!(render(md, &options), "<p>Text[^1]</p>\n<p>[^1]: The note</p>");
+    }
+
+    #[test]
+    fn test_admonitions() {
+        let md = "> [!NOTE]\n> Useful *information*.\n\n> [!WARNING] Careful\n> > quoted\n\n> Plain quote";
+        assert_eq!(render(md, &all_enabled()), r#"<div class="admonition note">
+<p class="admonition-title">Note</p>
+<p>Useful <em>information</em>.</p>
+</div>
+<div class="admonition warning">
+<p class="admonition-title">Warning</p>
+<p>Careful</p>
+<blockquote>
+<p>quoted</p>
+</blockquote>
+</div>
+<blockquote>
+<p>Plain quote</p>
+</blockquote>"#);
+        assert!(render(md, &MarkdownOptions::default()).starts_with("<blockquote>\n<p>[!NOTE]"));
+    }
+
+    #[test]
+    fn test_definition_lists() {
+        let md = "Rust\n: A language\n: Also a fungus\n\nCargo\n: The package manager\n\nNot a list";
+        assert_eq!(render(md, &all_enabled()), r#"<dl>
+<dt>Rust</dt>
+<dd>A language</dd>
+<dd>Also a fungus</dd>
+<dt>Cargo</dt>
+<dd>The package manager</dd>
+</dl>
+<p>Not a list</p>"#);
+    }
+
+    #[test]
+    fn test_heading_ids_and_punctuation() {
+        let md = "## Intro\n\n## Intro\n\n## \"Quoted\" -- it's...\n\n`\"code\" -- x`";
+        assert_eq!(render(md, &all_enabled()), "<h2 id=\"intro\">Intro</h2>\n<h2 id=\"intro-1\">Intro</h2>\n\
+<h2 id=\"quoted_it_s\">\u{201c}Quoted\u{201d} \u{2013} it\u{2019}s\u{2026}</h2>\n\
+<p><code>&quot;code&quot; -- x</code></p>");
+    }
+}
@@ -1,29 +1,57 @@
 use std::path::PathBuf;
 
 use chrono::NaiveDateTime;
+use serde::Serialize;
 
 pub mod content_file;
 pub mod content_renderer;
 pub mod parsing_utils;
 pub mod html_renderer;
 pub mod texted_renderer;
+pub mod markdown_extensions;
+pub mod asciidoc_renderer;
+pub mod rst_renderer;
+pub mod notebook_renderer;
 pub mod content_format;
+pub mod format_registry;
+pub mod wiki_links;
+pub mod shortcodes;
+pub mod html_utils;
 
+#[derive(Serialize)]
 pub struct Content {
+    #[serde(flatten)]
     pub header: ContentHeader,
+    /// Name of the post in the url, e.g. /view/{link}/
+    #[serde(rename = "slug")]
     pub link: String,
     pub title: String,
+    /// Summary or full content, depending on how it was rendered
+    #[serde(skip)]
     pub rendered: String,
+    /// Number of words of the full body
+    pub word_count: usize,
+    /// Estimated reading time in minutes
+    pub reading_time: usize,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ContentHeader {
+    #[serde(skip)]
     pub file_name: PathBuf,
     pub id: PostId,
     pub date: NaiveDateTime,
     pub author: String,
+    /// Authors listed in the AUTHOR header, separated by comma
+    pub authors: Vec<String>,
     pub tags: Vec<String>,
+    /// Name of the series this post is part of
+    pub series: Option<String>,
+    /// Position of the post inside the series
+    pub series_part: Option<u32>,
+    /// Date of the last update. UPDATED header or the last modification of the file
+    pub updated: NaiveDateTime,
 }
 
-#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize)]
 pub struct PostId(pub String);
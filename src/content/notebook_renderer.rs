@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::ErrorKind;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use lazy_static::lazy_static;
+use ntex::util::Bytes;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::content::content_file::ContentFile;
+use crate::content::content_renderer::{ContentRenderer, ImagePrefix, MaxLineCount, PreviewOptions, RenderOptions};
+use crate::content::parsing_utils::{count_words_markdown, escape_html, extract_content, header_from_attributes, image_src, parse_texted_header, parse_title_markdown, reading_time, remaining_text};
+use crate::content::markdown_extensions::MarkdownOptions;
+use crate::content::texted_renderer::TextedRenderer;
+use crate::content::Content;
+
+lazy_static! {
+    static ref ANSI_REGEX: Regex = Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap();
+    static ref ASSET_NAME_REGEX: Regex = Regex::new(r"^[\w.-]+$").unwrap();
+}
+
+/// Image outputs, by preference, and the extension of their assets
+const IMAGE_TYPES: [(&str, &str); 4] = [
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/svg+xml", "svg"),
+];
+
+/// Renders Jupyter notebooks (.ipynb). The header and title come from the first markdown cell, like a markdown post,
+/// or from the notebook metadata. Images embedded in the notebook are served as assets under the post url,
+/// e.g. /view/{post}/output-3-0.png, see notebook_assets
+#[derive(Default)]
+pub struct NotebookRenderer {
+    /// Markdown extensions of the markdown cells
+    pub options: MarkdownOptions,
+}
+
+impl ContentRenderer for NotebookRenderer {
+    fn render(&self, content_file: &ContentFile, render_options: RenderOptions) -> io::Result<Content> {
+        let link = content_file.link.clone();
+        let notebook = parse_notebook(&content_file.raw_content)?;
+        let metadata = &notebook["metadata"];
+        let cells = cells(&notebook);
+        let mut sources: Vec<String> = cells.iter().map(|cell| source_text(&cell["source"])).collect();
+
+        let mut texted_header = None;
+        let mut title = String::new();
+        if let Some(pos) = cells.iter().position(|cell| cell["cell_type"] == "markdown") {
+            let source = sources[pos].clone();
+            let (lines, maybe_line) = match parse_texted_header(&content_file.file_path, source.lines()) {
+                Ok((header, lines, maybe_line)) => {
+                    texted_header = Some(header);
+                    (lines, maybe_line)
+                }
+                Err(_) => (source.lines(), Some("")),
+            };
+            let after_header = remaining_text(maybe_line, lines.clone());
+            let (cell_title, lines, _title_line) = parse_title_markdown(lines, maybe_line);
+            title = cell_title;
+            sources[pos] = if title.is_empty() { after_header } else { remaining_text(None, lines) };
+        }
+        if title.is_empty() {
+            title = metadata["title"].as_str().unwrap_or("").to_string();
+        }
+        let header = match texted_header {
+            Some(header) => header,
+            None => header_from_attributes(&content_file.file_path, &metadata_attributes(metadata))?,
+        };
+
+        let word_count = cells.iter().zip(sources.iter())
+            .filter(|(cell, _)| cell["cell_type"] == "markdown")
+            .map(|(_, source)| count_words_markdown(source.lines()))
+            .sum();
+        let rendered = render_cells(cells, &sources, language(metadata), &render_options, &self.options)?;
+
+        Ok(Content {
+            header,
+            link,
+            title,
+            rendered,
+            word_count,
+            reading_time: reading_time(word_count),
+        })
+    }
+}
+
+fn parse_notebook(raw_content: &str) -> io::Result<Value> {
+    match serde_json::from_str::<Value>(raw_content) {
+        Ok(notebook) if notebook["cells"].is_array() => Ok(notebook),
+        Ok(_) => Err(io::Error::new(ErrorKind::InvalidData, "Invalid notebook: cells not found")),
+        Err(e) => Err(io::Error::new(ErrorKind::InvalidData, format!("Invalid notebook: {}", e))),
+    }
+}
+
+fn cells(notebook: &Value) -> &[Value] {
+    notebook["cells"].as_array().map_or(&[], |cells| cells.as_slice())
+}
+
+/// Sources and outputs are either a string or a list of lines
+fn source_text(source: &Value) -> String {
+    match source {
+        Value::String(text) => text.clone(),
+        Value::Array(lines) => lines.iter().filter_map(|line| line.as_str()).collect(),
+        _ => "".to_string(),
+    }
+}
+
+fn language(metadata: &Value) -> &str {
+    metadata["language_info"]["name"].as_str()
+        .or(metadata["kernelspec"]["language"].as_str())
+        .unwrap_or("")
+}
+
+/// Header fields of the notebook metadata, from the texted object or the metadata itself. E.g.
+/// `"authors": [{"name": "Thiago"}]`, `"tags": ["rust", "web"]` or `"date": "2024-05-01"`
+fn metadata_attributes(metadata: &Value) -> HashMap<String, String> {
+    let fields = if metadata["texted"].is_object() { &metadata["texted"] } else { metadata };
+    let mut attributes = HashMap::new();
+    for key in ["id", "date", "author", "authors", "tags", "keywords", "updated", "series", "series_part"] {
+        let value = match &fields[key] {
+            Value::String(value) => value.clone(),
+            Value::Number(value) => value.to_string(),
+            Value::Array(values) => values.iter()
+                .filter_map(|v| v.as_str().or(v["name"].as_str()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            _ => continue,
+        };
+        attributes.insert(key.to_string(), value);
+    }
+    attributes
+}
+
+fn render_cells(cells: &[Value], sources: &[String], language: &str, render_options: &RenderOptions, options: &MarkdownOptions) -> io::Result<String> {
+    let (prefix, preview_opt) = match render_options {
+        RenderOptions::PreviewOnly(preview_opt, img_prefix) => (Some(img_prefix.0.as_str()), Some(preview_opt)),
+        RenderOptions::FullContent => (None, None),
+    };
+    let mut remaining_lines = preview_opt.and_then(|opt| opt.max_line_count.as_ref()).map(|count| count.0);
+
+    let mut html = String::new();
+    for (index, (cell, source)) in cells.iter().zip(sources.iter()).enumerate() {
+        let (source, complete) = match preview_opt {
+            Some(preview_opt) => preview_source(source, preview_opt, &mut remaining_lines),
+            None => (source.clone(), true),
+        };
+        if source.trim().is_empty() && !complete {
+            break;
+        }
+        match cell["cell_type"].as_str() {
+            Some("markdown") => html.push_str(&render_markdown_cell(index, cell, &source, prefix, options)?),
+            Some("code") => html.push_str(&render_code_cell(index, cell, &source, language, prefix, complete, options)?),
+            _ => {}
+        }
+        if !complete || remaining_lines.is_some_and(|lines| lines <= 0) {
+            break;
+        }
+    }
+    Ok(html)
+}
+
+/// Part of the source in the preview, and whether the whole cell fits in it.
+/// The line count of the preview is shared by all the cells
+fn preview_source(source: &str, preview_opt: &PreviewOptions, remaining_lines: &mut Option<i32>) -> (String, bool) {
+    let options = RenderOptions::PreviewOnly(PreviewOptions {
+        max_line_count: remaining_lines.map(MaxLineCount),
+        tag_based: preview_opt.tag_based.clone(),
+    }, ImagePrefix("".to_string()));
+    let text = extract_content(source.lines(), &options);
+    let line_count = text.lines().count();
+    if let Some(lines) = remaining_lines {
+        *lines -= line_count as i32;
+    }
+    (text, line_count == source.lines().count())
+}
+
+fn render_markdown_cell(index: usize, cell: &Value, source: &str, prefix: Option<&str>, options: &MarkdownOptions) -> io::Result<String> {
+    let mut source = source.to_string();
+    if let Some(attachments) = cell["attachments"].as_object() {
+        for name in attachments.keys().filter(|name| ASSET_NAME_REGEX.is_match(name)) {
+            source = source.replace(&format!("attachment:{}", name), &attachment_name(index, name));
+        }
+    }
+    let mut html = TextedRenderer::render_markdown(&source, prefix, options)?;
+    if !html.is_empty() && !html.ends_with('\n') {
+        html.push('\n');
+    }
+    Ok(html)
+}
+
+/// The code and, if the whole cell is rendered, its outputs
+fn render_code_cell(index: usize, cell: &Value, source: &str, language: &str, prefix: Option<&str>, with_outputs: bool, options: &MarkdownOptions) -> io::Result<String> {
+    let mut html = match language {
+        "" => format!("<pre><code>{}</code></pre>\n", escape_html(source.trim_end())),
+        language => format!("<pre><code class=\"language-{}\">{}</code></pre>\n", escape_html(language), escape_html(source.trim_end())),
+    };
+    if !with_outputs {
+        return Ok(html);
+    }
+
+    let outputs = cell["outputs"].as_array().map_or(&[][..], |outputs| outputs.as_slice());
+    let mut rendered_outputs = String::new();
+    for (output_index, output) in outputs.iter().enumerate() {
+        rendered_outputs.push_str(&render_output(index, output_index, output, prefix, options)?);
+    }
+    if !rendered_outputs.is_empty() {
+        html.push_str(&format!("<div class=\"notebook-output\">\n{}</div>\n", rendered_outputs));
+    }
+    Ok(html)
+}
+
+fn render_output(index: usize, output_index: usize, output: &Value, prefix: Option<&str>, options: &MarkdownOptions) -> io::Result<String> {
+    let html = match output["output_type"].as_str() {
+        Some("stream") => {
+            let name = output["name"].as_str().unwrap_or("stdout");
+            format!("<pre class=\"{}\">{}</pre>\n", escape_html(name), escape_html(source_text(&output["text"]).trim_end()))
+        }
+        Some("error") => {
+            let traceback: Vec<String> = output["traceback"].as_array().map_or(vec![], |lines| {
+                lines.iter().filter_map(|line| line.as_str()).map(|line| ANSI_REGEX.replace_all(line, "").to_string()).collect()
+            });
+            format!("<pre class=\"error\">{}</pre>\n", escape_html(traceback.join("\n").trim_end()))
+        }
+        Some("execute_result") | Some("display_data") => {
+            let data = &output["data"];
+            if let Some((_, extension)) = IMAGE_TYPES.iter().find(|(mime, _)| !data[*mime].is_null()) {
+                let src = image_src(prefix, &output_name(index, output_index, extension));
+                format!("<p><img src=\"{}\" alt=\"Output {}\" /></p>\n", src, output_index + 1)
+            } else if !data["text/html"].is_null() {
+                format!("{}\n", source_text(&data["text/html"]).trim_end())
+            } else if !data["text/markdown"].is_null() {
+                TextedRenderer::render_markdown(&source_text(&data["text/markdown"]), prefix, options)?
+            } else if !data["text/plain"].is_null() {
+                format!("<pre>{}</pre>\n", escape_html(source_text(&data["text/plain"]).trim_end()))
+            } else {
+                "".to_string()
+            }
+        }
+        _ => "".to_string(),
+    };
+    Ok(html)
+}
+
+fn output_name(index: usize, output_index: usize, extension: &str) -> String {
+    format!("output-{}-{}.{}", index, output_index, extension)
+}
+
+fn attachment_name(index: usize, name: &str) -> String {
+    format!("attachment-{}-{}", index, name)
+}
+
+/// Images embedded in a notebook by their asset name, with their mime type
+pub type NotebookAssets = HashMap<String, (Bytes, &'static str)>;
+
+/// Images embedded in the notebook, by asset name. E.g. output-3-0.png is the first output of the fourth cell
+/// and attachment-1-chart.png is the attachment chart.png of the second cell
+pub fn notebook_assets(raw_content: &str) -> NotebookAssets {
+    let mut assets = NotebookAssets::new();
+    let Ok(notebook) = parse_notebook(raw_content) else {
+        return assets;
+    };
+    for (index, cell) in cells(&notebook).iter().enumerate() {
+        if let Some(outputs) = cell["outputs"].as_array() {
+            for (output_index, output) in outputs.iter().enumerate() {
+                let data = &output["data"];
+                if let Some((mime, extension)) = IMAGE_TYPES.iter().find(|(mime, _)| !data[*mime].is_null()) {
+                    if let Some(bytes) = decode_image(mime, &data[*mime]) {
+                        assets.insert(output_name(index, output_index, extension), (Bytes::from(bytes), *mime));
+                    }
+                }
+            }
+        }
+        if let Some(attachments) = cell["attachments"].as_object() {
+            for (name, data) in attachments {
+                if let Some((mime, _)) = IMAGE_TYPES.iter().find(|(mime, _)| !data[*mime].is_null()) {
+                    if let Some(bytes) = decode_image(mime, &data[*mime]) {
+                        assets.insert(attachment_name(index, name), (Bytes::from(bytes), *mime));
+                    }
+                }
+            }
+        }
+    }
+    assets
+}
+
+/// Images are base64 encoded, except svg, stored as text
+fn decode_image(mime: &str, data: &Value) -> Option<Vec<u8>> {
+    let text = source_text(data);
+    if mime == "image/svg+xml" {
+        return Some(text.into_bytes());
+    }
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    STANDARD.decode(text).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::content::content_format::ContentFormat;
+    use crate::content::content_renderer::BreakTag;
+
+    // A 1x1 png
+    const PNG_BASE64: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+
+    fn notebook() -> String {
+        serde_json::json!({
+            "metadata": {
+                "language_info": {"name": "python"},
+                "authors": [{"name": "Thiago"}],
+                "tags": ["data", "python"],
+                "date": "2024-05-01 10:00:00"
+            },
+            "nbformat": 4,
+            "cells": [
+                {"cell_type": "markdown", "metadata": {}, "source": ["# Sales analysis\n", "\n", "Monthly *sales*.\n", "\n", "<!-- more -->\n", "\n", "More text"]},
+                {"cell_type": "code", "metadata": {}, "source": "print(1 < 2)", "outputs": [
+                    {"output_type": "stream", "name": "stdout", "text": ["True\n"]},
+                    {"output_type": "display_data", "data": {"image/png": PNG_BASE64, "text/plain": ["<Figure>"]}}
+                ]},
+                {"cell_type": "markdown", "metadata": {}, "source": "![chart](attachment:chart.png)",
+                 "attachments": {"chart.png": {"image/png": PNG_BASE64}}}
+            ]
+        }).to_string()
+    }
+
+    fn content_file(raw_content: &str) -> ContentFile {
+        ContentFile {
+            link: "sales".to_string(),
+            file_path: PathBuf::from("res/posts/new_post.md"),
+            format: ContentFormat::Notebook,
+            raw_content: raw_content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_header_from_metadata() {
+        let content = NotebookRenderer::default().render(&content_file(&notebook()), RenderOptions::FullContent).unwrap();
+        assert_eq!(content.title, "Sales analysis");
+        assert_eq!(content.header.author, "Thiago");
+        assert_eq!(content.header.tags, vec!["data", "python"]);
+        assert_eq!(content.header.date.to_string(), "2024-05-01 10:00:00");
+        assert_eq!(content.word_count, 5);
+    }
+
+    #[test]
+    fn test_texted_header() {
+        let raw = notebook().replace("# Sales analysis", "[ID]: # (abc)\\n\",\"[DATE]: # (2024-02-12 22:54:00.000)\\n\",\"\\n\",\"# Sales");
+        let content = NotebookRenderer::default().render(&content_file(&raw), RenderOptions::FullContent).unwrap();
+        assert_eq!(content.title, "Sales");
+        assert_eq!(content.header.id.0, "abc");
+        assert!(content.rendered.starts_with("<p>Monthly <em>sales</em>.</p>"));
+    }
+
+    #[test]
+    fn test_full_content() {
+        let content = NotebookRenderer::default().render(&content_file(&notebook()), RenderOptions::FullContent).unwrap();
+        assert_eq!(content.rendered, r#"<p>Monthly <em>sales</em>.</p>
+<p>More text</p>
+<pre><code class="language-python">print(1 &lt; 2)</code></pre>
+<div class="notebook-output">
+<pre class="stdout">True</pre>
+<p><img src="output-1-1.png" alt="Output 2" /></p>
+</div>
+<p><img src="attachment-2-chart.png" alt="chart" /></p>
+"#);
+    }
+
+    #[test]
+    fn test_preview() {
+        let preview_opt = PreviewOptions { max_line_count: None, tag_based: BreakTag("<!-- more -->".to_string()) };
+        let options = RenderOptions::PreviewOnly(preview_opt, ImagePrefix("/view/sales".to_string()));
+        let content = NotebookRenderer::default().render(&content_file(&notebook()), options).unwrap();
+        assert_eq!(content.rendered, "<p>Monthly <em>sales</em>.</p>\n");
+
+        let raw = notebook().replace("<!-- more -->", "");
+        let preview_opt = PreviewOptions { max_line_count: Some(MaxLineCount(7)), tag_based: BreakTag("<!-- more -->".to_string()) };
+        let options = RenderOptions::PreviewOnly(preview_opt, ImagePrefix("/view/sales".to_string()));
+        let content = NotebookRenderer::default().render(&content_file(&raw), options).unwrap();
+        assert!(content.rendered.ends_with("<img src=\"/view/sales/output-1-1.png\" alt=\"Output 2\" /></p>\n</div>\n"), "{}", content.rendered);
+    }
+
+    #[test]
+    fn test_assets() {
+        let assets = notebook_assets(&notebook());
+        let (png, mime) = assets.get("output-1-1.png").unwrap();
+        assert_eq!(*mime, "image/png");
+        assert!(png.starts_with(b"\x89PNG"));
+        assert!(assets.contains_key("attachment-2-chart.png"));
+        assert!(!assets.contains_key("output-1-0.png"));
+        assert!(notebook_assets("{}").is_empty());
+    }
+}
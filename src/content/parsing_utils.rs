@@ -1,14 +1,18 @@
+use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::str::Lines;
 use std::{fs, io};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
+use spdlog::warn;
 use uuid::Uuid;
 
 use crate::content::content_renderer::RenderOptions;
+use crate::content::html_utils::{remove_code, strip_tags};
+use crate::content::shortcodes::remove_placeholders;
 use crate::content::{ContentHeader, PostId};
 use crate::text_utils::parse_date_time;
 use crate::util::os_helper::get_name;
@@ -18,6 +22,9 @@ pub fn parse_texted_header<'a>(file_name: &Path, lines: Lines<'a>) -> io::Result
     let mut date: String = "".to_string();
     let mut author: String = "".to_string();
     let mut tags: String = "".to_string();
+    let mut series: Option<String> = None;
+    let mut series_part: String = "".to_string();
+    let mut updated: String = "".to_string();
 
     let mut lines = lines.clone();
     let mut maybe_line = lines.next();
@@ -57,6 +64,9 @@ pub fn parse_texted_header<'a>(file_name: &Path, lines: Lines<'a>) -> io::Result
             "DATE" => date = val.to_string(),
             "AUTHOR" => author = val.to_string(),
             "TAGS" => tags = val.to_string(),
+            "SERIES" => series = Some(val.trim().to_string()),
+            "SERIES_PART" => series_part = val.trim().to_string(),
+            "UPDATED" => updated = val.to_string(),
             _ => {}
         }
         maybe_line = lines.next();
@@ -88,11 +98,24 @@ pub fn parse_texted_header<'a>(file_name: &Path, lines: Lines<'a>) -> io::Result
         }
     }
 
+    // NotFound tells a missing header apart from an invalid one
     if id.is_empty() && date.is_empty() && author.is_empty() && tags.is_empty() {
-        return Err(io::Error::new(ErrorKind::InvalidData, "Invalid texted header".to_string()));
+        return Err(io::Error::new(ErrorKind::NotFound, "Invalid texted header".to_string()));
     }
 
     let tags = extract_tags(&tags);
+    let authors = extract_authors(&author);
+    // An invalid part is ignored, keeping the rest of the header
+    let series_part = match series_part.as_str() {
+        "" => None,
+        part => match part.parse::<u32>() {
+            Ok(part) => Some(part),
+            Err(_) => {
+                warn!("Invalid series part {} - file={}", part, file_name.to_str().unwrap());
+                None
+            }
+        },
+    };
     let date = match parse_date_time(&date) {
         Ok(d) => Ok(d),
         Err(e) => {
@@ -100,17 +123,39 @@ pub fn parse_texted_header<'a>(file_name: &Path, lines: Lines<'a>) -> io::Result
         }
     }?;
 
+    // Without a valid UPDATED header, the last modification of the file is used. A post is never updated before its date
+    let updated = match updated.as_str() {
+        "" => None,
+        updated => match parse_date_time(updated) {
+            Ok(d) => Some(d),
+            Err(e) => {
+                warn!("Invalid updated date {} - {} - file={}", updated, e, file_name.to_str().unwrap());
+                None
+            }
+        },
+    };
+    let updated = updated.or_else(|| file_modified(file_name)).unwrap_or(date).max(date);
+
     let header = ContentHeader {
         file_name: file_name.to_path_buf(),
         id: PostId(id),
         date,
         author,
+        authors,
         tags,
+        series,
+        series_part,
+        updated,
     };
 
     Ok((header, lines, maybe_line))
 }
 
+fn file_modified(file_name: &Path) -> Option<NaiveDateTime> {
+    let modified: DateTime<Utc> = fs::metadata(file_name).ok()?.modified().ok()?.into();
+    Some(modified.naive_utc())
+}
+
 pub fn parse_title_markdown<'a>(lines: Lines<'a>, mut maybe_line: Option<&'a str>) -> (String, Lines<'a>, Option<&'a str>) {
     let mut lines = lines;
     let title = loop {
@@ -141,8 +186,12 @@ pub fn generate_header_from_file(file_name: &PathBuf) -> io::Result<ContentHeade
         file_name,
         id,
         date,
+        authors: vec![author.clone()],
         author,
         tags: vec![],
+        series: None,
+        series_part: None,
+        updated: date,
     })
 }
 
@@ -201,6 +250,148 @@ pub fn extract_content(lines: Lines, render_options: &RenderOptions) -> String {
     }
 }
 
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Counts the words of a markdown body, ignoring fenced code blocks and comments
+pub fn count_words_markdown(lines: Lines) -> usize {
+    let mut text = String::new();
+    let mut code_fence: Option<&str> = None;
+    for line in lines {
+        let trimmed = line.trim_start();
+        match code_fence {
+            Some(fence) => {
+                if trimmed.starts_with(fence) {
+                    code_fence = None;
+                }
+            }
+            None => {
+                if trimmed.starts_with("```") {
+                    code_fence = Some("```");
+                } else if trimmed.starts_with("~~~") {
+                    code_fence = Some("~~~");
+                } else {
+                    text.push_str(line);
+                    text.push('\n');
+                }
+            }
+        }
+    }
+
+    let text = remove_comments(&text).unwrap_or(text);
+    count_words(&text)
+}
+
+/// Counts the words of an html body, ignoring tags, code blocks, scripts and comments
+pub fn count_words_html(lines: Lines) -> usize {
+    lazy_static! {
+        static ref SCRIPT_REGEX: Regex = Regex::new(r"(?is)<(script|style)[\s>].*?</(script|style)>").unwrap();
+    }
+
+    let text: String = lines.collect::<Vec<_>>().join("\n");
+    let text = remove_comments(&text).unwrap_or(text);
+    let text = remove_code(&text, " ");
+    let text = SCRIPT_REGEX.replace_all(&text, " ");
+    let text = strip_tags(&text, " ");
+    count_words(&text)
+}
+
+/// Shortcodes are not counted, their placeholders are removed
+fn count_words(text: &str) -> usize {
+    remove_placeholders(text).split_whitespace()
+        .filter(|w| w.chars().any(|c| c.is_alphanumeric()))
+        .count()
+}
+
+/// Estimated reading time in minutes. Any text takes at least one minute
+pub fn reading_time(word_count: usize) -> usize {
+    match word_count {
+        0 => 0,
+        x => x.div_ceil(WORDS_PER_MINUTE),
+    }
+}
+
+/// Header from the native attributes of a document, e.g. AsciiDoc `:author:` or reStructuredText `:Author:`.
+/// Keys are lowercase. As in posts without header, missing values come from the file
+pub fn header_from_attributes(file_name: &PathBuf, attributes: &HashMap<String, String>) -> io::Result<ContentHeader> {
+    let mut header = generate_header_from_file(file_name)?;
+    let get = |keys: &[&str]| keys.iter().find_map(|k| attributes.get(*k)).map(|v| v.trim());
+
+    if let Some(id) = get(&["id"]) {
+        header.id = PostId(id.to_string());
+    }
+    if let Some(author) = get(&["author", "authors"]) {
+        header.author = author.to_string();
+        header.authors = extract_authors(author);
+    }
+    if let Some(tags) = get(&["tags", "keywords"]) {
+        header.tags = extract_tags(&tags.replace(',', " "));
+    }
+    if let Some(date) = get(&["date", "revdate"]) {
+        header.date = parse_attribute_date(date, file_name)?;
+    }
+    header.updated = match get(&["updated"]) {
+        Some(updated) => parse_attribute_date(updated, file_name)?,
+        None => file_modified(file_name).unwrap_or(header.date),
+    }.max(header.date);
+    header.series = get(&["series"]).map(|s| s.to_string());
+    header.series_part = match get(&["series_part", "series-part"]) {
+        None => None,
+        Some(part) => match part.parse::<u32>() {
+            Ok(part) => Some(part),
+            Err(_) => return Err(io::Error::new(ErrorKind::InvalidData, format!("Invalid series part {} - file={}", part, file_name.to_str().unwrap()))),
+        },
+    };
+
+    Ok(header)
+}
+
+/// Dates of document attributes may not have the time. E.g. 2024-05-01
+fn parse_attribute_date(date: &str, file_name: &Path) -> io::Result<NaiveDateTime> {
+    let date = match date.len() {
+        10 => format!("{} 00:00:00", date),
+        _ => date.to_string(),
+    };
+    match parse_date_time(&date) {
+        Ok(d) => Ok(d),
+        Err(e) => Err(io::Error::new(ErrorKind::InvalidData, format!("{} - file={}", e, file_name.to_str().unwrap()))),
+    }
+}
+
+/// Text from the current line to the end
+pub fn remaining_text<'a>(maybe_line: Option<&'a str>, lines: Lines<'a>) -> String {
+    let mut text = String::new();
+    for line in maybe_line.into_iter().chain(lines) {
+        text.push_str(line);
+        text.push('\n');
+    }
+    text
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Source of an image of a post. Relative images are prefixed, e.g. with the post url in the listing
+pub fn image_src(img_prefix: Option<&str>, src: &str) -> String {
+    match img_prefix {
+        Some(prefix) if !src.contains("://") && !src.starts_with('/') => {
+            format!("{}/{}", prefix.trim_end_matches('/'), src)
+        }
+        _ => src.to_string(),
+    }
+}
+
+/// Links urls not already in a link, e.g. "see https://texted.dev" (html escaped text)
+pub fn link_urls(html: &str) -> String {
+    lazy_static! {
+        static ref URL_REGEX: Regex = Regex::new(r#"(^|[\s(])(https?://[^\s<\[\])]*[^\s<\[\]).,;:!?])"#).unwrap();
+    }
+    URL_REGEX.replace_all(html, r#"$1<a href="$2">$2</a>"#).to_string()
+}
+
 fn extract_tags(tags_str: &str) -> Vec<String> {
     let x = tags_str.split(' ')
         .filter(|x| !x.is_empty())
@@ -209,6 +400,26 @@ fn extract_tags(tags_str: &str) -> Vec<String> {
     x
 }
 
+fn extract_authors(authors_str: &str) -> Vec<String> {
+    authors_str.split(',')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Value of the header key, e.g. MARKDOWN for `[MARKDOWN]: # (heading_ids=on)`, if present in the texted header
+pub fn texted_header_value<'a>(lines: Lines<'a>, key: &str) -> Option<&'a str> {
+    for line in lines.map(|line| line.trim()).filter(|line| !line.is_empty() && *line != "<!--") {
+        match extract_texted_header(line) {
+            Some((k, v)) if k == key => return Some(v),
+            Some(_) => continue,
+            None => break,
+        }
+    }
+    None
+}
+
 fn extract_texted_header(line: &str) -> Option<(&str, &str)> {
     lazy_static! {
             static ref HEADER_REGEX : Regex = Regex::new(r"\[(?P<key>\w+)\]: # \((?P<value>.+)\)").unwrap();
@@ -271,7 +482,9 @@ pub fn remove_comments(md_post: &str) -> io::Result<String> {
 
 #[cfg(test)]
 mod tests {
-    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+    use chrono::{NaiveDate, NaiveTime};
+
+    use crate::text_utils::format_date_time;
 
     use super::*;
 
@@ -297,6 +510,15 @@ mod tests {
         assert_eq!(tags, ["one", "two", "three", "four"]);
     }
 
+    #[test]
+    fn test_extract_authors() {
+        let authors = extract_authors("Thiago Guedes, joe ,, ana");
+        assert_eq!(authors, ["Thiago Guedes", "joe", "ana"]);
+        let authors = extract_authors("thiago");
+        assert_eq!(authors, ["thiago"]);
+        assert!(extract_authors("").is_empty());
+    }
+
     #[test]
     fn test_lines_texted() {
         let file_name = PathBuf::from("posts/20200522_how_to_write_a_code_review/index.md");
@@ -313,18 +535,99 @@ mod tests {
 -->        "##;
 
         let (header, _lines, _next_line) = parse_texted_header(&file_name, content.lines()).unwrap();
-        let date = NaiveDate::from_ymd_opt(2024, 02, 12).unwrap();
-        let time = NaiveTime::from_hms_opt(22, 54, 00).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 2, 12).unwrap();
+        let time = NaiveTime::from_hms_opt(22, 54, 0).unwrap();
         let expected = ContentHeader {
             file_name: PathBuf::from("posts/20200522_how_to_write_a_code_review/index.md"),
             id: PostId("21c1e9ad-4ebb-4168-a543-fbf77cc35a85".to_string()),
             date: NaiveDateTime::new(date, time),
             author: "thiago".to_string(),
+            authors: vec!["thiago".to_string()],
             tags: vec![],
+            series: None,
+            series_part: None,
+            updated: NaiveDateTime::new(date, time),
         };
         assert_eq!(header, expected);
     }
 
+    #[test]
+    fn test_series_header() {
+        let file_name = PathBuf::from("posts/rust_tutorial_2.md");
+        let content = r##"[ID]: # (21c1e9ad-4ebb-4168-a543-fbf77cc35a85)
+[DATE]: # (2024-02-12 22:54:00.000)
+[AUTHOR]: # (thiago)
+[SERIES]: # (Rust web tutorial)
+[SERIES_PART]: # (2)
+
+# Part 2"##;
+
+        let (header, _lines, _next_line) = parse_texted_header(&file_name, content.lines()).unwrap();
+        assert_eq!(header.series, Some("Rust web tutorial".to_string()));
+        assert_eq!(header.series_part, Some(2));
+
+        let content = content.replace("(2)", "(second)");
+        let (header, _lines, _next_line) = parse_texted_header(&file_name, content.lines()).unwrap();
+        assert_eq!(header.id.0, "21c1e9ad-4ebb-4168-a543-fbf77cc35a85");
+        assert_eq!(header.series, Some("Rust web tutorial".to_string()));
+        assert_eq!(header.series_part, None);
+    }
+
+    #[test]
+    fn test_updated_header() {
+        let file_name = PathBuf::from("posts/does_not_exist.md");
+        let content = r##"[ID]: # (21c1e9ad-4ebb-4168-a543-fbf77cc35a85)
+[DATE]: # (2024-02-12 22:54:00.000)
+[AUTHOR]: # (thiago)"##;
+
+        // No header and no file. Creation date is used
+        let (header, _lines, _next_line) = parse_texted_header(&file_name, content.lines()).unwrap();
+        assert_eq!(header.updated, header.date);
+
+        let with_updated = format!("{}\n[UPDATED]: # (2024-03-01 10:00:00.000)", content);
+        let (header, _lines, _next_line) = parse_texted_header(&file_name, with_updated.lines()).unwrap();
+        let (date, time) = format_date_time(&header.updated);
+        assert_eq!(date, "2024-03-01");
+        assert_eq!(time, "10:00:00");
+
+        // Before the post date or invalid
+        for updated in ["2024-01-01 10:00:00.000", "yesterday"] {
+            let with_updated = format!("{}\n[UPDATED]: # ({})", content, updated);
+            let (header, _lines, _next_line) = parse_texted_header(&file_name, with_updated.lines()).unwrap();
+            assert_eq!(header.updated, header.date);
+        }
+    }
+
+    #[test]
+    fn test_count_words() {
+        let md = r##"Some text with <!-- hidden comment --> five words
+
+```rust
+let code = "is not counted";
+```
+
+- item *one*
+"##;
+        assert_eq!(count_words_markdown(md.lines()), 7);
+
+        let html = r##"<p>Some <strong>html</strong> text</p>
+<pre><code>let x = 1;</code></pre>
+<!-- <p>comment</p> -->
+<p>End</p>"##;
+        assert_eq!(count_words_html(html.lines()), 4);
+
+        let shortcode = "Two words\n\n\u{fffc}0\u{fffc}\n\nand\u{fffc}1\u{fffc}";
+        assert_eq!(count_words_markdown(shortcode.lines()), 3);
+    }
+
+    #[test]
+    fn test_reading_time() {
+        assert_eq!(reading_time(0), 0);
+        assert_eq!(reading_time(1), 1);
+        assert_eq!(reading_time(200), 1);
+        assert_eq!(reading_time(201), 2);
+    }
+
     #[test]
     fn test_no_header() {
         let file_name = PathBuf::from("posts/20200522_how_to_write_a_code_review/index.md");
@@ -334,11 +637,43 @@ mod tests {
         let res = parse_texted_header(&file_name, content.lines());
         assert!(res.is_err());
         if let Err(err) = res {
-            assert_eq!(err.kind(), ErrorKind::InvalidData);
+            assert_eq!(err.kind(), ErrorKind::NotFound);
             assert_eq!(err.to_string(), "Invalid texted header");
         }
     }
 
+    #[test]
+    fn test_header_from_attributes() {
+        let attributes: HashMap<String, String> = [
+            ("author", "Thiago, Ana"),
+            ("keywords", "rust, web"),
+            ("revdate", "2024-05-01"),
+            ("series", "Rust web"),
+            ("series-part", "2"),
+        ].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let header = header_from_attributes(&PathBuf::from("res/posts/new_post.md"), &attributes).unwrap();
+        assert_eq!(header.authors, vec!["Thiago", "Ana"]);
+        assert_eq!(header.tags, vec!["rust", "web"]);
+        assert_eq!(header.date.to_string(), "2024-05-01 00:00:00");
+        assert_eq!(header.series, Some("Rust web".to_string()));
+        assert_eq!(header.series_part, Some(2));
+        assert!(header.updated >= header.date);
+
+        let attributes: HashMap<String, String> = [("date", "May 1st")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        assert!(header_from_attributes(&PathBuf::from("res/posts/new_post.md"), &attributes).is_err());
+    }
+
+    #[test]
+    fn test_html_helpers() {
+        assert_eq!(escape_html(r#"<a href="x">&</a>"#), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+        assert_eq!(image_src(Some("/view/post/"), "img.png"), "/view/post/img.png");
+        assert_eq!(image_src(Some("/view/post"), "https://x.com/img.png"), "https://x.com/img.png");
+        assert_eq!(image_src(None, "img.png"), "img.png");
+        assert_eq!(link_urls("see https://texted.dev."), r#"see <a href="https://texted.dev">https://texted.dev</a>."#);
+        assert_eq!(link_urls(r#"<a href="https://texted.dev">x</a>"#), r#"<a href="https://texted.dev">x</a>"#);
+        assert_eq!(remaining_text(Some("a"), "b\nc".lines()), "a\nb\nc\n");
+    }
+
     #[test]
     fn test_parse_removes_comment() {
         let content = r#"Some text.<!-- more -->Wo<!-- xyz -->rd"#;
@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use std::io;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::content::content_file::ContentFile;
+use crate::content::content_renderer::{ContentRenderer, RenderOptions};
+use crate::content::parsing_utils::{count_words_html, escape_html, extract_content, header_from_attributes, image_src, link_urls, parse_texted_header, reading_time, remaining_text};
+use crate::content::Content;
+
+lazy_static! {
+    static ref FIELD_REGEX: Regex = Regex::new(r"^:([\w][\w -]*):\s*(.*)$").unwrap();
+    static ref DIRECTIVE_REGEX: Regex = Regex::new(r"^\.\. ([\w-]+)::\s*(.*)$").unwrap();
+    static ref LIST_REGEX: Regex = Regex::new(r"^(\s*)([-*+]|\d+[.)]|#[.)]) (.*)$").unwrap();
+    static ref CODE_REGEX: Regex = Regex::new(r"``(.+?)``").unwrap();
+    static ref LINK_REGEX: Regex = Regex::new(r"`([^`]+?)\s*&lt;([^`]+?)&gt;`__?").unwrap();
+    static ref REFERENCE_REGEX: Regex = Regex::new(r"`([^`]+)`__?").unwrap();
+    static ref ROLE_REGEX: Regex = Regex::new(r":([\w-]+):`([^`]+)`").unwrap();
+    static ref STRONG_REGEX: Regex = Regex::new(r"\*\*(\S(?:.*?\S)?)\*\*").unwrap();
+    static ref EMPHASIS_REGEX: Regex = Regex::new(r"\*(\S(?:[^*]*?\S)?)\*").unwrap();
+    static ref INTERPRETED_REGEX: Regex = Regex::new(r"`([^`]+)`").unwrap();
+}
+
+const ADORNMENT_CHARS: &str = "=-`:'\"~^_*+#<>.";
+
+/// Renders reStructuredText posts. The header is either the texted header in comments, e.g. `.. [DATE]: # (...)`,
+/// or the document title followed by the bibliographic fields, e.g. `:Date:`, `:Author:` and `:Tags:`
+pub struct RstRenderer {}
+
+impl ContentRenderer for RstRenderer {
+    fn render(&self, content_file: &ContentFile, render_options: RenderOptions) -> io::Result<Content> {
+        let link = content_file.link.clone();
+        let (texted_header, text) = match parse_texted_header(&content_file.file_path, content_file.raw_content.lines()) {
+            Ok((header, lines, maybe_line)) => (Some(header), remaining_text(maybe_line, lines)),
+            Err(_) => (None, content_file.raw_content.clone()),
+        };
+
+        let document = parse_document(&text);
+        let header = match texted_header {
+            Some(header) => header,
+            None => header_from_attributes(&content_file.file_path, &document.fields)?,
+        };
+        let word_count = count_words_html(to_html(&document.body, None).lines());
+        let content = extract_content(document.body.lines(), &render_options);
+
+        let prefix: Option<&str> = match render_options {
+            RenderOptions::PreviewOnly(ref _preview_opt, ref img_prefix) => Some(img_prefix.0.as_str()),
+            RenderOptions::FullContent => None,
+        };
+
+        Ok(Content {
+            header,
+            link,
+            title: document.title,
+            rendered: to_html(&content, prefix),
+            word_count,
+            reading_time: reading_time(word_count),
+        })
+    }
+}
+
+struct Document {
+    title: String,
+    fields: HashMap<String, String>,
+    body: String,
+}
+
+/// Splits the document title and the bibliographic fields following it from the body
+fn parse_document(text: &str) -> Document {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut i = 0;
+    while i < lines.len() && (lines[i].trim().is_empty() || is_comment(lines[i])) {
+        i += 1;
+    }
+
+    let mut title = String::new();
+    if i + 2 < lines.len() && is_adornment(lines[i]) && lines[i + 2].trim_end() == lines[i].trim_end() {
+        title = lines[i + 1].trim().to_string();
+        i += 3;
+    } else if i + 1 < lines.len() && is_underline(lines[i], lines[i + 1]) {
+        title = lines[i].trim().to_string();
+        i += 2;
+    }
+
+    let mut fields = HashMap::new();
+    let mut j = i;
+    while j < lines.len() && lines[j].trim().is_empty() {
+        j += 1;
+    }
+    while j < lines.len() {
+        let Some(caps) = FIELD_REGEX.captures(lines[j]) else {
+            break;
+        };
+        fields.insert(caps[1].to_lowercase().replace(' ', "_"), caps[2].trim().to_string());
+        j += 1;
+        i = j;
+    }
+    while i < lines.len() && lines[i].trim().is_empty() {
+        i += 1;
+    }
+
+    Document {
+        title,
+        fields,
+        body: lines[i..].join("\n"),
+    }
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with("..") && !DIRECTIVE_REGEX.is_match(line)
+}
+
+/// A line of a single repeated punctuation character, e.g. ======
+fn is_adornment(line: &str) -> bool {
+    let line = line.trim_end();
+    match line.chars().next() {
+        Some(first) => line.len() >= 3 && ADORNMENT_CHARS.contains(first) && line.chars().all(|c| c == first),
+        None => false,
+    }
+}
+
+/// True if underline is the adornment of a section title
+fn is_underline(title: &str, underline: &str) -> bool {
+    !title.trim().is_empty() && !title.starts_with(' ') && !is_adornment(title)
+        && is_adornment(underline) && underline.trim_end().len() >= title.trim().chars().count()
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Lines after start indented more than indent, with the common indentation removed
+fn indented_block<'a>(lines: &[&'a str], start: usize, indent: usize) -> (Vec<&'a str>, usize) {
+    let mut end = start;
+    let mut i = start;
+    while i < lines.len() {
+        if lines[i].trim().is_empty() {
+            i += 1;
+        } else if indentation(lines[i]) > indent {
+            i += 1;
+            end = i;
+        } else {
+            break;
+        }
+    }
+    let block = &lines[start..end];
+    let min_indent = block.iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| indentation(l))
+        .min()
+        .unwrap_or(0);
+    let block = block.iter()
+        .map(|l| if l.len() >= min_indent { &l[min_indent..] } else { l.trim() })
+        .collect();
+    (block, end)
+}
+
+/// Converts the reStructuredText blocks to html: sections, paragraphs, lists, literal blocks, block quotes and directives
+fn to_html(src: &str, img_prefix: Option<&str>) -> String {
+    let lines: Vec<&str> = src.lines().collect();
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = vec![];
+    let mut lists: Vec<(usize, &str)> = vec![];
+    let mut in_list_item = false;
+    // Section styles in the order they appear, the first one is h2
+    let mut styles: Vec<(char, bool)> = vec![];
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_end();
+
+        if line.is_empty() {
+            let literal = flush_paragraph(&mut html, &mut paragraph, img_prefix);
+            in_list_item = false;
+            i += 1;
+            if literal {
+                let (block, end) = indented_block(&lines, i, 0);
+                html.push_str(&format!("<pre><code>{}</code></pre>\n", escape_html(block.join("\n").trim_end())));
+                i = end;
+            }
+            continue;
+        }
+
+        // Section title, with or without overline
+        let section = if is_adornment(line) && i + 2 < lines.len() && !lines[i + 1].trim().is_empty() && lines[i + 2].trim_end() == line {
+            Some((line.chars().next().unwrap(), true, lines[i + 1].trim(), 3))
+        } else if i + 1 < lines.len() && indentation(line) == 0 && is_underline(line, lines[i + 1]) {
+            Some((lines[i + 1].chars().next().unwrap(), false, line.trim(), 2))
+        } else {
+            None
+        };
+        if let Some((adornment, overline, title, line_count)) = section {
+            flush_paragraph(&mut html, &mut paragraph, img_prefix);
+            close_lists(&mut html, &mut lists);
+            let style = (adornment, overline);
+            let level = match styles.iter().position(|s| *s == style) {
+                Some(pos) => pos + 2,
+                None => {
+                    styles.push(style);
+                    styles.len() + 1
+                }
+            }.min(6);
+            html.push_str(&format!("<h{}>{}</h{}>\n", level, inline(title, img_prefix), level));
+            i += line_count;
+            continue;
+        }
+
+        if !paragraph.is_empty() {
+            paragraph.push(line.trim());
+            i += 1;
+            continue;
+        }
+
+        if let Some(caps) = LIST_REGEX.captures(line) {
+            let indent = caps[1].len();
+            let tag = if caps[2].starts_with(['-', '*', '+']) { "ul" } else { "ol" };
+            list_item(&mut html, &mut lists, indent, tag);
+            html.push_str(&inline(&caps[3], img_prefix));
+            in_list_item = true;
+            i += 1;
+            continue;
+        }
+        let indent = indentation(line);
+        if let Some((list_indent, _)) = lists.last() {
+            if indent > *list_indent {
+                html.push(' ');
+                html.push_str(&inline(line.trim(), img_prefix));
+                i += 1;
+                continue;
+            }
+        }
+        if in_list_item {
+            html.push(' ');
+            html.push_str(&inline(line.trim(), img_prefix));
+            i += 1;
+            continue;
+        }
+        close_lists(&mut html, &mut lists);
+
+        if let Some(caps) = DIRECTIVE_REGEX.captures(line) {
+            let (block, end) = indented_block(&lines, i + 1, indent);
+            html.push_str(&directive(&caps[1], caps[2].trim(), &block, img_prefix));
+            i = end;
+        } else if line.starts_with("..") {
+            // Comments, targets and the summary tag
+            let (_, end) = indented_block(&lines, i + 1, indent);
+            i = end;
+        } else if is_adornment(line) && line.len() >= 4 {
+            html.push_str("<hr />\n");
+            i += 1;
+        } else if indent > 0 {
+            let (block, end) = indented_block(&lines, i, 0);
+            html.push_str(&format!("<blockquote>\n{}</blockquote>\n", to_html(&block.join("\n"), img_prefix)));
+            i = end;
+        } else {
+            paragraph.push(line);
+            i += 1;
+        }
+    }
+
+    flush_paragraph(&mut html, &mut paragraph, img_prefix);
+    close_lists(&mut html, &mut lists);
+    html
+}
+
+/// Writes the paragraph, returning true if it ends with :: and the next block is a literal block
+fn flush_paragraph(html: &mut String, paragraph: &mut Vec<&str>, img_prefix: Option<&str>) -> bool {
+    if paragraph.is_empty() {
+        return false;
+    }
+    let text = paragraph.join("\n");
+    paragraph.clear();
+    let (text, literal) = match text.strip_suffix("::") {
+        Some(text) if text.trim().is_empty() => return true,
+        Some(text) if text.ends_with(char::is_whitespace) => (text.trim_end().to_string(), true),
+        Some(text) => (format!("{}:", text), true),
+        None => (text, false),
+    };
+    html.push_str(&format!("<p>{}</p>\n", inline(&text, img_prefix)));
+    literal
+}
+
+fn directive(name: &str, argument: &str, block: &[&str], img_prefix: Option<&str>) -> String {
+    // Options are the first lines of the block, e.g. :alt: Text
+    let mut options = HashMap::new();
+    let mut content_start = 0;
+    for line in block {
+        match FIELD_REGEX.captures(line) {
+            Some(caps) => options.insert(caps[1].to_lowercase(), caps[2].trim().to_string()),
+            None => break,
+        };
+        content_start += 1;
+    }
+    let content = block[content_start..].join("\n");
+    let content = content.trim_matches('\n');
+
+    match name {
+        "code" | "code-block" | "sourcecode" => match argument {
+            "" => format!("<pre><code>{}</code></pre>\n", escape_html(content)),
+            lang => format!("<pre><code class=\"language-{}\">{}</code></pre>\n", escape_html(lang), escape_html(content)),
+        },
+        "image" | "figure" => {
+            let alt = options.get("alt").map_or("", |a| a.as_str());
+            let img = format!("<img src=\"{}\" alt=\"{}\" />", image_src(img_prefix, argument), escape_html(alt));
+            if name == "figure" && !content.is_empty() {
+                format!("<figure>{}<figcaption>{}</figcaption></figure>\n", img, inline(content, img_prefix))
+            } else {
+                format!("<p>{}</p>\n", img)
+            }
+        }
+        "note" | "tip" | "hint" | "important" | "warning" | "caution" | "attention" | "danger" | "error" => {
+            let text = if argument.is_empty() { content.to_string() } else { format!("{}\n{}", argument, content) };
+            format!("<div class=\"admonition {}\">\n{}</div>\n", name, to_html(&text, img_prefix))
+        }
+        "raw" if argument == "html" => format!("{}\n", content),
+        _ => "".to_string(),
+    }
+}
+
+/// Opens a list item, opening or closing the lists to reach its indentation
+fn list_item(html: &mut String, lists: &mut Vec<(usize, &'static str)>, indent: usize, tag: &'static str) {
+    while let Some((list_indent, list_tag)) = lists.last() {
+        if *list_indent <= indent {
+            break;
+        }
+        html.push_str(&format!("</li>\n</{}>\n", list_tag));
+        lists.pop();
+    }
+    match lists.last() {
+        Some((list_indent, list_tag)) if *list_indent == indent => {
+            html.push_str("</li>\n");
+            if *list_tag != tag {
+                html.push_str(&format!("</{}>\n<{}>\n", list_tag, tag));
+                lists.pop();
+                lists.push((indent, tag));
+            }
+        }
+        _ => {
+            html.push_str(&format!("<{}>\n", tag));
+            lists.push((indent, tag));
+        }
+    }
+    html.push_str("<li>");
+}
+
+fn close_lists(html: &mut String, lists: &mut Vec<(usize, &str)>) {
+    while let Some((_, tag)) = lists.pop() {
+        html.push_str(&format!("</li>\n</{}>\n", tag));
+    }
+}
+
+/// Inline markup. The text of inline literals is not formatted
+fn inline(text: &str, img_prefix: Option<&str>) -> String {
+    let mut html = String::new();
+    let mut last = 0;
+    for caps in CODE_REGEX.captures_iter(text) {
+        let code = caps.get(0).unwrap();
+        html.push_str(&format_text(&text[last..code.start()], img_prefix));
+        html.push_str(&format!("<code>{}</code>", escape_html(&caps[1])));
+        last = code.end();
+    }
+    html.push_str(&format_text(&text[last..], img_prefix));
+    html
+}
+
+fn format_text(text: &str, _img_prefix: Option<&str>) -> String {
+    let text = escape_html(text);
+    let text = LINK_REGEX.replace_all(&text, "<a href=\"$2\">$1</a>");
+    let text = REFERENCE_REGEX.replace_all(&text, "$1");
+    let text = ROLE_REGEX.replace_all(&text, |caps: &Captures| match &caps[1] {
+        "code" | "literal" => format!("<code>{}</code>", &caps[2]),
+        _ => caps[2].to_string(),
+    });
+    let text = link_urls(&text);
+    let text = STRONG_REGEX.replace_all(&text, "<strong>$1</strong>");
+    let text = EMPHASIS_REGEX.replace_all(&text, "<em>$1</em>");
+    let text = INTERPRETED_REGEX.replace_all(&text, "<em>$1</em>");
+    text.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::content::content_format::ContentFormat;
+    use crate::content::content_renderer::{BreakTag, ImagePrefix, MaxLineCount, PreviewOptions};
+
+    const POST_DATA_RST: &str = r#"=========================
+Writing reStructuredText
+=========================
+
+:Author: Thiago Guedes
+:Date: 2024-05-01 10:00:00
+:Tags: rst, tutorial
+
+Texted renders **reStructuredText** posts, with ``code`` and *emphasis*.
+See `the spec <https://docutils.sourceforge.io/rst.html>`_ for the syntax.
+
+.. <!-- more -->
+
+Lists
+-----
+
+- First
+
+  - Nested
+
+- Second
+
+1. One
+2. Two
+
+Example::
+
+    fn main() {}
+
+.. code-block:: rust
+
+   println!("<hi>");
+
+.. note:: Admonitions are supported.
+
+.. image:: dino.jpg
+   :alt: Dino
+"#;
+
+    fn content_file(raw_content: &str) -> ContentFile {
+        ContentFile {
+            link: "rst".to_string(),
+            file_path: PathBuf::from("res/posts/new_post.md"),
+            format: ContentFormat::ReStructuredText,
+            raw_content: raw_content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_document_header() {
+        let content = RstRenderer {}.render(&content_file(POST_DATA_RST), RenderOptions::FullContent).unwrap();
+        assert_eq!(content.title, "Writing reStructuredText");
+        assert_eq!(content.header.author, "Thiago Guedes");
+        assert_eq!(content.header.tags, vec!["rst", "tutorial"]);
+        assert_eq!(content.header.date.to_string(), "2024-05-01 10:00:00");
+
+        let texted_header = ".. [ID]: # (abc)\n.. [DATE]: # (2024-02-12 22:54:00.000)\n\nTitle\n=====\n\nText\n";
+        let content = RstRenderer {}.render(&content_file(texted_header), RenderOptions::FullContent).unwrap();
+        assert_eq!(content.title, "Title");
+        assert_eq!(content.header.id.0, "abc");
+        assert_eq!(content.rendered, "<p>Text</p>\n");
+    }
+
+    #[test]
+    fn test_full_content() {
+        let content = RstRenderer {}.render(&content_file(POST_DATA_RST), RenderOptions::FullContent).unwrap();
+        assert_eq!(content.rendered, r#"<p>Texted renders <strong>reStructuredText</strong> posts, with <code>code</code> and <em>emphasis</em>.
+See <a href="https://docutils.sourceforge.io/rst.html">the spec</a> for the syntax.</p>
+<h2>Lists</h2>
+<ul>
+<li>First<ul>
+<li>Nested</li>
+</ul>
+</li>
+<li>Second</li>
+</ul>
+<ol>
+<li>One</li>
+<li>Two</li>
+</ol>
+<p>Example:</p>
+<pre><code>fn main() {}</code></pre>
+<pre><code class="language-rust">println!(&quot;&lt;hi&gt;&quot;);</code></pre>
+<div class="admonition note">
+<p>Admonitions are supported.</p>
+</div>
+<p><img src="dino.jpg" alt="Dino" /></p>
+"#);
+    }
+
+    #[test]
+    fn test_preview() {
+        let preview_opt = PreviewOptions { max_line_count: None, tag_based: BreakTag("<!-- more -->".to_string()) };
+        let options = RenderOptions::PreviewOnly(preview_opt, ImagePrefix("/view/rst".to_string()));
+        let content = RstRenderer {}.render(&content_file(POST_DATA_RST), options).unwrap();
+        assert_eq!(content.rendered, r#"<p>Texted renders <strong>reStructuredText</strong> posts, with <code>code</code> and <em>emphasis</em>.
+See <a href="https://docutils.sourceforge.io/rst.html">the spec</a> for the syntax.</p>
+"#);
+
+        let preview_opt = PreviewOptions { max_line_count: Some(MaxLineCount(1)), tag_based: BreakTag("<!-- more -->".to_string()) };
+        let options = RenderOptions::PreviewOnly(preview_opt, ImagePrefix("/view/rst".to_string()));
+        let content = RstRenderer {}.render(&content_file("Title\n=====\n\n.. image:: dino.jpg\n\nText"), options).unwrap();
+        assert_eq!(content.rendered, "<p><img src=\"/view/rst/dino.jpg\" alt=\"\" /></p>\n");
+    }
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(inline("a *b* and **c** ``*d*``", None), "a <em>b</em> and <strong>c</strong> <code>*d*</code>");
+        assert_eq!(inline(":code:`x < y` and `Title`_", None), "<code>x &lt; y</code> and Title");
+    }
+}
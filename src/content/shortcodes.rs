@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+use std::{fs, io};
+
+use lazy_static::lazy_static;
+use ramhorns::Template;
+use regex::{Captures, Regex};
+use spdlog::warn;
+
+use crate::content::content_file::ContentFile;
+use crate::content::parsing_utils::{escape_html, image_src};
+
+lazy_static! {
+    static ref SHORTCODE_REGEX: Regex = Regex::new(r"\{\{<\s*([\w-]+)(.*?)\s*>\}\}").unwrap();
+    static ref ESCAPED_REGEX: Regex = Regex::new(r"\{\{</\*(.*?)\*/>\}\}").unwrap();
+    static ref INCLUDE_REGEX: Regex = Regex::new(r"\{\{<\s*include\s+(.*?)\s*>\}\}").unwrap();
+    static ref ARG_REGEX: Regex = Regex::new(r#"(?:([\w-]+)=)?(?:"([^"]*)"|(\S+))"#).unwrap();
+    static ref YOUTUBE_ID_REGEX: Regex = Regex::new(r"^[\w-]+$").unwrap();
+    static ref GIST_REGEX: Regex = Regex::new(r"^[\w-]+/\w+$").unwrap();
+    static ref PLACEHOLDER_REGEX: Regex = Regex::new(r"\x{fffc}\d+\x{fffc}").unwrap();
+}
+
+/// Directory of the user shortcodes, inside each template directory
+pub const SHORTCODE_DIR: &str = "shortcodes";
+/// Surrounds the number of an expanded shortcode until the content is rendered. E.g. markdown would escape its html
+const PLACEHOLDER: char = '\u{fffc}';
+
+/// Shortcodes of posts and pages, e.g. `{{< youtube id >}}` or `{{< figure src="dino.jpg" caption="A dino" >}}`.
+/// Built-in shortcodes are youtube, gist, figure and include. User shortcodes are mustache templates in
+/// template_dir/shortcodes/, e.g. `shortcodes/note.tpl` for `{{< note "Be careful" >}}`, receiving the
+/// positional arguments as `{{arg0}}`, `{{arg1}}`... and the named ones by their name
+#[derive(Default)]
+pub struct Shortcodes {
+    templates: RwLock<HashMap<String, Template<'static>>>,
+}
+
+/// Content file with the shortcodes replaced by placeholders and the html of each placeholder
+pub struct ExpandedContent {
+    pub content_file: ContentFile,
+    html: Vec<String>,
+}
+
+struct Args {
+    positional: Vec<String>,
+    named: HashMap<String, String>,
+}
+
+impl Shortcodes {
+    /// Loads the user shortcodes of template_dirs, the first directories taking precedence
+    pub fn from_dirs(template_dirs: &[PathBuf]) -> io::Result<Self> {
+        let shortcodes = Shortcodes::default();
+        shortcodes.reload(template_dirs)?;
+        Ok(shortcodes)
+    }
+
+    /// Loads the user shortcodes again, e.g. when the templates change
+    pub fn reload(&self, template_dirs: &[PathBuf]) -> io::Result<()> {
+        let mut templates = HashMap::new();
+        for template_dir in template_dirs.iter().rev() {
+            let dir = template_dir.join(SHORTCODE_DIR);
+            if !dir.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.extension().is_none_or(|ext| ext != "tpl") {
+                    continue;
+                }
+                let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+                let template = match Template::new(fs::read_to_string(&path)?) {
+                    Ok(template) => template,
+                    Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Error parsing shortcode {}: {}", path.to_str().unwrap(), e))),
+                };
+                templates.insert(name, template);
+            }
+        }
+        *self.templates.write().unwrap() = templates;
+        Ok(())
+    }
+
+    /// Includes the files of `{{< include file >}}` and replaces the other shortcodes by placeholders, to be replaced
+    /// after the content is rendered. img_prefix is the prefix of relative images, e.g. in the post list
+    pub fn expand(&self, content_file: &ContentFile, img_prefix: Option<&str>) -> ExpandedContent {
+        let raw_content = INCLUDE_REGEX.replace_all(&content_file.raw_content, |caps: &Captures| {
+            match include(&content_file.file_path, &caps[1]) {
+                Ok(text) => text,
+                Err(e) => {
+                    warn!("Error including {} in {}: {}", &caps[1], content_file.link, e);
+                    caps[0].to_string()
+                }
+            }
+        });
+
+        let mut html = vec![];
+        let raw_content = SHORTCODE_REGEX.replace_all(&raw_content, |caps: &Captures| {
+            let args = Args::parse(&caps[2]);
+            match self.render(&caps[1], &args, &content_file.link, img_prefix) {
+                Some(rendered) => {
+                    html.push(rendered);
+                    format!("{}{}{}", PLACEHOLDER, html.len() - 1, PLACEHOLDER)
+                }
+                None => {
+                    warn!("Unknown shortcode {} in {}", &caps[1], content_file.link);
+                    caps[0].to_string()
+                }
+            }
+        });
+        // {{</* name */>}} is written as {{< name >}}
+        let raw_content = ESCAPED_REGEX.replace_all(&raw_content, "{{<$1>}}");
+
+        ExpandedContent {
+            content_file: ContentFile {
+                link: content_file.link.clone(),
+                file_path: content_file.file_path.clone(),
+                format: content_file.format.clone(),
+                raw_content: raw_content.to_string(),
+            },
+            html,
+        }
+    }
+
+    /// User shortcodes override the built-in ones
+    fn render(&self, name: &str, args: &Args, link: &str, img_prefix: Option<&str>) -> Option<String> {
+        if let Some(template) = self.templates.read().unwrap().get(name) {
+            let mut values = args.named.clone();
+            for (i, arg) in args.positional.iter().enumerate() {
+                values.insert(format!("arg{}", i), arg.clone());
+            }
+            values.insert("post".to_string(), link.to_string());
+            return Some(template.render(&values));
+        }
+
+        let html = match name {
+            "youtube" => {
+                let id = args.get("id", 0).unwrap_or("");
+                if !YOUTUBE_ID_REGEX.is_match(id) {
+                    return Some(error_html(name, "invalid video id"));
+                }
+                format!(r#"<div class="shortcode youtube"><iframe src="https://www.youtube-nocookie.com/embed/{}" title="{}" loading="lazy" allowfullscreen></iframe></div>"#,
+                        id, escape_html(args.get("title", 1).unwrap_or("YouTube video")))
+            }
+            "gist" => {
+                let gist = args.get("id", 0).unwrap_or("");
+                if !GIST_REGEX.is_match(gist) {
+                    return Some(error_html(name, "expected user/id"));
+                }
+                match args.get("file", 1) {
+                    Some(file) => format!(r#"<script src="https://gist.github.com/{}.js?file={}"></script>"#, gist, escape_html(file)),
+                    None => format!(r#"<script src="https://gist.github.com/{}.js"></script>"#, gist),
+                }
+            }
+            "figure" => {
+                let Some(src) = args.get("src", 0) else {
+                    return Some(error_html(name, "src is missing"));
+                };
+                let caption = args.get("caption", 1).unwrap_or("");
+                let alt = args.get("alt", 2).unwrap_or(caption);
+                let img = format!(r#"<img src="{}" alt="{}" />"#, escape_html(&image_src(img_prefix, src)), escape_html(alt));
+                match caption {
+                    "" => format!("<figure>{}</figure>", img),
+                    caption => format!("<figure>{}<figcaption>{}</figcaption></figure>", img, escape_html(caption)),
+                }
+            }
+            _ => return None,
+        };
+        Some(html)
+    }
+}
+
+impl ExpandedContent {
+    /// Replaces the placeholders of the rendered html. A placeholder alone in a paragraph replaces the paragraph
+    pub fn replace_placeholders(&self, rendered: &str) -> String {
+        let mut rendered = rendered.to_string();
+        for (i, html) in self.html.iter().enumerate() {
+            let placeholder = format!("{}{}{}", PLACEHOLDER, i, PLACEHOLDER);
+            rendered = rendered.replace(&format!("<p>{}</p>", placeholder), html)
+                .replace(&placeholder, html);
+        }
+        rendered
+    }
+}
+
+/// Text without the placeholders of the expanded shortcodes, e.g. to count its words
+pub fn remove_placeholders(text: &str) -> String {
+    PLACEHOLDER_REGEX.replace_all(text, " ").to_string()
+}
+
+impl Args {
+    /// Arguments are separated by spaces. Values with spaces are quoted, e.g. `dino.jpg caption="A dino"`
+    fn parse(text: &str) -> Self {
+        let mut args = Args { positional: vec![], named: HashMap::new() };
+        for caps in ARG_REGEX.captures_iter(text) {
+            let value = caps.get(2).or(caps.get(3)).unwrap().as_str().to_string();
+            match caps.get(1) {
+                Some(name) => args.named.insert(name.as_str().to_string(), value),
+                None => {
+                    args.positional.push(value);
+                    None
+                }
+            };
+        }
+        args
+    }
+
+    /// Named argument or, if not set, the positional argument
+    fn get(&self, name: &str, position: usize) -> Option<&str> {
+        self.named.get(name).or(self.positional.get(position)).map(|v| v.as_str())
+    }
+}
+
+/// Text of a file relative to the directory of the post. Files outside it cannot be included
+fn include(post_file: &Path, file: &str) -> io::Result<String> {
+    let file = file.trim_matches('"');
+    let relative = Path::new(file);
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "Only files in the post directory can be included"));
+    }
+    let dir = post_file.parent().unwrap_or(Path::new("."));
+    fs::read_to_string(dir.join(relative))
+}
+
+fn error_html(name: &str, error: &str) -> String {
+    format!(r#"<span class="shortcode-error">Shortcode {}: {}</span>"#, name, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::content_format::ContentFormat;
+    use crate::test_data::temp_dir;
+
+    fn content_file(raw_content: &str) -> ContentFile {
+        ContentFile {
+            link: "post_with_image".to_string(),
+            file_path: PathBuf::from("res/posts/post_with_image/index.md"),
+            format: ContentFormat::Texted,
+            raw_content: raw_content.to_string(),
+        }
+    }
+
+    fn expand(shortcodes: &Shortcodes, raw_content: &str, img_prefix: Option<&str>) -> String {
+        let expanded = shortcodes.expand(&content_file(raw_content), img_prefix);
+        expanded.replace_placeholders(&expanded.content_file.raw_content)
+    }
+
+    #[test]
+    fn test_builtin() {
+        let shortcodes = Shortcodes::default();
+        assert_eq!(expand(&shortcodes, "{{< youtube dQw4w9WgXcQ >}}", None),
+                   r#"<div class="shortcode youtube"><iframe src="https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ" title="YouTube video" loading="lazy" allowfullscreen></iframe></div>"#);
+        assert_eq!(expand(&shortcodes, "{{< youtube \"x\\\" onload=\" >}}", None),
+                   r#"<span class="shortcode-error">Shortcode youtube: invalid video id</span>"#);
+        assert_eq!(expand(&shortcodes, "{{<gist thiagomg/abc123 main.rs>}}", None),
+                   r#"<script src="https://gist.github.com/thiagomg/abc123.js?file=main.rs"></script>"#);
+        assert_eq!(expand(&shortcodes, r#"{{< figure dino.jpg caption="A <b>dino</b>" >}}"#, Some("/view/post_with_image")),
+                   r#"<figure><img src="/view/post_with_image/dino.jpg" alt="A &lt;b&gt;dino&lt;/b&gt;" /><figcaption>A &lt;b&gt;dino&lt;/b&gt;</figcaption></figure>"#);
+        assert_eq!(expand(&shortcodes, "{{< unknown 1 >}} and {{</* youtube id */>}}", None), "{{< unknown 1 >}} and {{< youtube id >}}");
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let shortcodes = Shortcodes::default();
+        let expanded = shortcodes.expand(&content_file("Intro\n\n{{< gist a/1 >}}\n\nText {{< gist b/2 >}}"), None);
+        assert!(!expanded.content_file.raw_content.contains("{{<"));
+        let rendered = markdown::to_html(&expanded.content_file.raw_content);
+        assert_eq!(expanded.replace_placeholders(&rendered), r#"<p>Intro</p>
+<script src="https://gist.github.com/a/1.js"></script>
+<p>Text <script src="https://gist.github.com/b/2.js"></script></p>"#);
+    }
+
+    #[test]
+    fn test_include_and_user_shortcodes() {
+        let dir = temp_dir("shortcodes");
+        fs::create_dir_all(dir.join("template").join(SHORTCODE_DIR)).unwrap();
+        fs::create_dir_all(dir.join("theme").join(SHORTCODE_DIR)).unwrap();
+        fs::write(dir.join("template/shortcodes/note.tpl"), r#"<div class="note {{kind}}">{{arg0}}</div>"#).unwrap();
+        fs::write(dir.join("theme/shortcodes/note.tpl"), "theme note").unwrap();
+        fs::write(dir.join("theme/shortcodes/youtube.tpl"), "video {{arg0}} in {{post}}").unwrap();
+        fs::write(dir.join("snippet.md"), "Snippet {{< note \"included\" >}}").unwrap();
+
+        let shortcodes = Shortcodes::from_dirs(&[dir.join("template"), dir.join("theme")]).unwrap();
+        let file = ContentFile { file_path: dir.join("post.md"), ..content_file("") };
+        let expand_file = |raw: &str| {
+            let expanded = shortcodes.expand(&ContentFile { raw_content: raw.to_string(), ..content_file("") }, None);
+            expanded.replace_placeholders(&expanded.content_file.raw_content)
+        };
+        assert_eq!(expand_file(r#"{{< note "<Be careful>" kind=warning >}}"#), r#"<div class="note warning">&lt;Be careful&gt;</div>"#);
+        assert_eq!(expand_file("{{< youtube abc >}}"), "video abc in post_with_image");
+
+        let expanded = shortcodes.expand(&ContentFile { raw_content: "{{< include snippet.md >}}".to_string(), ..file }, None);
+        assert_eq!(expanded.replace_placeholders(&expanded.content_file.raw_content), r#"Snippet <div class="note ">included</div>"#);
+        assert!(include(&dir.join("post.md"), "../snippet.md").is_err());
+        assert!(include(&dir.join("post.md"), "/etc/hostname").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
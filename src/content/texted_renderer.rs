@@ -3,41 +3,48 @@ use std::io::ErrorKind;
 use std::path::PathBuf;
 use std::str::Lines;
 
-use markdown::Options;
-
 use crate::content::content_file::ContentFile;
-use crate::content::content_format::ContentFormat;
-use crate::content::content_renderer::RenderOptions;
-use crate::content::parsing_utils::{extract_content, generate_header_from_file, parse_texted_header, parse_title_markdown, remove_comments};
+use crate::content::content_renderer::{ContentRenderer, RenderOptions};
+use crate::content::markdown_extensions::MarkdownOptions;
+use crate::content::parsing_utils::{count_words_markdown, extract_content, generate_header_from_file, parse_texted_header, parse_title_markdown, reading_time, remove_comments, texted_header_value};
 use crate::content::{Content, ContentHeader};
 
-pub struct TextedRenderer {}
-
-impl TextedRenderer {
-    pub fn render(content_file: &ContentFile, render_options: RenderOptions) -> io::Result<Content> {
-        if content_file.format != ContentFormat::Texted {
-            return Err(io::Error::new(ErrorKind::InvalidData, format!("Unsupported format: {:?}", content_file.format)));
-        }
+#[derive(Default)]
+pub struct TextedRenderer {
+    /// Markdown extensions of the configuration. Each post can override them with the MARKDOWN header
+    pub options: MarkdownOptions,
+}
 
+impl ContentRenderer for TextedRenderer {
+    fn render(&self, content_file: &ContentFile, render_options: RenderOptions) -> io::Result<Content> {
         let link = content_file.link.clone();
         let (header, lines, maybe_line) = Self::parse_markdown_header(&content_file.file_path, content_file.raw_content.lines())?;
         let (title, lines, _title_line) = parse_title_markdown(lines, maybe_line);
+        let word_count = count_words_markdown(lines.clone());
         let content = extract_content(lines, &render_options);
 
         let prefix: Option<&str> = match render_options {
             RenderOptions::PreviewOnly(ref _preview_opt, ref img_prefix) => Some(img_prefix.0.as_str()),
             RenderOptions::FullContent => None,
         };
-        let rendered = Self::render_markdown(&content, prefix)?;
+        let options = match texted_header_value(content_file.raw_content.lines(), "MARKDOWN") {
+            Some(overrides) => self.options.with_overrides(overrides),
+            None => self.options.clone(),
+        };
+        let rendered = Self::render_markdown(&content, prefix, &options)?;
 
         Ok(Content {
             header,
             link,
             title,
             rendered,
+            word_count,
+            reading_time: reading_time(word_count),
         })
     }
+}
 
+impl TextedRenderer {
     pub fn parse_markdown_header<'a>(file_name: &PathBuf, lines: Lines<'a>) -> io::Result<(ContentHeader, Lines<'a>, Option<&'a str>)> {
         let lines_clone = lines.clone();
         match parse_texted_header(file_name, lines) {
@@ -53,15 +60,15 @@ impl TextedRenderer {
     }
     // parse_texted_header
 
-    fn render_markdown(md_text: &str, img_prefix: Option<&str>) -> io::Result<String> {
+    pub fn render_markdown(md_text: &str, img_prefix: Option<&str>, options: &MarkdownOptions) -> io::Result<String> {
         let buf = remove_comments(md_text)?;
         let buf = if let Some(img_prefix) = img_prefix {
             Self::change_images(img_prefix, buf.as_str())
         } else {
             buf
         };
-        match markdown::to_html_with_options(buf.as_str(), &Options::gfm()) {
-            Ok(x) => Ok(x),
+        match markdown::to_html_with_options(buf.as_str(), &options.parse_options()) {
+            Ok(x) => Ok(options.apply(x)),
             Err(e) => Err(io::Error::new(ErrorKind::InvalidInput, e.reason.as_str())),
         }
     }
@@ -118,6 +125,7 @@ impl TextedRenderer {
 mod tests {
     use std::path::PathBuf;
 
+    use crate::content::content_format::ContentFormat;
     use crate::content::content_renderer::{BreakTag, ImagePrefix, PreviewOptions};
     use crate::test_data::POST_DATA_MD;
 
@@ -133,9 +141,9 @@ mod tests {
             raw_content: POST_DATA_MD.to_string(),
         };
 
-        let prefix = ImagePrefix { 0: "image/".to_string() };
+        let prefix = ImagePrefix("image/".to_string());
         let preview_opt = PreviewOptions { max_line_count: None, tag_based: BreakTag("<!-- more -->".to_string()) };
-        let content = TextedRenderer::render(&content, RenderOptions::PreviewOnly(preview_opt, prefix)).unwrap();
+        let content = TextedRenderer::default().render(&content, RenderOptions::PreviewOnly(preview_opt, prefix)).unwrap();
         assert_eq!(content.rendered, r##"<p>How to be a great software engineer?</p>
 <p>Someone asked me this question today and I didn’t have an answer. After thinking for a while, I came up with a list of what I try to do myself.</p>
 <p>Disclaimer: I don't think I am a great engineer, but I would love to have listened to that myself when I started my career, over 20 years ago.</p>
@@ -152,7 +160,7 @@ mod tests {
             format: ContentFormat::Texted,
             raw_content: POST_DATA_MD.to_string(),
         };
-        let content = TextedRenderer::render(&content, RenderOptions::FullContent).unwrap();
+        let content = TextedRenderer::default().render(&content, RenderOptions::FullContent).unwrap();
         assert_eq!(content.rendered, r##"<p>How to be a great software engineer?</p>
 <p>Someone asked me this question today and I didn’t have an answer. After thinking for a while, I came up with a list of what I try to do myself.</p>
 <p>Disclaimer: I don't think I am a great engineer, but I would love to have listened to that myself when I started my career, over 20 years ago.</p>
@@ -164,4 +172,18 @@ It's common to think you are awesome and the smartest person in the planet.
 Some day in your life, you will find that you are not and that there are many developers much better than you. Not in capacity, but in wisdom and knowledge. <strong>The earlier you find that, the better.</strong> This will drive you to improve yourself as you now recognize better your weakest points.</p>
 "##)
     }
+
+    #[test]
+    fn test_markdown_header_overrides() {
+        let content = ContentFile {
+            link: "".to_string(),
+            file_path: PathBuf::from("posts/20200522_how_to_write_a_code_review/index.md"),
+            format: ContentFormat::Texted,
+            raw_content: "[ID]: # (1)\n[DATE]: # (2024-02-12 22:54:00.000)\n[AUTHOR]: # (thiago)\n[MARKDOWN]: # (heading_ids=on)\n\n# Title\n\n## Intro\n\nIt's here".to_string(),
+        };
+        let renderer = TextedRenderer { options: MarkdownOptions::default().with_overrides("smart_punctuation=on") };
+        let content = renderer.render(&content, RenderOptions::FullContent).unwrap();
+        assert_eq!(content.rendered, "<h2 id=\"intro\">Intro</h2>\n<p>It\u{2019}s here</p>\n");
+        assert_eq!(content.title, "Title");
+    }
 }
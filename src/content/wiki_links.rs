@@ -0,0 +1,130 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use spdlog::warn;
+
+use crate::content::content_file::ContentFile;
+use crate::content::content_renderer::RenderOptions;
+use crate::content::format_registry::FormatRegistry;
+use crate::content::html_utils::{outside_code, remove_code};
+
+lazy_static! {
+    static ref WIKI_LINK_REGEX: Regex = Regex::new(r"\[\[([^\[\]|]+)(?:\|([^\[\]]+))?\]\]").unwrap();
+}
+
+/// Targets of the wiki links `[[slug]]` and `[[slug|label]]`, resolved to posts first and then to pages,
+/// and the posts linking to each target
+#[derive(Debug, Default)]
+pub struct WikiLinks {
+    posts: HashSet<String>,
+    pages: HashSet<String>,
+    /// Posts linking to a post or page, by target slug
+    backlinks: HashMap<String, Vec<String>>,
+}
+
+impl WikiLinks {
+    /// Renders the posts with formats to build the backlinks. Posts that cannot be rendered are ignored
+    pub fn new(post_links: &HashMap<String, PathBuf>, page_links: &HashMap<String, PathBuf>, formats: &FormatRegistry) -> Self {
+        let mut wiki_links = WikiLinks {
+            posts: post_links.keys().cloned().collect(),
+            pages: page_links.keys().cloned().collect(),
+            backlinks: HashMap::new(),
+        };
+        for (post_link, post_path) in post_links.iter() {
+            let content = ContentFile::from_file(post_link.clone(), post_path.clone())
+                .and_then(|content_file| formats.render(&content_file, RenderOptions::FullContent));
+            if let Ok(content) = content {
+                wiki_links.add_backlinks(post_link, &content.rendered);
+            }
+        }
+        wiki_links.backlinks.values_mut().for_each(|links| links.sort());
+        wiki_links
+    }
+
+    /// Targets of the wiki links in the rendered html of post_link. As in resolve, links in code are skipped
+    fn add_backlinks(&mut self, post_link: &str, html: &str) {
+        let html = remove_code(html, " ");
+        let targets: HashSet<&str> = WIKI_LINK_REGEX.captures_iter(&html)
+            .map(|caps| caps.get(1).unwrap().as_str().trim())
+            .filter(|target| *target != post_link && self.url(target).is_some())
+            .collect();
+        for target in targets {
+            self.backlinks.entry(target.to_string()).or_default().push(post_link.to_string());
+        }
+    }
+
+    /// Url of the post or page slug, if it exists
+    pub fn url(&self, slug: &str) -> Option<String> {
+        if self.posts.contains(slug) {
+            Some(format!("/view/{}/", slug))
+        } else if self.pages.contains(slug) {
+            Some(format!("/page/{}/", slug))
+        } else {
+            None
+        }
+    }
+
+    /// Posts linking to slug, sorted by link
+    pub fn backlinks(&self, slug: &str) -> &[String] {
+        self.backlinks.get(slug).map_or(&[], |links| links.as_slice())
+    }
+
+    /// Replaces the wiki links of the rendered html of source, except in code. Broken links are logged and
+    /// rendered as `<span class="wiki-link broken">`
+    pub fn resolve(&self, html: &str, source: &str) -> String {
+        outside_code(html, |text| self.resolve_text(text, source))
+    }
+
+    fn resolve_text(&self, text: &str, source: &str) -> String {
+        WIKI_LINK_REGEX.replace_all(text, |caps: &Captures| {
+            let slug = caps[1].trim();
+            let label = caps.get(2).map_or(slug, |label| label.as_str().trim());
+            match self.url(slug) {
+                Some(url) => format!("<a class=\"wiki-link\" href=\"{}\">{}</a>", url, label),
+                None => {
+                    warn!("Broken wiki link [[{}]] in {}", slug, source);
+                    format!("<span class=\"wiki-link broken\" title=\"Broken link: {}\">{}</span>", slug, label)
+                }
+            }
+        }).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wiki_links() -> WikiLinks {
+        let post_links: HashMap<String, PathBuf> = ["rust_intro", "rust_traits", "web"].iter()
+            .map(|link| (link.to_string(), PathBuf::from(format!("/not-found/{}.md", link))))
+            .collect();
+        let page_links = HashMap::from([("about".to_string(), PathBuf::from("/not-found/about.md"))]);
+        let mut wiki_links = WikiLinks::new(&post_links, &page_links, &FormatRegistry::default());
+        wiki_links.add_backlinks("rust_traits", "<p>See [[rust_intro]], [[rust_intro|the intro]] and [[missing]]</p>");
+        wiki_links.add_backlinks("web", "<p>See [[rust_intro]], [[web]] and [[about]]</p><pre><code>[[rust_traits]]</code></pre>");
+        wiki_links
+    }
+
+    #[test]
+    fn test_resolve() {
+        let wiki_links = wiki_links();
+        assert_eq!(wiki_links.resolve("<p>See [[rust_intro]] and [[ about | me ]]</p>", "web"),
+                   r#"<p>See <a class="wiki-link" href="/view/rust_intro/">rust_intro</a> and <a class="wiki-link" href="/page/about/">me</a></p>"#);
+        assert_eq!(wiki_links.resolve("<p>[[missing|Missing]]</p>", "web"),
+                   r#"<p><span class="wiki-link broken" title="Broken link: missing">Missing</span></p>"#);
+        assert_eq!(wiki_links.resolve("<pre><code>[[web]]</code></pre><code>[[web]]</code>", "web"),
+                   "<pre><code>[[web]]</code></pre><code>[[web]]</code>");
+    }
+
+    #[test]
+    fn test_backlinks() {
+        let wiki_links = wiki_links();
+        assert_eq!(wiki_links.backlinks("rust_intro"), &["rust_traits", "web"]);
+        assert_eq!(wiki_links.backlinks("about"), &["web"]);
+        assert!(wiki_links.backlinks("web").is_empty());
+        assert!(wiki_links.backlinks("rust_traits").is_empty());
+        assert!(wiki_links.backlinks("missing").is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 
 use chrono::{DateTime, Duration, Utc};
@@ -6,6 +7,17 @@ use chrono::{DateTime, Duration, Utc};
 pub struct ContentCache<T> {
     cache: Option<CacheMap<T>>,
     lock: RwLock<i32>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Lookups and size of a cache, for the server metrics
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Number of cached values, including the expired ones not yet replaced
+    pub size: usize,
 }
 
 type CacheMap<T> = HashMap<String, CacheValue<T>>;
@@ -27,6 +39,8 @@ impl<T> ContentCache<T> {
         ContentCache {
             cache,
             lock,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
         }
     }
 
@@ -36,6 +50,8 @@ impl<T> ContentCache<T> {
         ContentCache {
             cache,
             lock,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
         }
     }
 
@@ -82,7 +98,30 @@ impl<T> ContentCache<T> {
         self.add(key, content, expire_after)
     }
 
+    /// Removes all cached values. E.g. when templates change
+    pub fn clear(&mut self) {
+        if let Some(ref mut cache) = self.cache {
+            let _lock = self.lock.write().unwrap();
+            cache.clear();
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            size: self.cache.as_ref().map_or(0, |cache| cache.len()),
+        }
+    }
+
     pub fn get(&self, key: &str) -> Option<Arc<T>> {
+        let value = self.lookup(key);
+        let counter = if value.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    fn lookup(&self, key: &str) -> Option<Arc<T>> {
         if let Some(ref cache) = self.cache {
             let _reader = self.lock.read().unwrap();
             if let Some(cache_value) = cache.get(key) {
@@ -163,6 +202,17 @@ mod tests {
         assert_eq!(retrieved_content.as_ref(), &content);
     }
 
+    #[test]
+    fn test_clear() {
+        let mut cache = ContentCache::new();
+        cache.add_post("test-post", "Post content".to_string(), Expire::Never);
+        cache.add_page("test-page", "Page content".to_string(), Expire::Never);
+
+        cache.clear();
+        assert!(cache.get_post("test-post").is_none());
+        assert!(cache.get_page("test-page").is_none());
+    }
+
     #[test]
     fn test_get_nonexistent_key() {
         let cache: ContentCache<String> = ContentCache::new();
@@ -180,4 +230,18 @@ mod tests {
 
         assert!(cache.get_post(link).is_none());
     }
+
+    #[test]
+    fn test_stats() {
+        let mut cache = ContentCache::new();
+        cache.add_post("test-post", "Post content".to_string(), Expire::Never);
+        cache.get_post("test-post");
+        cache.get_post("test-post");
+        cache.get_page("test-post");
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 1, size: 1 });
+
+        let cache: ContentCache<String> = ContentCache::non_caching();
+        cache.get_post("test-post");
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 1, size: 0 });
+    }
 }
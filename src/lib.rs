@@ -1,16 +1,22 @@
 pub mod config;
+mod authors;
 pub mod server;
-mod post_list;
+pub mod post_list;
 mod test_data;
 mod text_utils;
 mod post_processor;
 mod query_string;
 mod paginator;
-mod content;
+mod post_navigation;
+mod series;
+pub mod content;
 mod view;
 pub mod post_render;
 pub mod util;
 mod content_cache;
 pub mod logger;
 mod metrics;
+pub mod theme;
+mod api;
+mod client_address;
 
@@ -10,6 +10,8 @@ pub struct EventSlot {
     pub value: String,
     pub unique_total: u64,
     pub total: u64,
+    /// Empty when the origins are not stored
+    #[serde(skip_serializing_if = "HashSet::is_empty")]
     pub origins: HashSet<String>,
     pub stats_date_start: DateTime<Utc>,
     pub stats_date_end: DateTime<Utc>,
@@ -35,7 +37,7 @@ impl EventSlot {
     }
 
     pub fn key_from(event: &Event) -> String {
-        let (key, value) = Self::get_key_val(&event);
+        let (key, value) = Self::get_key_val(event);
         format!("{}={}", key, value)
     }
 
@@ -47,6 +49,9 @@ impl EventSlot {
                 None => ("list", ""),
                 Some(tag) => ("list", tag.as_str()),
             },
+            EventApi::Archive(detail) => ("archive", detail.period.as_str()),
+            EventApi::Author(detail) => ("author", detail.author_id.as_str()),
+            EventApi::Series(detail) => ("series", detail.series_id.as_str()),
             EventApi::Index => ("index", ""),
             EventApi::Rss => ("rss", ""),
         };
@@ -1,25 +1,37 @@
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::metrics::event_slot::EventSlot;
+use chrono::{Duration, Local, NaiveDate};
 use spdlog::formatter::{pattern, PatternFormatter};
 use spdlog::sink::{RotatingFileSink, RotationPolicy};
-use spdlog::{info, Logger};
+use spdlog::{error, info, Logger};
 
 pub struct MetricPublisher {
     logger: Arc<Logger>,
 }
 
 impl MetricPublisher {
-    pub fn new(base_path: &PathBuf) -> spdlog::Result<Self> {
+    /// Files older than retention_days are deleted now and when the files rotate
+    pub fn new(base_path: &PathBuf, retention_days: Option<u32>) -> spdlog::Result<Self> {
         let pattern = pattern!("[SOR]{date} {time}.{millisecond}|{payload}[EOR]{eol}");
         let fmt = Box::new(PatternFormatter::new(pattern));
 
+        // The file of today is kept in addition to the ones of the last retention_days
+        let max_files = retention_days.map_or(0, |days| days as usize + 1);
+        if let Some(retention_days) = retention_days {
+            let oldest_day = Local::now().date_naive() - Duration::days(retention_days as i64);
+            if let Err(e) = remove_files_before(base_path, oldest_day) {
+                error!("Error removing metric files older than {} days: {}", retention_days, e);
+            }
+        }
+
         let daily: Arc<RotatingFileSink> = Arc::new(
             RotatingFileSink::builder()
                 .base_path(base_path)
                 .rotation_policy(RotationPolicy::Daily { hour: 0, minute: 0 })
+                .max_files(max_files)
                 .rotate_on_open(false)
                 .formatter(fmt)
                 .build()?,
@@ -39,3 +51,51 @@ impl MetricPublisher {
         Ok(())
     }
 }
+
+/// Removes the daily files of the base path, e.g. metrics_2024-05-01.log, of the days before oldest_day.
+/// The rotation only removes the files it created, not the ones left by previous runs
+fn remove_files_before(base_path: &Path, oldest_day: NaiveDate) -> io::Result<()> {
+    let stem = base_path.file_stem().unwrap_or_default().to_str().unwrap_or_default();
+    let dir = base_path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in dir.read_dir()?.flatten() {
+        let path = entry.path();
+        if path.extension() != base_path.extension() {
+            continue;
+        }
+        let day = path.file_stem()
+            .and_then(|file_stem| file_stem.to_str())
+            .and_then(|file_stem| file_stem.strip_prefix(stem))
+            .and_then(|suffix| suffix.strip_prefix('_'))
+            .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok());
+        if day.is_some_and(|day| day < oldest_day) {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn test_remove_files_before() {
+        let dir = temp_dir("metric-files");
+        let files = ["metrics_2024-04-30.log", "metrics_2024-05-01.log", "metrics.log", "metrics_2024-04-01.txt", "view_counts.json"];
+        for file in files {
+            fs::write(dir.join(file), "").unwrap();
+        }
+
+        remove_files_before(&dir.join("metrics.log"), NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()).unwrap();
+        let mut remaining: Vec<String> = fs::read_dir(&dir).unwrap().flatten().map(|e| e.file_name().into_string().unwrap()).collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["metrics.log", "metrics_2024-04-01.txt", "metrics_2024-05-01.log", "view_counts.json"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
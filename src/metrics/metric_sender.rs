@@ -1,78 +1,73 @@
-use crate::metrics::metric_types::EventApi::{Index, List, Page, Rss, View};
-use crate::metrics::metric_types::{ListDetail, MetricEvent, PageDetail, PostDetail};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::metrics::metric_types::EventApi::{Archive, Author, Index, List, Page, Rss, Series, View};
+use crate::metrics::metric_types::{ArchiveDetail, AuthorDetail, EventApi, ListDetail, MetricEvent, PageDetail, PostDetail, SeriesDetail};
 use spdlog::error;
 use tokio::sync::mpsc::Sender;
 
 pub struct MetricSender {
     sender_ch: Option<Sender<MetricEvent>>,
+    /// Events not sent because the channel was closed
+    dropped: AtomicU64,
 }
 
 impl MetricSender {
     pub fn new(sender_ch: Sender<MetricEvent>) -> Self {
         Self {
             sender_ch: Some(sender_ch),
+            dropped: AtomicU64::new(0),
         }
     }
 
     pub fn no_op() -> Self {
-        Self { sender_ch: None }
+        Self {
+            sender_ch: None,
+            dropped: AtomicU64::new(0),
+        }
     }
 
-    pub async fn view(&self, post_name: String, origin: String) {
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    async fn send(&self, api: EventApi, origin: String, metric_name: &str) {
         if let Some(ref sender) = self.sender_ch {
-            if let Err(e) = sender
-                .send(MetricEvent {
-                    api: View(PostDetail { post_name }),
-                    origin,
-                })
-                .await
-            {
-                error!("Error writing view metrics: {}", e);
+            if let Err(e) = sender.send(MetricEvent { api, origin }).await {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                error!("Error writing {} metrics: {}", metric_name, e);
             }
         }
     }
 
+    pub async fn view(&self, post_name: String, origin: String) {
+        self.send(View(PostDetail { post_name }), origin, "view").await;
+    }
+
     pub async fn page(&self, page_name: String, origin: String) {
-        if let Some(ref sender) = self.sender_ch {
-            if let Err(e) = sender
-                .send(MetricEvent {
-                    api: Page(PageDetail { page_name }),
-                    origin,
-                })
-                .await
-            {
-                error!("Error writing page metrics: {}", e);
-            }
-        }
+        self.send(Page(PageDetail { page_name }), origin, "page").await;
     }
 
     pub async fn list(&self, tag: Option<String>, origin: String) {
-        if let Some(ref sender) = self.sender_ch {
-            if let Err(e) = sender
-                .send(MetricEvent {
-                    api: List(ListDetail { tag }),
-                    origin,
-                })
-                .await
-            {
-                error!("Error writing list metrics: {}", e);
-            }
-        }
+        self.send(List(ListDetail { tag }), origin, "list").await;
+    }
+
+    pub async fn archive(&self, period: String, origin: String) {
+        self.send(Archive(ArchiveDetail { period }), origin, "archive").await;
+    }
+
+    pub async fn author(&self, author_id: String, origin: String) {
+        self.send(Author(AuthorDetail { author_id }), origin, "author").await;
+    }
+
+    pub async fn series(&self, series_id: String, origin: String) {
+        self.send(Series(SeriesDetail { series_id }), origin, "series").await;
     }
 
     pub async fn rss(&self, origin: String) {
-        if let Some(ref sender) = self.sender_ch {
-            if let Err(e) = sender.send(MetricEvent { api: Rss, origin }).await {
-                error!("Error writing rss metrics: {}", e);
-            }
-        }
+        self.send(Rss, origin, "rss").await;
     }
 
     pub async fn index(&self, origin: String) {
-        if let Some(ref sender) = self.sender_ch {
-            if let Err(e) = sender.send(MetricEvent { api: Index, origin }).await {
-                error!("Error writing index metrics: {}", e);
-            }
-        }
+        self.send(Index, origin, "index").await;
     }
 }
@@ -9,10 +9,26 @@ pub struct PageDetail {
 pub struct ListDetail {
     pub tag: Option<String>,
 }
+
+pub struct AuthorDetail {
+    pub author_id: String,
+}
+
+pub struct SeriesDetail {
+    pub series_id: String,
+}
+
+pub struct ArchiveDetail {
+    pub period: String,
+}
+
 pub enum EventApi {
     View(PostDetail),
     Page(PageDetail),
     List(ListDetail),
+    Archive(ArchiveDetail),
+    Author(AuthorDetail),
+    Series(SeriesDetail),
     Index,
     Rss,
 }
@@ -1,42 +1,58 @@
+use crate::config::Metrics;
 use crate::metrics::metric_aggregator::{Event, MetricAggregator};
 use crate::metrics::metric_publisher::MetricPublisher;
 use crate::metrics::metric_types::MetricEvent;
+use crate::metrics::origin_anonymizer::OriginAnonymizer;
 use chrono::{Duration, Utc};
 use std::io;
-use std::path::PathBuf;
 
 pub struct MetricWriter {
     metric_aggregator: MetricAggregator,
     metric_publisher: MetricPublisher,
+    anonymizer: OriginAnonymizer,
+    /// When false, the origins are only used to count the unique visits and are not written
+    store_origins: bool,
 }
 
 impl MetricWriter {
-    pub fn new(base_path: &PathBuf, time_slot: Duration) -> spdlog::Result<Self> {
+    /// Writer of the metrics section. The location and time slot are set when the configuration is loaded
+    pub fn new(config: &Metrics) -> anyhow::Result<Self> {
+        let time_slot = Duration::seconds(config.time_slot_secs.unwrap());
         let metric_aggregator = MetricAggregator::new(time_slot);
-        let metric_publisher = MetricPublisher::new(base_path)?;
+        let metric_publisher = MetricPublisher::new(config.location.as_ref().unwrap(), config.retention_days)?;
+        // After the publisher, which creates the directory of the metric files
+        let anonymizer = OriginAnonymizer::new(config)?;
 
         Ok(Self {
             metric_aggregator,
             metric_publisher,
+            anonymizer,
+            store_origins: config.store_origins.unwrap_or(true),
         })
     }
 
-    pub fn add_event(&mut self, metric_event: MetricEvent) -> io::Result<()> {
+    pub fn add_event(&mut self, mut metric_event: MetricEvent) -> io::Result<()> {
+        let date_time = Utc::now();
+        metric_event.origin = self.anonymizer.anonymize(metric_event.origin, date_time.date_naive());
         let event = Event {
             metric_event,
-            date_time: Utc::now(),
+            date_time,
             total: 1,
         };
         self.metric_aggregator.add_event(event);
-        if let Some(history) = self.metric_aggregator.take_events() {
-            self.metric_publisher.store_events(&history)?;
-        }
-        Ok(())
+        self.publish()
     }
 
     pub fn flush(&mut self) -> io::Result<()> {
         self.metric_aggregator.flush();
-        if let Some(history) = self.metric_aggregator.take_events() {
+        self.publish()
+    }
+
+    fn publish(&mut self) -> io::Result<()> {
+        if let Some(mut history) = self.metric_aggregator.take_events() {
+            if !self.store_origins {
+                history.iter_mut().for_each(|slot| slot.origins.clear());
+            }
             self.metric_publisher.store_events(&history)?;
         }
         Ok(())
@@ -1,8 +1,11 @@
 pub mod metric_handler;
 pub mod metric_sender;
 pub mod metric_writer;
+pub mod server_metrics;
+pub mod view_counter;
 
 mod event_slot;
 mod metric_aggregator;
 mod metric_publisher;
 mod metric_types;
+mod origin_anonymizer;
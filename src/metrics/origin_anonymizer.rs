@@ -0,0 +1,196 @@
+use std::hash::Hasher;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use chrono::{NaiveDate, Utc};
+use siphasher::sip::SipHasher24;
+use spdlog::warn;
+use uuid::Uuid;
+
+use crate::config::{Metrics, OriginAnonymization};
+
+const DEFAULT_IPV4_PREFIX: u8 = 24;
+const DEFAULT_IPV6_PREFIX: u8 = 48;
+/// Salt of the origin hashes of the current day, in the directory of the metric files
+const ORIGIN_KEY_FILE: &str = "origin_key";
+
+/// Replaces the visitor addresses before they are aggregated, so the metric files do not keep them
+pub struct OriginAnonymizer {
+    mode: OriginAnonymization,
+    ipv4_prefix: u8,
+    ipv6_prefix: u8,
+    /// File keeping the salt of the current day, so the hashes do not change when the server restarts
+    salt_file: Option<PathBuf>,
+    /// Random salt of the hashes and its day, so they cannot be reversed by hashing every address.
+    /// A new salt replaces it every day, so visitors cannot be followed across days
+    salt: Option<(NaiveDate, u128)>,
+}
+
+impl OriginAnonymizer {
+    /// Anonymizer of the metrics section. The location is set when the configuration is loaded
+    pub fn new(config: &Metrics) -> io::Result<Self> {
+        let salt_file = match config.anonymize_origins {
+            OriginAnonymization::Hash => Some(config.location.as_ref().unwrap().with_file_name(ORIGIN_KEY_FILE)),
+            _ => None,
+        };
+        // The salt of a previous day is replaced when starting
+        let salt = match salt_file {
+            Some(ref file) => {
+                let today = Utc::now().date_naive();
+                Some((today, daily_salt(file, today)?))
+            }
+            None => None,
+        };
+        Ok(OriginAnonymizer {
+            mode: config.anonymize_origins,
+            ipv4_prefix: config.ipv4_prefix.unwrap_or(DEFAULT_IPV4_PREFIX).min(32),
+            ipv6_prefix: config.ipv6_prefix.unwrap_or(DEFAULT_IPV6_PREFIX).min(128),
+            salt_file,
+            salt,
+        })
+    }
+
+    /// Origin to aggregate for a request of the day. Origins that are not an address are removed
+    /// when anonymizing
+    pub fn anonymize(&mut self, origin: String, day: NaiveDate) -> String {
+        if self.mode == OriginAnonymization::None {
+            return origin;
+        }
+        let Some(ip) = parse_ip(&origin) else {
+            return String::new();
+        };
+
+        match self.mode {
+            OriginAnonymization::Truncate => match ip {
+                IpAddr::V4(ip) => {
+                    let mask = u32::MAX.checked_shl(32 - self.ipv4_prefix as u32).unwrap_or(0);
+                    format!("{}/{}", Ipv4Addr::from(u32::from(ip) & mask), self.ipv4_prefix)
+                }
+                IpAddr::V6(ip) => {
+                    let mask = u128::MAX.checked_shl(128 - self.ipv6_prefix as u32).unwrap_or(0);
+                    format!("{}/{}", Ipv6Addr::from(u128::from(ip) & mask), self.ipv6_prefix)
+                }
+            },
+            _ => {
+                if self.salt.is_none_or(|(salt_day, _)| salt_day != day) {
+                    self.salt = Some((day, self.new_salt(day)));
+                }
+                let (_, salt) = self.salt.unwrap();
+                let mut hasher = SipHasher24::new_with_keys((salt >> 64) as u64, salt as u64);
+                match ip {
+                    IpAddr::V4(ip) => hasher.write(&ip.octets()),
+                    IpAddr::V6(ip) => hasher.write(&ip.octets()),
+                }
+                format!("{:016x}", hasher.finish())
+            }
+        }
+    }
+
+    /// Salt of day, saved in the salt file. If it cannot be saved, the hashes change when the server restarts
+    fn new_salt(&self, day: NaiveDate) -> u128 {
+        let Some(ref file) = self.salt_file else {
+            return Uuid::new_v4().as_u128();
+        };
+        daily_salt(file, day).unwrap_or_else(|e| {
+            warn!("Unable to save the origin salt in {}: {}", file.to_str().unwrap(), e);
+            Uuid::new_v4().as_u128()
+        })
+    }
+}
+
+/// Salt of day, kept in file as `<day> <salt>`. The salt of another day is replaced by a new one, so the file
+/// only keeps the salt of the current day
+fn daily_salt(file: &Path, day: NaiveDate) -> io::Result<u128> {
+    let day = day.format("%Y-%m-%d").to_string();
+    match fs::read_to_string(file) {
+        Ok(text) => {
+            let salt = text.trim().strip_prefix(&day).and_then(|salt| u128::from_str_radix(salt.trim(), 16).ok());
+            if let Some(salt) = salt {
+                return Ok(salt);
+            }
+        }
+        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+        Err(_) => {}
+    }
+    let salt = Uuid::new_v4().as_u128();
+    fs::write(file, format!("{} {:032x}", day, salt))?;
+    Ok(salt)
+}
+
+/// Address of an origin, with or without the port
+fn parse_ip(origin: &str) -> Option<IpAddr> {
+    let origin = origin.trim();
+    origin.parse::<IpAddr>().ok()
+        .or_else(|| origin.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::temp_dir;
+
+    fn new_anonymizer(mode: OriginAnonymization, ipv4_prefix: u8, salt_file: Option<PathBuf>) -> OriginAnonymizer {
+        OriginAnonymizer {
+            mode,
+            ipv4_prefix,
+            ipv6_prefix: DEFAULT_IPV6_PREFIX,
+            salt_file,
+            salt: None,
+        }
+    }
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut anonymizer = new_anonymizer(OriginAnonymization::Truncate, DEFAULT_IPV4_PREFIX, None);
+        assert_eq!(anonymizer.anonymize("203.0.113.77".to_string(), day(1)), "203.0.113.0/24");
+        assert_eq!(anonymizer.anonymize("203.0.113.77:52100".to_string(), day(1)), "203.0.113.0/24");
+        assert_eq!(anonymizer.anonymize("2001:db8:1234:5678::1".to_string(), day(1)), "2001:db8:1234::/48");
+        assert_eq!(anonymizer.anonymize("[2001:db8::1]:443".to_string(), day(1)), "2001:db8::/48");
+        assert_eq!(anonymizer.anonymize("not an address".to_string(), day(1)), "");
+
+        let mut anonymizer = new_anonymizer(OriginAnonymization::Truncate, 0, None);
+        assert_eq!(anonymizer.anonymize("203.0.113.77".to_string(), day(1)), "0.0.0.0/0");
+    }
+
+    #[test]
+    fn test_hash() {
+        let salt_file = temp_dir("origin-hash").join(ORIGIN_KEY_FILE);
+        let mut anonymizer = new_anonymizer(OriginAnonymization::Hash, DEFAULT_IPV4_PREFIX, Some(salt_file.clone()));
+        let first = anonymizer.anonymize("203.0.113.77".to_string(), day(1));
+        assert_eq!(first.len(), 16);
+        // Same visitor on the same day, also with another port
+        assert_eq!(anonymizer.anonymize("203.0.113.77:52100".to_string(), day(1)), first);
+        assert_ne!(anonymizer.anonymize("203.0.113.78".to_string(), day(1)), first);
+        // The hashes do not change when the server restarts
+        let mut restarted = new_anonymizer(OriginAnonymization::Hash, DEFAULT_IPV4_PREFIX, Some(salt_file.clone()));
+        assert_eq!(restarted.anonymize("203.0.113.77".to_string(), day(1)), first);
+        // but the salt changes every day, and the one of the previous day is gone
+        assert_ne!(anonymizer.anonymize("203.0.113.77".to_string(), day(2)), first);
+        let mut restarted = new_anonymizer(OriginAnonymization::Hash, DEFAULT_IPV4_PREFIX, Some(salt_file));
+        assert_ne!(restarted.anonymize("203.0.113.77".to_string(), day(1)), first);
+
+        let mut anonymizer = new_anonymizer(OriginAnonymization::None, DEFAULT_IPV4_PREFIX, None);
+        assert_eq!(anonymizer.anonymize("203.0.113.77:52100".to_string(), day(1)), "203.0.113.77:52100");
+    }
+
+    #[test]
+    fn test_daily_salt() {
+        let file = temp_dir("origin-key").join(ORIGIN_KEY_FILE);
+        let salt = daily_salt(&file, day(1)).unwrap();
+        assert_eq!(daily_salt(&file, day(1)).unwrap(), salt);
+        assert_eq!(fs::read_to_string(&file).unwrap(), format!("2024-05-01 {:032x}", salt));
+
+        let next_salt = daily_salt(&file, day(2)).unwrap();
+        assert_ne!(next_salt, salt);
+        assert_eq!(fs::read_to_string(&file).unwrap(), format!("2024-05-02 {:032x}", next_salt));
+
+        fs::write(&file, "not a salt").unwrap();
+        assert_ne!(daily_salt(&file, day(2)).unwrap(), next_salt);
+    }
+}
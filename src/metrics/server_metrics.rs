@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ntex::service::{Middleware, Service, ServiceCtx};
+use ntex::web::{WebRequest, WebResponse};
+
+use crate::content_cache::CacheStats;
+
+/// Upper bounds of the duration histograms, in seconds
+const DURATION_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+/// Routes served, "*" matches any segment. Other paths are counted as "other", so unknown urls do not create new series
+const ROUTES: &[&str] = &[
+    "/", "/view/*", "/view/*/*", "/page/*", "/page/*/*", "/list", "/list/*", "/author/*", "/series/*", "/archive",
+    "/archive/*", "/archive/*/*", "/rss", "/public/*", "/api/posts", "/api/posts/*", "/api/pages/*", "/api/tags",
+    "/metrics",
+];
+
+/// Counters and histograms of the server, served in the Prometheus text format at /metrics. Unlike the
+/// visitor metrics of MetricWriter, they describe the server health
+#[derive(Default)]
+pub struct ServerMetrics {
+    /// Requests by route and status code
+    requests: Mutex<BTreeMap<(String, u16), u64>>,
+    /// Duration of the requests by route
+    request_durations: Mutex<BTreeMap<String, Histogram>>,
+    /// Time rendering posts and pages from their files, by content kind
+    render_durations: Mutex<BTreeMap<&'static str, Histogram>>,
+}
+
+/// Values read from the server state when the metrics are collected
+pub struct ServerGauges {
+    pub posts: usize,
+    pub pages: usize,
+    pub post_cache: CacheStats,
+    pub summary_cache: CacheStats,
+    /// Visitor metric events dropped because the channel was closed
+    pub metric_drops: u64,
+}
+
+struct Histogram {
+    /// Observations of each bucket of DURATION_BUCKETS, not cumulative. The last one is +Inf
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram {
+            counts: vec![0; DURATION_BUCKETS.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        let bucket = DURATION_BUCKETS.iter().position(|le| secs <= *le).unwrap_or(DURATION_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            cumulative += count;
+            let le = DURATION_BUCKETS.get(bucket).map_or("+Inf".to_string(), |le| le.to_string());
+            let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, le, cumulative);
+        }
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+impl ServerMetrics {
+    pub fn add_request(&self, route: String, status: u16, duration: Duration) {
+        self.request_durations.lock().unwrap().entry(route.clone()).or_default().observe(duration);
+        *self.requests.lock().unwrap().entry((route, status)).or_insert(0) += 1;
+    }
+
+    pub fn add_render(&self, kind: &'static str, duration: Duration) {
+        self.render_durations.lock().unwrap().entry(kind).or_default().observe(duration);
+    }
+
+    /// Metrics in the Prometheus text exposition format
+    pub fn encode(&self, gauges: &ServerGauges) -> String {
+        let mut out = String::new();
+        write_header(&mut out, "texted_http_requests_total", "counter", "Requests by route and status code");
+        for ((route, status), count) in self.requests.lock().unwrap().iter() {
+            let _ = writeln!(out, "texted_http_requests_total{{route=\"{}\",status=\"{}\"}} {}", route, status, count);
+        }
+
+        write_header(&mut out, "texted_http_request_duration_seconds", "histogram", "Duration of the requests by route");
+        for (route, histogram) in self.request_durations.lock().unwrap().iter() {
+            histogram.write(&mut out, "texted_http_request_duration_seconds", &format!("route=\"{}\"", route));
+        }
+
+        write_header(&mut out, "texted_render_duration_seconds", "histogram", "Time rendering posts and pages from their files");
+        for (kind, histogram) in self.render_durations.lock().unwrap().iter() {
+            histogram.write(&mut out, "texted_render_duration_seconds", &format!("content=\"{}\"", kind));
+        }
+
+        let caches = [("post", &gauges.post_cache), ("summary", &gauges.summary_cache)];
+        write_header(&mut out, "texted_cache_hits_total", "counter", "Values found in the rendering caches");
+        for (cache, stats) in caches {
+            let _ = writeln!(out, "texted_cache_hits_total{{cache=\"{}\"}} {}", cache, stats.hits);
+        }
+        write_header(&mut out, "texted_cache_misses_total", "counter", "Values not found or expired in the rendering caches");
+        for (cache, stats) in caches {
+            let _ = writeln!(out, "texted_cache_misses_total{{cache=\"{}\"}} {}", cache, stats.misses);
+        }
+        write_header(&mut out, "texted_cache_entries", "gauge", "Values in the rendering caches");
+        for (cache, stats) in caches {
+            let _ = writeln!(out, "texted_cache_entries{{cache=\"{}\"}} {}", cache, stats.size);
+        }
+
+        write_header(&mut out, "texted_metric_events_dropped_total", "counter", "Visitor metric events dropped because the channel was closed");
+        let _ = writeln!(out, "texted_metric_events_dropped_total {}", gauges.metric_drops);
+        write_header(&mut out, "texted_posts", "gauge", "Posts served");
+        let _ = writeln!(out, "texted_posts {}", gauges.posts);
+        write_header(&mut out, "texted_pages", "gauge", "Pages served");
+        let _ = writeln!(out, "texted_pages {}", gauges.pages);
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Route of a path, with its parameters replaced by *. E.g. /view/my-post/ is /view/*
+pub fn route_label(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let route = ROUTES.iter().find(|route| {
+        let route_segments: Vec<&str> = route.split('/').filter(|segment| !segment.is_empty()).collect();
+        route_segments.len() == segments.len()
+            && route_segments.iter().zip(&segments).all(|(route_segment, segment)| *route_segment == "*" || route_segment == segment)
+    });
+    route.unwrap_or(&"other").to_string()
+}
+
+/// Counts the requests and their duration in the server metrics
+pub struct RequestMetrics {
+    metrics: Arc<ServerMetrics>,
+}
+
+impl RequestMetrics {
+    pub fn new(metrics: Arc<ServerMetrics>) -> Self {
+        RequestMetrics { metrics }
+    }
+}
+
+impl<S> Middleware<S> for RequestMetrics {
+    type Service = RequestMetricsMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        RequestMetricsMiddleware {
+            service,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+pub struct RequestMetricsMiddleware<S> {
+    service: S,
+    metrics: Arc<ServerMetrics>,
+}
+
+impl<S, E> Service<WebRequest<E>> for RequestMetricsMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+
+    ntex::forward_poll!(service);
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(&self, req: WebRequest<E>, ctx: ServiceCtx<'_, Self>) -> Result<Self::Response, Self::Error> {
+        let route = route_label(req.path());
+        let start = Instant::now();
+        let res = ctx.call(&self.service, req).await;
+        let status = res.as_ref().map_or(500, |res| res.status().as_u16());
+        self.metrics.add_request(route, status, start.elapsed());
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_label() {
+        assert_eq!(route_label("/"), "/");
+        assert_eq!(route_label("/view/my-post/"), "/view/*");
+        assert_eq!(route_label("/view/my-post/image.png"), "/view/*/*");
+        assert_eq!(route_label("/archive/2024/05/"), "/archive/*/*");
+        assert_eq!(route_label("/api/posts/my-post"), "/api/posts/*");
+        assert_eq!(route_label("/api/other"), "other");
+        assert_eq!(route_label("/wp-login.php"), "other");
+        assert_eq!(route_label("/view/my-post/a/b/c"), "other");
+        assert_eq!(route_label("/archive/2024/05/01/"), "other");
+    }
+
+    #[test]
+    fn test_encode() {
+        let metrics = ServerMetrics::default();
+        metrics.add_request("/view/*".to_string(), 200, Duration::from_millis(3));
+        metrics.add_request("/view/*".to_string(), 200, Duration::from_secs(10));
+        metrics.add_request("/view/*".to_string(), 404, Duration::from_millis(1));
+        metrics.add_render("post", Duration::from_millis(20));
+        let gauges = ServerGauges {
+            posts: 3,
+            pages: 1,
+            post_cache: CacheStats { hits: 5, misses: 2, size: 2 },
+            summary_cache: CacheStats::default(),
+            metric_drops: 4,
+        };
+
+        let text = metrics.encode(&gauges);
+        assert!(text.contains("# TYPE texted_http_requests_total counter\n"));
+        assert!(text.contains("texted_http_requests_total{route=\"/view/*\",status=\"200\"} 2\n"));
+        assert!(text.contains("texted_http_requests_total{route=\"/view/*\",status=\"404\"} 1\n"));
+        assert!(text.contains("texted_http_request_duration_seconds_bucket{route=\"/view/*\",le=\"0.001\"} 1\n"));
+        assert!(text.contains("texted_http_request_duration_seconds_bucket{route=\"/view/*\",le=\"0.005\"} 2\n"));
+        assert!(text.contains("texted_http_request_duration_seconds_bucket{route=\"/view/*\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("texted_http_request_duration_seconds_count{route=\"/view/*\"} 3\n"));
+        assert!(text.contains("texted_render_duration_seconds_bucket{content=\"post\",le=\"0.025\"} 1\n"));
+        assert!(text.contains("texted_cache_hits_total{cache=\"post\"} 5\n"));
+        assert!(text.contains("texted_cache_entries{cache=\"summary\"} 0\n"));
+        assert!(text.contains("texted_metric_events_dropped_total 4\n"));
+        assert!(text.contains("texted_posts 3\n"));
+        assert!(text.contains("texted_pages 1\n"));
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::{fs, io};
+
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Number of days of the weekly views
+const WEEK_DAYS: i64 = 7;
+
+/// Views of each post since the counters were created, kept in a state file to survive restarts
+pub struct ViewCounter {
+    /// None when the metrics are disabled. Nothing is counted
+    state_file: Option<PathBuf>,
+    state: RwLock<ViewState>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ViewState {
+    posts: HashMap<String, PostViews>,
+    /// Views added after the last save
+    #[serde(skip)]
+    changed: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct PostViews {
+    total: u64,
+    /// Views of the last days, used for the weekly views
+    days: BTreeMap<NaiveDate, u64>,
+}
+
+/// Views of the posts at a given moment, by post name
+#[derive(Default)]
+pub struct ViewCounts {
+    pub all_time: HashMap<String, u64>,
+    /// Views of the last 7 days, including today
+    pub last_week: HashMap<String, u64>,
+}
+
+impl ViewCounter {
+    /// Counter with the views of the state file. A missing file starts all the counters at zero
+    pub fn load(state_file: &Path) -> io::Result<Self> {
+        let state = match fs::read_to_string(state_file) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| io::Error::new(
+                ErrorKind::InvalidData, format!("Error reading view counters from {}: {}", state_file.to_str().unwrap(), e)))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => ViewState::default(),
+            Err(e) => return Err(e),
+        };
+        Ok(ViewCounter {
+            state_file: Some(state_file.to_path_buf()),
+            state: RwLock::new(state),
+        })
+    }
+
+    pub fn no_op() -> Self {
+        ViewCounter {
+            state_file: None,
+            state: RwLock::new(ViewState::default()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state_file.is_some()
+    }
+
+    pub fn add_view(&self, post_name: &str) {
+        self.add_view_on(post_name, Utc::now().date_naive());
+    }
+
+    fn add_view_on(&self, post_name: &str, day: NaiveDate) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut state = self.state.write().unwrap();
+        let views = state.posts.entry(post_name.to_string()).or_default();
+        views.total += 1;
+        *views.days.entry(day).or_insert(0) += 1;
+        state.changed = true;
+    }
+
+    pub fn counts(&self) -> ViewCounts {
+        self.counts_on(Utc::now().date_naive())
+    }
+
+    fn counts_on(&self, today: NaiveDate) -> ViewCounts {
+        let week_start = today - Duration::days(WEEK_DAYS - 1);
+        let state = self.state.read().unwrap();
+        let mut counts = ViewCounts::default();
+        for (post_name, views) in state.posts.iter() {
+            counts.all_time.insert(post_name.clone(), views.total);
+            let week: u64 = views.days.range(week_start..).map(|(_, count)| count).sum();
+            if week > 0 {
+                counts.last_week.insert(post_name.clone(), week);
+            }
+        }
+        counts
+    }
+
+    /// Writes the counters to the state file, if there are new views. Days older than a week are dropped
+    pub fn save(&self) -> io::Result<()> {
+        let Some(ref state_file) = self.state_file else {
+            return Ok(());
+        };
+        let mut state = self.state.write().unwrap();
+        if !state.changed {
+            return Ok(());
+        }
+
+        let week_start = Utc::now().date_naive() - Duration::days(WEEK_DAYS - 1);
+        for views in state.posts.values_mut() {
+            views.days.retain(|day, _| *day >= week_start);
+        }
+        let json = serde_json::to_string(&*state)?;
+        if let Some(dir) = state_file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Written to a temporary file first, so a crash does not leave a truncated state file
+        let tmp_file = state_file.with_extension("tmp");
+        fs::write(&tmp_file, json)?;
+        fs::rename(&tmp_file, state_file)?;
+        state.changed = false;
+        Ok(())
+    }
+}
+
+impl ViewCounts {
+    pub fn views(&self, post_name: &str) -> u64 {
+        self.all_time.get(post_name).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::temp_dir;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    #[test]
+    fn test_counts() {
+        let file = temp_dir("view-counts").join("view_counts.json");
+
+        let counter = ViewCounter::load(&file).unwrap();
+        counter.add_view_on("old", day(1));
+        counter.add_view_on("old", day(1));
+        counter.add_view_on("new", day(10));
+        counter.add_view_on("new", day(4));
+
+        let counts = counter.counts_on(day(10));
+        assert_eq!(counts.views("old"), 2);
+        assert_eq!(counts.views("new"), 2);
+        assert_eq!(counts.views("missing"), 0);
+        assert_eq!(counts.last_week, HashMap::from([("new".to_string(), 2)]));
+
+        counter.save().unwrap();
+        let counter = ViewCounter::load(&file).unwrap();
+        assert_eq!(counter.counts_on(day(10)).views("old"), 2);
+        fs::remove_file(&file).unwrap();
+
+        let counter = ViewCounter::no_op();
+        counter.add_view_on("post", day(1));
+        assert!(counter.counts_on(day(1)).all_time.is_empty());
+    }
+}
@@ -29,7 +29,12 @@ impl<'a, T> Paginator<'a, T> {
         self.page_count
     }
 
-    pub fn get_page(&self, page: u32) -> Result<&[T], String> {
+    /// Number of items of all pages
+    pub fn total_count(&self) -> usize {
+        self.post_list.len()
+    }
+
+    pub fn get_page(&self, page: u32) -> Result<&'a [T], String> {
         match page {
             0 => return Err("Page has to be greater than 0".to_string()),
             x if x > self.page_count => return Err(format!("Page has to be less than page_count ({})", self.page_count)),
@@ -55,6 +60,7 @@ mod tests {
         let items = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13];
         let paginator = Paginator::from(&items, 3);
         assert_eq!(paginator.page_count(), 5);
+        assert_eq!(paginator.total_count(), 13);
         assert_eq!(paginator.get_page(1), Ok(&[1, 2, 3].as_slice()).copied());
         assert_eq!(paginator.get_page(2), Ok(&[4, 5, 6].as_slice()).copied());
         assert_eq!(paginator.get_page(3), Ok(&[7, 8, 9].as_slice()).copied());
@@ -70,6 +76,7 @@ mod tests {
         let items: Vec<u32> = vec![];
         let paginator = Paginator::from(&items, 3);
         assert_eq!(paginator.page_count(), 0);
+        assert_eq!(paginator.total_count(), 0);
         assert_eq!(paginator.get_page(0), Err("Page has to be greater than 0".to_string()));
         assert_eq!(paginator.get_page(1), Err("Page has to be less than page_count (0)".to_string()));
     }
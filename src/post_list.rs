@@ -4,9 +4,19 @@ use std::{fs, io};
 use anyhow::Context;
 use anyhow::Result;
 
-pub struct PostList {
+use crate::content::format_registry::FormatRegistry;
+
+#[derive(Debug, PartialEq)]
+pub struct PostLink {
+    pub post_name: String,
+    pub post_path: PathBuf,
+}
+
+pub struct PostList<'a> {
     pub root_dir: PathBuf,
     pub post_file: PostListType,
+    /// Only files with a registered format are listed
+    pub formats: &'a FormatRegistry,
 }
 
 #[derive(Clone)]
@@ -16,7 +26,7 @@ pub enum PostListType {
     AnyContentFile,
 }
 
-impl PostList {
+impl PostList<'_> {
     pub fn retrieve_files(&self) -> io::Result<Vec<PathBuf>> {
         let mut posts = vec![];
         let entries = fs::read_dir(self.root_dir.as_path())?;
@@ -25,12 +35,8 @@ impl PostList {
                 if !file_type.is_file() {
                     continue;
                 }
-                let file_name = entry.file_name();
-                if let Some(file_name) = file_name.to_str() {
-                    // Check if the file has a .md extension
-                    if file_name.ends_with(".md") || file_name.ends_with(".html") || file_name.ends_with(".htm") {
-                        posts.push(entry.path());
-                    }
+                if self.formats.is_supported(&entry.path()) {
+                    posts.push(entry.path());
                 }
             }
         }
@@ -41,7 +47,7 @@ impl PostList {
         // Per directory, we should have a file called post.md
         let dirs = Self::list_dirs(self.root_dir.as_path())?;
         // Filtering only the dirs with a post inside
-        let post_dirs = Self::filter_dirs(&self.post_file, dirs);
+        let post_dirs = self.filter_dirs(dirs);
         Ok(post_dirs)
     }
 
@@ -60,31 +66,31 @@ impl PostList {
         Ok(dirs)
     }
 
-    fn filter_dirs(post_file: &PostListType, dirs: Vec<PathBuf>) -> Vec<(PathBuf, String)> {
+    fn filter_dirs(&self, dirs: Vec<PathBuf>) -> Vec<(PathBuf, String)> {
         let mut post_dirs = vec![];
         for dir in dirs {
-            if let Some(file_name) = Self::contains_file(&dir, post_file).unwrap() {
+            if let Some(file_name) = self.contains_file(&dir).unwrap() {
                 post_dirs.push((dir, file_name));
             }
         }
         post_dirs
     }
 
-    fn contains_file(dir: &PathBuf, base_name: &PostListType) -> Result<Option<String>> {
+    fn contains_file(&self, dir: &PathBuf) -> Result<Option<String>> {
         let entries = fs::read_dir(dir)
             .context(format!("Could not read directory {}", dir.to_str().unwrap()))?;
         for entry in entries {
             let entry = entry?;
             if entry.file_type()?.is_file() {
                 let file_name = entry.file_name().to_str().unwrap().to_string();
-                match base_name {
+                match &self.post_file {
                     PostListType::IndexBaseName(base_name) => {
                         if file_name.contains(base_name) {
                             return Ok(Some(file_name.to_string()));
                         }
                     }
                     PostListType::AnyContentFile => {
-                        if file_name.ends_with(".md") || file_name.ends_with(".htm") || file_name.ends_with(".html") {
+                        if self.formats.is_supported(&entry.path()) {
                             return Ok(Some(file_name.to_string()));
                         }
                     }
@@ -96,3 +102,61 @@ impl PostList {
     }
 }
 
+/// Posts (or pages) of root_dir: directories with a content file and content files with a registered format
+pub fn list_post_files(root_dir: &Path, post_file: &PostListType, formats: &FormatRegistry) -> Result<Vec<PostLink>> {
+    let root_dir = root_dir.to_path_buf();
+    let post_list = PostList {
+        root_dir,
+        post_file: post_file.clone(),
+        formats,
+    };
+
+    let dirs = post_list.retrieve_dirs()?;
+    let mut posts = vec![];
+    for (dir, file_name) in dirs {
+        // Adding default file to directory posts
+        let post_name = dir.iter().next_back().unwrap().to_str().unwrap().to_string();
+        let post_path = dir.join(file_name);
+
+        posts.push(PostLink {
+            post_name,
+            post_path,
+        });
+    }
+
+    // Retrieve files in post directory
+    let md_posts: Vec<PathBuf> = post_list.retrieve_files()?;
+    for post_file in md_posts {
+        let post_name = post_file.file_stem().unwrap().to_str().unwrap().to_string();
+        let post_path = post_file;
+        posts.push(PostLink {
+            post_name,
+            post_path,
+        });
+    }
+
+    Ok(posts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_last() {
+        let list_type = PostListType::IndexBaseName("index".to_string());
+        let mut posts = list_post_files(&PathBuf::from("res/posts"), &list_type, &FormatRegistry::default()).unwrap();
+        // Directory entries are returned in file system order
+        posts.sort_by(|a, b| a.post_path.cmp(&b.post_path));
+
+        let mut expected = vec![
+            PostLink { post_name: "html_post_with_image".to_string(), post_path: PathBuf::from("res/posts/html_post_with_image/index.html") },
+            PostLink { post_name: "post_with_image".to_string(), post_path: PathBuf::from("res/posts/post_with_image/index.md") },
+            PostLink { post_name: "html_post".to_string(), post_path: PathBuf::from("res/posts/html_post.htm") },
+            PostLink { post_name: "post_without_images".to_string(), post_path: PathBuf::from("res/posts/post_without_images.md") },
+            PostLink { post_name: "new_post".to_string(), post_path: PathBuf::from("res/posts/new_post.md") },
+        ];
+        expected.sort_by(|a, b| a.post_path.cmp(&b.post_path));
+        assert_eq!(expected, posts);
+    }
+}
\ No newline at end of file
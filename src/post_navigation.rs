@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::content::html_utils::strip_tags;
+use crate::content::Content;
+use crate::series::SeriesNavigation;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavLink {
+    pub link: String,
+    pub title: String,
+}
+
+#[derive(Clone)]
+pub struct RelatedOptions {
+    /// Maximum number of related posts
+    pub max_count: usize,
+    /// Besides tags, also use the similarity of the summary text to rank related posts
+    pub text_similarity: bool,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PostNavigation {
+    /// Post published right before the current one
+    pub previous: Option<NavLink>,
+    /// Post published right after the current one
+    pub next: Option<NavLink>,
+    /// Posts sharing tags (and optionally text) with the current one, most related first
+    pub related: Vec<NavLink>,
+    /// Parts of the series the current post belongs to
+    pub series: Option<SeriesNavigation>,
+    /// Posts with wiki links to the current one, most recent first
+    pub backlinks: Vec<NavLink>,
+}
+
+impl NavLink {
+    fn from(content: &Content) -> Self {
+        NavLink {
+            link: format!("/view/{}", &content.link),
+            title: content.title.clone(),
+        }
+    }
+}
+
+impl PostNavigation {
+    pub fn from(contents: &[Arc<Content>], post_link: &str, related_opt: &RelatedOptions) -> Self {
+        // Oldest first, so previous is the index before and next the index after
+        let mut by_date: Vec<&Arc<Content>> = contents.iter().collect();
+        by_date.sort_by(|a, b| {
+            a.header.date.cmp(&b.header.date).then_with(|| a.link.cmp(&b.link))
+        });
+
+        let Some(pos) = by_date.iter().position(|c| c.link == post_link) else {
+            return PostNavigation::default();
+        };
+
+        let previous = match pos {
+            0 => None,
+            x => Some(NavLink::from(by_date[x - 1])),
+        };
+        let next = by_date.get(pos + 1).map(|c| NavLink::from(c));
+        let related = Self::find_related(&by_date, by_date[pos], related_opt);
+
+        PostNavigation {
+            previous,
+            next,
+            related,
+            series: None,
+            backlinks: vec![],
+        }
+    }
+
+    /// Posts of links, most recent first
+    pub fn find_backlinks(contents: &[Arc<Content>], links: &[String]) -> Vec<NavLink> {
+        let mut posts: Vec<&Arc<Content>> = contents.iter()
+            .filter(|c| links.contains(&c.link))
+            .collect();
+        posts.sort_by(|a, b| b.header.date.cmp(&a.header.date).then_with(|| a.link.cmp(&b.link)));
+        posts.into_iter().map(|c| NavLink::from(c)).collect()
+    }
+
+    fn find_related(contents: &[&Arc<Content>], current: &Content, related_opt: &RelatedOptions) -> Vec<NavLink> {
+        if related_opt.max_count == 0 {
+            return vec![];
+        }
+
+        let cur_words = match related_opt.text_similarity {
+            true => Some(word_set(&current.rendered)),
+            false => None,
+        };
+
+        let mut scored: Vec<(f64, &Content)> = vec![];
+        for content in contents {
+            if content.link == current.link {
+                continue;
+            }
+
+            let shared_tags = content.header.tags.iter()
+                .filter(|t| current.header.tags.contains(t))
+                .count();
+            let similarity = match cur_words {
+                Some(ref cur_words) => jaccard(cur_words, &word_set(&content.rendered)),
+                None => 0.0,
+            };
+
+            let score = shared_tags as f64 + similarity;
+            if score > 0.0 {
+                scored.push((score, content));
+            }
+        }
+
+        // Highest score first. For the same score, the most recent post first
+        scored.sort_by(|(sa, ca), (sb, cb)| {
+            sb.total_cmp(sa).then_with(|| cb.header.date.cmp(&ca.header.date))
+        });
+
+        scored.into_iter()
+            .take(related_opt.max_count)
+            .map(|(_, c)| NavLink::from(c))
+            .collect()
+    }
+}
+
+fn word_set(html: &str) -> HashSet<String> {
+    let text = strip_tags(html, " ");
+    text.split(|c: char| !c.is_alphanumeric())
+        // Short words are mostly articles and prepositions
+        .filter(|w| w.chars().count() > 3)
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    let intersection = a.intersection(b).count();
+    intersection as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{create_cont, ContOptions};
+
+    fn nav(id: &str) -> NavLink {
+        NavLink {
+            link: format!("/view/post-{}", id),
+            title: format!("title-of-post-{}", id),
+        }
+    }
+
+    #[test]
+    fn test_previous_and_next() {
+        let contents = vec![
+            create_cont("3", "2024-01-03", ContOptions::default()),
+            create_cont("1", "2024-01-01", ContOptions::default()),
+            create_cont("2", "2024-01-02", ContOptions::default()),
+        ];
+        let opt = RelatedOptions { max_count: 5, text_similarity: false };
+
+        let res = PostNavigation::from(&contents, "post-2", &opt);
+        assert_eq!(res.previous, Some(nav("1")));
+        assert_eq!(res.next, Some(nav("3")));
+
+        let res = PostNavigation::from(&contents, "post-1", &opt);
+        assert_eq!(res.previous, None);
+        assert_eq!(res.next, Some(nav("2")));
+
+        let res = PostNavigation::from(&contents, "post-3", &opt);
+        assert_eq!(res.previous, Some(nav("2")));
+        assert_eq!(res.next, None);
+
+        let res = PostNavigation::from(&contents, "not-a-post", &opt);
+        assert_eq!(res, PostNavigation::default());
+    }
+
+    #[test]
+    fn test_related_by_tags() {
+        let contents = vec![
+            create_cont("1", "2024-01-01", ContOptions { tags: &["rust", "web"], ..Default::default() }),
+            create_cont("2", "2024-01-02", ContOptions { tags: &["rust"], ..Default::default() }),
+            create_cont("3", "2024-01-03", ContOptions { tags: &["rust", "web", "blog"], ..Default::default() }),
+            create_cont("4", "2024-01-04", ContOptions { tags: &["cooking"], ..Default::default() }),
+            create_cont("5", "2024-01-05", ContOptions { tags: &["web"], ..Default::default() }),
+        ];
+        let opt = RelatedOptions { max_count: 5, text_similarity: false };
+        let res = PostNavigation::from(&contents, "post-1", &opt);
+        assert_eq!(res.related, vec![nav("3"), nav("5"), nav("2")]);
+
+        let opt = RelatedOptions { max_count: 1, text_similarity: false };
+        let res = PostNavigation::from(&contents, "post-1", &opt);
+        assert_eq!(res.related, vec![nav("3")]);
+    }
+
+    #[test]
+    fn test_related_by_text() {
+        let contents = vec![
+            create_cont("1", "2024-01-01", ContOptions { rendered: Some("<p>Writing a blog engine using rust</p>"), ..Default::default() }),
+            create_cont("2", "2024-01-02", ContOptions { rendered: Some("<p>Cooking pasta at home</p>"), ..Default::default() }),
+            create_cont("3", "2024-01-03", ContOptions { rendered: Some("<p>A rust blog engine</p>"), ..Default::default() }),
+        ];
+        let opt = RelatedOptions { max_count: 5, text_similarity: false };
+        let res = PostNavigation::from(&contents, "post-1", &opt);
+        assert!(res.related.is_empty());
+
+        let opt = RelatedOptions { max_count: 5, text_similarity: true };
+        let res = PostNavigation::from(&contents, "post-1", &opt);
+        assert_eq!(res.related, vec![nav("3")]);
+    }
+
+    #[test]
+    fn test_backlinks() {
+        let contents = vec![
+            create_cont("1", "2024-01-01", ContOptions::default()),
+            create_cont("2", "2024-01-02", ContOptions::default()),
+            create_cont("3", "2024-01-03", ContOptions::default()),
+        ];
+        let links = vec!["post-1".to_string(), "post-3".to_string(), "removed".to_string()];
+        assert_eq!(PostNavigation::find_backlinks(&contents, &links), vec![nav("3"), nav("1")]);
+        assert!(PostNavigation::find_backlinks(&contents, &[]).is_empty());
+    }
+}
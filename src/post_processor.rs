@@ -2,28 +2,34 @@ use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
-use std::{fs, io};
+use std::io;
 
+use crate::api::{api_tags, ApiContent, ApiKind, ApiPostList};
+use crate::authors::{Author, AuthorRegistry};
 use crate::config::{Config, RssFeed};
 use crate::content::content_file::ContentFile;
-use crate::content::content_format::ContentFormat;
-use crate::content::content_renderer::{BreakTag, ContentRenderer, ImagePrefix, MaxLineCount, PreviewOptions, RenderOptions};
-use crate::content::html_renderer::HtmlRenderer;
-use crate::content::texted_renderer::TextedRenderer;
+use crate::content::content_renderer::{BreakTag, ImagePrefix, MaxLineCount, PreviewOptions, RenderOptions};
+use crate::content::format_registry::FormatRegistry;
+use crate::content::notebook_renderer::{notebook_assets, NotebookAssets};
 use crate::content::Content;
 use crate::content_cache::{ContentCache, Expire};
+use crate::metrics::view_counter::ViewCounts;
 use crate::paginator::Paginator;
-use crate::post_list::{PostList, PostListType};
+use crate::post_navigation::{PostNavigation, RelatedOptions};
 use crate::query_string::QueryString;
+use crate::series::{add_to_series, series_id, SeriesMap};
+use crate::view::archive_renderer::{ArchivePeriod, ArchiveRenderer};
 use crate::view::list_renderer::ListRenderer;
 use crate::view::post_renderer::PostRenderer;
 use crate::view::rss_renderer::RssChannel;
+use crate::view::series_renderer::SeriesRenderer;
+use crate::view::site_context::{SiteContext, WithSite};
+use crate::view::template_registry::TemplateRegistry;
 use anyhow::Result;
 use chrono::{Datelike, NaiveDate, Utc};
 use ntex::web;
 use ntex::web::{Error, HttpRequest};
 use ntex_files::NamedFile;
-use ramhorns::Template;
 use spdlog::info;
 
 #[derive(ramhorns::Content)]
@@ -33,51 +39,6 @@ struct IndexPage {
     days_since_started: i64,
 }
 
-#[derive(Debug, PartialEq)]
-pub struct PostLink {
-    pub post_name: String,
-    pub post_path: PathBuf,
-}
-
-pub fn list_post_files(root_dir: &Path, post_file: &PostListType) -> Result<Vec<PostLink>> {
-    let root_dir = root_dir.to_path_buf();
-    let post_list = PostList {
-        root_dir,
-        post_file: post_file.clone(),
-    };
-
-    let dirs = post_list.retrieve_dirs()?;
-    let mut posts = vec![];
-    for (dir, file_name) in dirs {
-        // Adding default file to directory posts
-        let post_name = dir.iter().last().unwrap().to_str().unwrap().to_string();
-        let post_path = dir.join(file_name);
-
-        posts.push(PostLink {
-            post_name,
-            post_path,
-        });
-    }
-
-    // Retrieve files in post directory
-    let md_posts: Vec<PathBuf> = post_list.retrieve_files()?;
-    for post_file in md_posts {
-        let post_name = post_file.file_stem().unwrap().to_str().unwrap().to_string();
-        let post_path = post_file;
-        posts.push(PostLink {
-            post_name,
-            post_path,
-        });
-    }
-
-    Ok(posts)
-}
-
-pub fn read_template(tpl_dir: &Path, file_name: &str) -> io::Result<String> {
-    let full_path = tpl_dir.join(file_name);
-    fs::read_to_string(full_path)
-}
-
 pub fn get_file(root_dir: &Path, post: String, file: String) -> Result<NamedFile, Error> {
     if post.contains("../") || file.contains("../") {
         return Err(web::error::ErrorUnauthorized("Access forbidden").into());
@@ -87,30 +48,41 @@ pub fn get_file(root_dir: &Path, post: String, file: String) -> Result<NamedFile
     Ok(NamedFile::open(file_path)?)
 }
 
-pub fn render_index(req: HttpRequest, num_of_posts: usize, tpl_dir: &Path,
-                    activity_start_year: i32, blog_start_date: NaiveDate) -> io::Result<String> {
-    let index_tpl_src: String = match read_template(tpl_dir, "index.tpl") {
-        Ok(s) => s,
-        Err(e) => {
-            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Error loading index template: {}", e)));
+/// Image embedded in the notebook of post. The images of a notebook are extracted once and cached by the
+/// notebook path, shared by posts and pages
+pub fn get_notebook_asset(asset_cache: &RwLock<ContentCache<NotebookAssets>>, link_to_files: &HashMap<String, PathBuf>,
+                          post: &str, file: &str) -> Option<web::HttpResponse> {
+    let content_path = link_to_files.get(post)?;
+    if content_path.extension()? != "ipynb" {
+        return None;
+    }
+    let cache_key = content_path.to_str()?;
+    let cached = asset_cache.read().unwrap().get_post(cache_key);
+    let assets = match cached {
+        Some(assets) => assets,
+        None => {
+            info!("Extracting notebook images from file for {}", post);
+            let raw_content = std::fs::read_to_string(content_path).ok()?;
+            asset_cache.write().unwrap().add_post(cache_key, notebook_assets(&raw_content), Expire::Never)
         }
     };
+    let (image, mime) = assets.get(file)?;
+    Some(web::HttpResponse::Ok().content_type(*mime).body(image.clone()))
+}
 
-    let index_tpl = match Template::new(index_tpl_src) {
-        Ok(x) => x,
-        Err(e) => {
-            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Error parsing index template: {}", e)));
-        }
-    };
+pub fn render_index(req: HttpRequest, num_of_posts: usize, templates: &TemplateRegistry,
+                    activity_start_year: i32, blog_start_date: NaiveDate, site: &SiteContext) -> io::Result<String> {
+    let index_tpl = templates.get("index")?;
 
     let days_since_first_post = (Utc::now().date_naive() - blog_start_date).num_days();
     let years_developing = (Utc::now().year() - activity_start_year) as i64;
 
-    let rendered = index_tpl.render(&IndexPage {
+    let page = IndexPage {
         years_developing,
         post_count: num_of_posts as i64,
         days_since_started: days_since_first_post,
-    });
+    };
+    let rendered = index_tpl.render(&WithSite::new(&page, site));
 
     let mut referer: String = match req.headers().get("referer") {
         Some(v) => v.to_str().unwrap().to_string(),
@@ -123,24 +95,23 @@ pub fn render_index(req: HttpRequest, num_of_posts: usize, tpl_dir: &Path,
     Ok(rendered)
 }
 
-pub fn open_content(config: &Config, link_to_files: &HashMap<String, PathBuf>, template_filename: &str, link: &str) -> io::Result<String> {
+/// Reads the file of the post or page link
+pub fn load_content_file(link_to_files: &HashMap<String, PathBuf>, link: &str) -> io::Result<ContentFile> {
     let content_path = match link_to_files.get(link) {
         None => return Err(io::Error::new(io::ErrorKind::NotFound, "Could not find post")),
         Some(path) => path,
     }.clone();
 
-    let content_file = ContentFile::from_file(link.to_string(), content_path)?;
-    let content = match content_file.format {
-        ContentFormat::Texted => TextedRenderer::render(&content_file, RenderOptions::FullContent),
-        ContentFormat::Html => HtmlRenderer::render(&content_file, RenderOptions::FullContent),
-    }?;
+    ContentFile::from_file(link.to_string(), content_path)
+}
 
-    let template_dir = &config.paths.template_dir;
-    let template_path = template_dir.join(template_filename);
-    let template_src = fs::read_to_string(&template_path)?;
+pub fn open_content(templates: &TemplateRegistry, formats: &FormatRegistry, content_file: &ContentFile, template_name: &str,
+                    authors: &AuthorRegistry, navigation: &PostNavigation, site: &SiteContext) -> io::Result<String> {
+    let content = formats.render(content_file, RenderOptions::FullContent)?;
 
-    let post_renderer = PostRenderer::new(&template_src)?;
-    Ok(post_renderer.render(&content))
+    let post_renderer = PostRenderer::new(templates.get(template_name)?);
+    let post_authors = authors.resolve_all(&content.header.authors);
+    Ok(post_renderer.render(&content, &post_authors, navigation, site))
 }
 
 pub fn get_cur_page(req: HttpRequest) -> u32 {
@@ -155,11 +126,22 @@ pub fn get_cur_page(req: HttpRequest) -> u32 {
 pub struct PostListWithTags {
     contents: Vec<Arc<Content>>,
     tag_map: HashMap<String, i32>,
+    series_map: SeriesMap,
+}
+
+impl PostListWithTags {
+    pub fn first_author(&self, authors: &AuthorRegistry, author_id: &str) -> Option<Author> {
+        self.contents.iter()
+            .flat_map(|c| c.header.authors.iter())
+            .map(|name| authors.resolve(name))
+            .find(|author| author.id == author_id)
+    }
 }
 
-pub fn retrieve_post_list(content_cache: &RwLock<ContentCache<Content>>, link_to_files: &HashMap<String, PathBuf>, tag_to_filter: Option<String>, preview_opt: &PreviewOptions) -> io::Result<PostListWithTags> {
+pub fn retrieve_post_list(content_cache: &RwLock<ContentCache<Content>>, formats: &FormatRegistry, link_to_files: &HashMap<String, PathBuf>, tag_to_filter: Option<String>, preview_opt: &PreviewOptions) -> io::Result<PostListWithTags> {
     let mut contents = vec![];
     let mut tag_map = HashMap::new();
+    let mut series_map = SeriesMap::new();
 
     let mut cache = content_cache.read().unwrap();
     for (post_link, content_path) in link_to_files.iter() {
@@ -169,10 +151,7 @@ pub fn retrieve_post_list(content_cache: &RwLock<ContentCache<Content>>, link_to
                 info!("Rendering post preview from file for {}", post_link);
                 let content_file = ContentFile::from_file(post_link.clone(), content_path.clone())?;
                 let img_prefix = ImagePrefix(format!("/view/{}", post_link));
-                let content = match content_file.format {
-                    ContentFormat::Texted => TextedRenderer::render(&content_file, RenderOptions::PreviewOnly(preview_opt.clone(), img_prefix)),
-                    ContentFormat::Html => HtmlRenderer::render(&content_file, RenderOptions::PreviewOnly(preview_opt.clone(), img_prefix)),
-                }?;
+                let content = formats.render(&content_file, RenderOptions::PreviewOnly(preview_opt.clone(), img_prefix))?;
 
                 drop(cache);
 
@@ -190,15 +169,13 @@ pub fn retrieve_post_list(content_cache: &RwLock<ContentCache<Content>>, link_to
         //     info!("Rendering post preview from file for {}", post_link);
         //     let content_file = ContentFile::from_file(post_link.clone(), content_path.clone())?;
         //     let img_prefix = ImagePrefix(format!("/view/{}", post_link));
-        //     match content_file.format {
-        //         ContentFormat::Texted => TextedRenderer::render(&content_file, RenderOptions::PreviewOnly(preview_opt.clone(), img_prefix)),
-        //         ContentFormat::Html => HtmlRenderer::render(&content_file, RenderOptions::PreviewOnly(preview_opt.clone(), img_prefix)),
-        //     }
+        //     formats.render(&content_file, RenderOptions::PreviewOnly(preview_opt.clone(), img_prefix))
         // })?;
 
         for post_tag in content.header.tags.iter() {
             *tag_map.entry(post_tag.clone()).or_insert(0) += 1;
         }
+        add_to_series(&mut series_map, &content);
 
         match tag_to_filter {
             None => contents.push(content),
@@ -210,13 +187,43 @@ pub fn retrieve_post_list(content_cache: &RwLock<ContentCache<Content>>, link_to
         };
     }
 
+    series_map.values_mut().for_each(|series| series.sort());
+
     Ok(PostListWithTags {
         contents,
         tag_map,
+        series_map,
     })
 }
 
-pub fn render_list(config: &Config, posts: PostListWithTags, cur_page: u32) -> io::Result<String> {
+pub fn filter_by_tag(posts: PostListWithTags, tag: &str) -> PostListWithTags {
+    let contents = posts.contents.into_iter()
+        .filter(|c| c.header.tags.iter().any(|post_tag| post_tag == tag))
+        .collect();
+
+    PostListWithTags {
+        contents,
+        tag_map: posts.tag_map,
+        series_map: posts.series_map,
+    }
+}
+
+pub fn filter_by_author(posts: PostListWithTags, authors: &AuthorRegistry, author_id: &str) -> PostListWithTags {
+    let contents = posts.contents.into_iter()
+        .filter(|c| {
+            c.header.authors.iter().any(|name| authors.resolve(name).id == author_id)
+        })
+        .collect();
+
+    PostListWithTags {
+        contents,
+        tag_map: posts.tag_map,
+        series_map: posts.series_map,
+    }
+}
+
+pub fn render_list(config: &Config, templates: &TemplateRegistry, posts: PostListWithTags, cur_page: u32, authors: &AuthorRegistry, author: Option<&Author>,
+                   site: &SiteContext) -> io::Result<String> {
     let tag_map = posts.tag_map;
     let mut contents = posts.contents;
 
@@ -242,22 +249,81 @@ pub fn render_list(config: &Config, posts: PostListWithTags, cur_page: u32) -> i
         x => x,
     };
 
-    let template_dir = &config.paths.template_dir;
-    let template_path = template_dir.join("postlist.tpl");
-    let template_src = fs::read_to_string(&template_path)?;
-    let list_posts = ListRenderer::new(&template_src, paginator.page_count());
-    let list_posts = list_posts.unwrap();
+    let list_posts = ListRenderer::new(templates.get("postlist")?, paginator.page_count());
 
     let content_page = match paginator.get_page(cur_page) {
         Ok(content) => content,
         Err(err_desc) => return Err(io::Error::new(ErrorKind::InvalidInput, err_desc)),
     };
 
-    let res = list_posts.render(content_page, cur_page, tags);
+    let res = list_posts.render(content_page, cur_page, tags, authors, author, site);
     Ok(res)
 }
 
-pub fn render_rss(rss_feed: &RssFeed, posts: PostListWithTags) -> io::Result<Vec<u8>> {
+pub fn render_archive(config: &Config, templates: &TemplateRegistry, posts: PostListWithTags, period: &ArchivePeriod, cur_page: u32, authors: &AuthorRegistry,
+                      site: &SiteContext) -> io::Result<String> {
+    let mut all_contents = posts.contents;
+
+    // sort contents by date reversed
+    all_contents.sort_by(|a, b| {
+        b.header.date.cmp(&a.header.date)
+    });
+
+    let contents: Vec<Arc<Content>> = all_contents.iter()
+        .filter(|c| period.contains(c))
+        .cloned()
+        .collect();
+
+    let page_size = config.defaults.page_size;
+    let paginator = Paginator::from(&contents, page_size);
+    let cur_page = match cur_page { // Sanity check for current page
+        0 => 1,
+        x if x > paginator.page_count() => 1,
+        x => x,
+    };
+
+    let archive_renderer = ArchiveRenderer::new(templates.get("archive")?, paginator.page_count(), contents.len());
+
+    // An empty period still renders the list of periods
+    let content_page = match paginator.page_count() {
+        0 => &[],
+        _ => match paginator.get_page(cur_page) {
+            Ok(content) => content,
+            Err(err_desc) => return Err(io::Error::new(ErrorKind::InvalidInput, err_desc)),
+        },
+    };
+
+    let res = archive_renderer.render(&all_contents, period, content_page, cur_page, authors, site);
+    Ok(res)
+}
+
+/// backlinks are the links of the posts linking to post_link
+pub fn find_navigation(posts: &PostListWithTags, post_link: &str, related_opt: &RelatedOptions, backlinks: &[String]) -> PostNavigation {
+    let mut navigation = PostNavigation::from(&posts.contents, post_link, related_opt);
+    navigation.backlinks = PostNavigation::find_backlinks(&posts.contents, backlinks);
+
+    let series_name = posts.contents.iter()
+        .find(|c| c.link == post_link)
+        .and_then(|c| c.header.series.as_ref());
+    if let Some(series_name) = series_name {
+        navigation.series = posts.series_map.get(&series_id(series_name))
+            .and_then(|series| series.navigation(post_link));
+    }
+
+    navigation
+}
+
+pub fn render_series(templates: &TemplateRegistry, posts: &PostListWithTags, id: &str, authors: &AuthorRegistry, site: &SiteContext) -> io::Result<Option<String>> {
+    let Some(series) = posts.series_map.get(id) else {
+        return Ok(None);
+    };
+
+    let series_renderer = SeriesRenderer::new(templates.get("series")?);
+
+    Ok(Some(series_renderer.render(series, authors, site)))
+}
+
+pub fn render_rss(rss_feed: &RssFeed, posts: PostListWithTags, authors: &AuthorRegistry) -> io::Result<Vec<u8>> {
     let mut contents = posts.contents;
 
     // sort contents by date reversed
@@ -280,6 +346,7 @@ pub fn render_rss(rss_feed: &RssFeed, posts: PostListWithTags) -> io::Result<Vec
         ch_title,
         ch_link,
         ch_desc,
+        authors,
     };
     let xml = match rss.render(contents) {
         Ok(xml) => xml,
@@ -289,6 +356,47 @@ pub fn render_rss(rss_feed: &RssFeed, posts: PostListWithTags) -> io::Result<Vec
     Ok(xml)
 }
 
+/// Page of posts of the JSON API, /api/posts
+pub fn render_api_list(config: &Config, posts: PostListWithTags, cur_page: u32, tag: Option<&str>) -> io::Result<String> {
+    let mut contents = posts.contents;
+
+    // sort contents by date reversed
+    contents.sort_by(|a, b| {
+        b.header.date.cmp(&a.header.date)
+    });
+
+    let page_size = config.defaults.page_size;
+    let paginator = Paginator::from(&contents, page_size);
+    let post_list = ApiPostList::new(&paginator, cur_page, page_size, tag);
+    to_json(&post_list)
+}
+
+/// Post or page of the JSON API, with the summary and the full content
+pub fn render_api_content(formats: &FormatRegistry, link_to_files: &HashMap<String, PathBuf>, kind: ApiKind, link: &str, preview_opt: &PreviewOptions) -> io::Result<String> {
+    let content_file = load_content_file(link_to_files, link)?;
+    let img_prefix = match kind {
+        ApiKind::Post => ImagePrefix(format!("/view/{}", link)),
+        ApiKind::Page => ImagePrefix(format!("/page/{}", link)),
+    };
+    let preview = RenderOptions::PreviewOnly(preview_opt.clone(), img_prefix);
+    let summary = formats.render(&content_file, preview)?;
+    let full = formats.render(&content_file, RenderOptions::FullContent)?;
+
+    to_json(&ApiContent::new(kind, &summary, Some(&full)))
+}
+
+/// Tags of the JSON API, /api/tags
+pub fn render_api_tags(posts: &PostListWithTags) -> io::Result<String> {
+    to_json(&api_tags(&posts.tag_map))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> io::Result<String> {
+    match serde_json::to_string(value) {
+        Ok(json) => Ok(json),
+        Err(e) => Err(io::Error::new(ErrorKind::InvalidData, format!("Error serializing json: {}", e))),
+    }
+}
+
 pub fn get_preview_option(config: &Config) -> PreviewOptions {
     let mut max_line_count = None;
     let mut tag = "<!-- more -->";
@@ -306,22 +414,15 @@ pub fn get_preview_option(config: &Config) -> PreviewOptions {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_extract_last() {
-        let list_type = PostListType::IndexBaseName("index".to_string());
-        let posts = list_post_files(&PathBuf::from("res/posts"), &list_type).unwrap();
-        
-        let expected = vec![
-            PostLink { post_name: "html_post_with_image".to_string(), post_path: PathBuf::from("res/posts/html_post_with_image/index.html") },
-            PostLink { post_name: "post_with_image".to_string(), post_path: PathBuf::from("res/posts/post_with_image/index.md") },
-            PostLink { post_name: "html_post".to_string(), post_path: PathBuf::from("res/posts/html_post.htm") },
-            PostLink { post_name: "post_without_images".to_string(), post_path: PathBuf::from("res/posts/post_without_images.md") },
-            PostLink { post_name: "new_post".to_string(), post_path: PathBuf::from("res/posts/new_post.md") },
-        ];
-        assert_eq!(expected, posts);
+pub fn get_site_context(config: &Config, posts: &PostListWithTags, views: &ViewCounts) -> SiteContext {
+    let recent_count = config.defaults.recent_post_count.unwrap_or(5);
+    let popular_count = config.defaults.popular_post_count.unwrap_or(5);
+    SiteContext::new(&config.site, &posts.contents, recent_count, views, popular_count)
+}
+
+pub fn get_related_options(config: &Config) -> RelatedOptions {
+    RelatedOptions {
+        max_count: config.defaults.related_post_count.unwrap_or(5),
+        text_similarity: config.defaults.related_text_similarity.unwrap_or(false),
     }
-}
\ No newline at end of file
+}
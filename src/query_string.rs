@@ -16,6 +16,10 @@ impl QueryString {
         }
     }
 
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.items.get(key).map(|v| v.as_str())
+    }
+
     pub fn get_page(&self) -> u32 {
         let one = "1".to_string();
         let val = self.items.get("page").unwrap_or(&one);
@@ -49,6 +53,8 @@ mod tests {
         };
 
         assert_eq!(QueryString::from(buf), expected);
+        assert_eq!(QueryString::from(buf).get("cheese"), Some("comté"));
+        assert_eq!(QueryString::from(buf).get("wine"), None);
     }
 
     #[test]
@@ -63,7 +69,7 @@ mod tests {
     #[test]
     fn test_parse_key_only_query_str() {
         let buf = "key-only";
-        let expected: HashMap<String, String> = vec![("key-only", "")].iter().map(|(x, y)| (x.to_string(), y.to_string())).collect::<HashMap<_, _>>();
+        let expected: HashMap<String, String> = [("key-only", "")].iter().map(|(x, y)| (x.to_string(), y.to_string())).collect::<HashMap<_, _>>();
         assert_eq!(QueryString::from(buf), QueryString { items: expected });
     }
 }
\ No newline at end of file
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::content::Content;
+use crate::post_navigation::NavLink;
+use crate::text_utils::slugify;
+
+/// Posts sharing the same SERIES header, in reading order
+pub struct Series {
+    pub id: String,
+    pub name: String,
+    pub contents: Vec<Arc<Content>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeriesPartLink {
+    pub number: usize,
+    pub link: String,
+    pub title: String,
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeriesNavigation {
+    pub id: String,
+    pub name: String,
+    pub link: String,
+    /// Position of the current post, starting in 1
+    pub part: usize,
+    pub count: usize,
+    pub parts: Vec<SeriesPartLink>,
+    pub previous: Option<NavLink>,
+    pub next: Option<NavLink>,
+}
+
+/// Series id -> Series
+pub type SeriesMap = HashMap<String, Series>;
+
+pub fn series_id(name: &str) -> String {
+    slugify(name)
+}
+
+impl Series {
+    fn new(name: &str) -> Self {
+        Series {
+            id: series_id(name),
+            name: name.to_string(),
+            contents: vec![],
+        }
+    }
+
+    pub fn link(&self) -> String {
+        format!("/series/{}/", self.id)
+    }
+
+    /// Sort by SERIES_PART. Posts without a part go to the end, by date
+    pub fn sort(&mut self) {
+        self.contents.sort_by(|a, b| {
+            let part_a = a.header.series_part.unwrap_or(u32::MAX);
+            let part_b = b.header.series_part.unwrap_or(u32::MAX);
+            part_a.cmp(&part_b)
+                .then_with(|| a.header.date.cmp(&b.header.date))
+        });
+    }
+
+    pub fn navigation(&self, post_link: &str) -> Option<SeriesNavigation> {
+        let pos = self.contents.iter().position(|c| c.link == post_link)?;
+
+        let parts = self.contents.iter().enumerate().map(|(i, c)| {
+            SeriesPartLink {
+                number: i + 1,
+                link: format!("/view/{}", &c.link),
+                title: c.title.clone(),
+                current: i == pos,
+            }
+        }).collect();
+
+        let nav_link = |c: &Arc<Content>| NavLink {
+            link: format!("/view/{}", &c.link),
+            title: c.title.clone(),
+        };
+
+        let previous = match pos {
+            0 => None,
+            x => self.contents.get(x - 1).map(nav_link),
+        };
+        let next = self.contents.get(pos + 1).map(nav_link);
+
+        Some(SeriesNavigation {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            link: self.link(),
+            part: pos + 1,
+            count: self.contents.len(),
+            parts,
+            previous,
+            next,
+        })
+    }
+}
+
+pub fn add_to_series(series_map: &mut SeriesMap, content: &Arc<Content>) {
+    if let Some(ref name) = content.header.series {
+        series_map.entry(series_id(name))
+            .or_insert_with(|| Series::new(name))
+            .contents.push(content.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{create_cont, ContOptions};
+
+    #[test]
+    fn test_series_navigation() {
+        let mut series_map = SeriesMap::new();
+        let contents = [
+            create_cont("a", "2024-01-01", ContOptions { series: Some("Rust Tutorial"), series_part: Some(2), ..Default::default() }),
+            create_cont("b", "2024-01-02", ContOptions { series: Some("Rust Tutorial"), series_part: Some(1), ..Default::default() }),
+            create_cont("c", "2024-01-03", ContOptions::default()),
+            create_cont("d", "2024-01-04", ContOptions { series: Some("Rust Tutorial"), ..Default::default() }),
+            create_cont("e", "2024-01-05", ContOptions { series: Some("Other"), ..Default::default() }),
+        ];
+        for content in contents.iter() {
+            add_to_series(&mut series_map, content);
+        }
+        series_map.values_mut().for_each(|s| s.sort());
+
+        assert_eq!(series_map.len(), 2);
+        let series = series_map.get("rust_tutorial").unwrap();
+        let links: Vec<_> = series.contents.iter().map(|c| c.link.as_str()).collect();
+        assert_eq!(links, vec!["post-b", "post-a", "post-d"]);
+        assert!(series.navigation("post-c").is_none());
+
+        let nav = series.navigation("post-a").unwrap();
+        assert_eq!(nav.name, "Rust Tutorial");
+        assert_eq!(nav.link, "/series/rust_tutorial/");
+        assert_eq!(nav.part, 2);
+        assert_eq!(nav.count, 3);
+        assert_eq!(nav.previous.unwrap().link, "/view/post-b");
+        assert_eq!(nav.next.unwrap().link, "/view/post-d");
+        let current: Vec<_> = nav.parts.iter().map(|p| p.current).collect();
+        assert_eq!(current, vec![false, true, false]);
+    }
+}
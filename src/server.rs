@@ -2,21 +2,45 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
+use crate::api::ApiKind;
+use crate::authors::AuthorRegistry;
+use crate::client_address::ClientAddressResolver;
 use crate::config::Config;
+use crate::content::format_registry::FormatRegistry;
+use crate::content::notebook_renderer::NotebookAssets;
+use crate::content::shortcodes::Shortcodes;
+use crate::content::wiki_links::WikiLinks;
 use crate::content::Content;
 use crate::content_cache::{ContentCache, Expire};
 use crate::metrics::metric_handler::MetricHandler;
 use crate::metrics::metric_sender::MetricSender;
 use crate::metrics::metric_writer::MetricWriter;
-use crate::post_list::PostListType;
+use crate::metrics::server_metrics::{RequestMetrics, ServerGauges, ServerMetrics};
+use crate::metrics::view_counter::ViewCounter;
+use crate::post_list::{list_post_files, PostLink, PostListType};
+use crate::post_navigation::PostNavigation;
+use crate::query_string::QueryString;
+use crate::view::archive_renderer::ArchivePeriod;
+use crate::view::site_context::SiteContext;
+use crate::view::template_registry::TemplateRegistry;
 use crate::post_processor::*;
+use crate::theme::{configured_theme, layered_dirs, resolve_file, Theme};
 use crate::util::toml_date::TomlDate;
 use anyhow::Result;
 use chrono::Duration;
+use std::time::Instant;
 use ntex::web;
 use ntex::web::HttpRequest;
 use ntex_files::NamedFile;
-use spdlog::{debug, info};
+use spdlog::{debug, error, info, warn};
+use tokio::task::JoinHandle;
+
+/// Templates rendered by the routes
+const REQUIRED_TEMPLATES: &[&str] = &["index", "view", "page", "postlist", "archive", "series"];
+const TEMPLATE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+const VIEW_COUNTS_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+/// Rendered posts are refreshed after it when the view counters are enabled, to update their view_count
+const VIEW_COUNT_REFRESH_MINUTES: i64 = 1;
 
 struct AppState {
     /// Links of posts. E.g. my-blog.ca/view/my_post_url
@@ -29,8 +53,28 @@ struct AppState {
     post_cache: RwLock<ContentCache<String>>,
     /// Cache for post and page summary, used in listing
     summary_cache: RwLock<ContentCache<Content>>,
+    /// Cache for the images embedded in notebooks, by notebook file
+    asset_cache: RwLock<ContentCache<NotebookAssets>>,
     /// Sender to generate access metrics
     metric_sender: MetricSender,
+    /// Views of each post, for view_count and the popular posts
+    view_counter: ViewCounter,
+    /// Requests, render latency and caches, served at /metrics
+    server_metrics: Arc<ServerMetrics>,
+    /// Client addresses behind the trusted proxies
+    client_addresses: ClientAddressResolver,
+    /// Authors described in the authors file
+    authors: AuthorRegistry,
+    /// Templates of template_dir and the theme, parsed at startup and when they change
+    templates: RwLock<TemplateRegistry>,
+    /// Theme providing the templates and public files not found locally
+    theme: Option<Theme>,
+    /// Renderers of the post and page files, by extension
+    formats: FormatRegistry,
+    /// Targets of the wiki links and the posts linking to each one
+    wiki_links: Arc<WikiLinks>,
+    /// Built-in and user shortcodes, reloaded with the templates
+    shortcodes: Arc<Shortcodes>,
 }
 
 // Begin: Redirect region --------
@@ -58,11 +102,10 @@ async fn page(
     app_state: web::types::State<Arc<AppState>>,
 ) -> web::HttpResponse {
     let page_name = page_name.into_inner();
-    let origin: String = get_origin(&req);
+    let origin: String = get_origin(&req, &app_state);
     app_state
         .metric_sender
-        .page(page_name.clone(), origin)
-        .await;
+        .page(page_name.clone(), origin).await;
 
     let read_cache = app_state.post_cache.read().unwrap();
     let rendered_page = match read_cache.get_page(&page_name) {
@@ -73,14 +116,28 @@ async fn page(
             info!("Rendering page {} from file", page_name);
             let config = &app_state.config.read().unwrap();
             let page_links = &app_state.page_links.read().unwrap();
-            let content = match open_content(config, page_links, "page.tpl", &page_name) {
+            let navigation = PostNavigation::default();
+            let site = match load_site_context(&app_state, config) {
+                Ok(site) => site,
+                Err(e) => {
+                    return web::HttpResponse::InternalServerError()
+                        .body(format!("Error listing posts: {}", e))
+                }
+            };
+            let render_start = Instant::now();
+            let content = load_content_file(page_links, &page_name).and_then(|content_file| {
+                open_content(&app_state.templates.read().unwrap(), &app_state.formats, &content_file, "page", &app_state.authors, &navigation, &site)
+            });
+            app_state.server_metrics.add_render("page", render_start.elapsed());
+            let content = match content {
                 Ok(content) => content,
                 Err(e) => {
                     return web::HttpResponse::BadRequest()
                         .body(format!("Error loading page {}: {}", &page_name, e));
                 }
             };
-            write_cache.add_page(&page_name, content, Expire::Never)
+            // The site context of the page lists the recent posts, so it is rendered again like the index
+            write_cache.add_page(&page_name, content, Expire::After(Duration::days(1)))
         }
         Some(content) => {
             debug!("Returning cached page for {}", &page_name);
@@ -102,11 +159,10 @@ async fn view(
 ) -> web::HttpResponse {
     let post_name = post_name.into_inner();
 
-    let origin: String = get_origin(&req);
+    let origin: String = get_origin(&req, &app_state);
     app_state
         .metric_sender
-        .view(post_name.clone(), origin)
-        .await;
+        .view(post_name.clone(), origin).await;
 
     let read_cache = app_state.post_cache.read().unwrap();
     let rendered_post = match read_cache.get_post(&post_name) {
@@ -117,14 +173,35 @@ async fn view(
             info!("Rendering post {} from file", post_name);
             let config = &app_state.config.read().unwrap();
             let post_links = &app_state.post_links.read().unwrap();
-            let content = match open_content(config, post_links, "view.tpl", &post_name) {
+
+            // Previous, next and related posts are taken from the post summaries
+            let preview_opt = get_preview_option(config);
+            let (navigation, site) = match retrieve_post_list(&app_state.summary_cache, &app_state.formats, post_links, None, &preview_opt) {
+                Ok(posts) => (find_navigation(&posts, &post_name, &get_related_options(config), app_state.wiki_links.backlinks(&post_name)),
+                              get_site_context(config, &posts, &app_state.view_counter.counts())),
+                Err(e) => {
+                    return web::HttpResponse::InternalServerError()
+                        .body(format!("Error listing posts: {}", e))
+                }
+            };
+
+            let render_start = Instant::now();
+            let content = load_content_file(post_links, &post_name).and_then(|content_file| {
+                open_content(&app_state.templates.read().unwrap(), &app_state.formats, &content_file, "view", &app_state.authors, &navigation, &site)
+            });
+            app_state.server_metrics.add_render("post", render_start.elapsed());
+            let content = match content {
                 Ok(content) => content,
                 Err(e) => {
                     return web::HttpResponse::BadRequest()
                         .body(format!("Error loading post {}: {}", &post_name, e));
                 }
             };
-            write_cache.add_post(&post_name, content, Expire::Never)
+            let expire = match app_state.view_counter.is_enabled() {
+                true => Expire::After(Duration::minutes(VIEW_COUNT_REFRESH_MINUTES)),
+                false => Expire::Never,
+            };
+            write_cache.add_post(&post_name, content, expire)
         }
         Some(content) => {
             debug!("Returning cached post for {}", &post_name);
@@ -132,6 +209,7 @@ async fn view(
         }
     }
     .to_string();
+    app_state.view_counter.add_view(&post_name);
 
     web::HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
@@ -140,7 +218,7 @@ async fn view(
 
 #[web::get("/list")]
 async fn list(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) -> web::HttpResponse {
-    let origin: String = get_origin(&req);
+    let origin: String = get_origin(&req, &app_state);
     app_state.metric_sender.list(None, origin).await;
 
     let config = app_state.config.read().unwrap();
@@ -148,7 +226,7 @@ async fn list(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) ->
     let post_links = app_state.post_links.read().unwrap();
 
     let rendered_posts =
-        match retrieve_post_list(&app_state.summary_cache, &post_links, None, &preview_opt) {
+        match retrieve_post_list(&app_state.summary_cache, &app_state.formats, &post_links, None, &preview_opt) {
             Ok(posts) => posts,
             Err(e) => {
                 return web::HttpResponse::InternalServerError()
@@ -156,8 +234,9 @@ async fn list(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) ->
             }
         };
 
+    let site = get_site_context(&config, &rendered_posts, &app_state.view_counter.counts());
     let cur_page: u32 = get_cur_page(req);
-    let post_list = match render_list(&config, rendered_posts, cur_page) {
+    let post_list = match render_list(&config, &app_state.templates.read().unwrap(), rendered_posts, cur_page, &app_state.authors, None, &site) {
         Ok(posts) => posts,
         Err(e) => {
             return web::HttpResponse::InternalServerError()
@@ -178,31 +257,89 @@ async fn list_with_tags(
 ) -> web::HttpResponse {
     let tag = path.into_inner();
 
-    let origin: String = get_origin(&req);
+    let origin: String = get_origin(&req, &app_state);
     app_state
         .metric_sender
-        .list(Some(tag.clone()), origin)
-        .await;
+        .list(Some(tag.clone()), origin).await;
 
     let config = app_state.config.read().unwrap();
     let preview_opt = get_preview_option(&config);
     let post_links = app_state.post_links.read().unwrap();
 
-    let rendered_posts = match retrieve_post_list(
-        &app_state.summary_cache,
-        &post_links,
-        Some(tag),
-        &preview_opt,
-    ) {
+    let (rendered_posts, site) =
+        match retrieve_post_list(&app_state.summary_cache, &app_state.formats, &post_links, None, &preview_opt) {
+            Ok(posts) => {
+                let site = get_site_context(&config, &posts, &app_state.view_counter.counts());
+                (filter_by_tag(posts, &tag), site)
+            }
+            Err(e) => {
+                return web::HttpResponse::InternalServerError()
+                    .body(format!("Error listing posts: {}", e))
+            }
+        };
+
+    let cur_page: u32 = get_cur_page(req);
+    let post_list = match render_list(&config, &app_state.templates.read().unwrap(), rendered_posts, cur_page, &app_state.authors, None, &site) {
         Ok(posts) => posts,
         Err(e) => {
             return web::HttpResponse::InternalServerError()
-                .body(format!("Error listing posts: {}", e))
+                .body(format!("Error rendering post list: {}", e))
         }
     };
 
+    web::HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(post_list)
+}
+
+#[web::get("/author/{author}")]
+async fn author_wo_slash(path: web::types::Path<String>) -> web::HttpResponse {
+    web::HttpResponse::TemporaryRedirect()
+        .header("Location", format!("{}/", path.into_inner()))
+        .content_type("text/html; charset=utf-8")
+        .finish()
+}
+
+#[web::get("/author/{author}/")]
+async fn author(
+    req: HttpRequest,
+    path: web::types::Path<String>,
+    app_state: web::types::State<Arc<AppState>>,
+) -> web::HttpResponse {
+    let author_id = path.into_inner();
+
+    let origin: String = get_origin(&req, &app_state);
+    app_state
+        .metric_sender
+        .author(author_id.clone(), origin).await;
+
+    let config = app_state.config.read().unwrap();
+    let preview_opt = get_preview_option(&config);
+    let post_links = app_state.post_links.read().unwrap();
+
+    let (rendered_posts, site) =
+        match retrieve_post_list(&app_state.summary_cache, &app_state.formats, &post_links, None, &preview_opt) {
+            Ok(posts) => {
+                let site = get_site_context(&config, &posts, &app_state.view_counter.counts());
+                (filter_by_author(posts, &app_state.authors, &author_id), site)
+            }
+            Err(e) => {
+                return web::HttpResponse::InternalServerError()
+                    .body(format!("Error listing posts: {}", e))
+            }
+        };
+
+    // Authors missing in the authors file are still listed, using the name from the post header
+    let author = match app_state.authors.get(&author_id) {
+        Some(author) => author.clone(),
+        None => match rendered_posts.first_author(&app_state.authors, &author_id) {
+            Some(author) => author,
+            None => return web::HttpResponse::NotFound().body(format!("Author {} not found", author_id)),
+        },
+    };
+
     let cur_page: u32 = get_cur_page(req);
-    let post_list = match render_list(&config, rendered_posts, cur_page) {
+    let post_list = match render_list(&config, &app_state.templates.read().unwrap(), rendered_posts, cur_page, &app_state.authors, Some(&author), &site) {
         Ok(posts) => posts,
         Err(e) => {
             return web::HttpResponse::InternalServerError()
@@ -215,9 +352,153 @@ async fn list_with_tags(
         .body(post_list)
 }
 
+#[web::get("/series/{series}")]
+async fn series_wo_slash(path: web::types::Path<String>) -> web::HttpResponse {
+    web::HttpResponse::TemporaryRedirect()
+        .header("Location", format!("{}/", path.into_inner()))
+        .content_type("text/html; charset=utf-8")
+        .finish()
+}
+
+#[web::get("/series/{series}/")]
+async fn series(
+    req: HttpRequest,
+    path: web::types::Path<String>,
+    app_state: web::types::State<Arc<AppState>>,
+) -> web::HttpResponse {
+    let series_id = path.into_inner();
+
+    let origin: String = get_origin(&req, &app_state);
+    app_state
+        .metric_sender
+        .series(series_id.clone(), origin).await;
+
+    let config = app_state.config.read().unwrap();
+    let preview_opt = get_preview_option(&config);
+    let post_links = app_state.post_links.read().unwrap();
+
+    let rendered_posts =
+        match retrieve_post_list(&app_state.summary_cache, &app_state.formats, &post_links, None, &preview_opt) {
+            Ok(posts) => posts,
+            Err(e) => {
+                return web::HttpResponse::InternalServerError()
+                    .body(format!("Error listing posts: {}", e))
+            }
+        };
+
+    let site = get_site_context(&config, &rendered_posts, &app_state.view_counter.counts());
+    let series_page = match render_series(&app_state.templates.read().unwrap(), &rendered_posts, &series_id, &app_state.authors, &site) {
+        Ok(Some(rendered)) => rendered,
+        Ok(None) => {
+            return web::HttpResponse::NotFound()
+                .body(format!("Series {} not found", series_id))
+        }
+        Err(e) => {
+            return web::HttpResponse::InternalServerError()
+                .body(format!("Error rendering series: {}", e))
+        }
+    };
+
+    web::HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(series_page)
+}
+
+#[web::get("/archive")]
+async fn archive_wo_slash() -> web::HttpResponse {
+    web::HttpResponse::TemporaryRedirect()
+        .header("Location", "/archive/")
+        .content_type("text/html; charset=utf-8")
+        .finish()
+}
+
+#[web::get("/archive/")]
+async fn archive(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) -> web::HttpResponse {
+    render_archive_page(req, ArchivePeriod::all(), &app_state).await
+}
+
+#[web::get("/archive/{year}")]
+async fn archive_year_wo_slash(path: web::types::Path<String>) -> web::HttpResponse {
+    web::HttpResponse::TemporaryRedirect()
+        .header("Location", format!("{}/", path.into_inner()))
+        .content_type("text/html; charset=utf-8")
+        .finish()
+}
+
+#[web::get("/archive/{year}/")]
+async fn archive_year(
+    req: HttpRequest,
+    path: web::types::Path<i32>,
+    app_state: web::types::State<Arc<AppState>>,
+) -> web::HttpResponse {
+    let period = ArchivePeriod {
+        year: Some(path.into_inner()),
+        month: None,
+    };
+    render_archive_page(req, period, &app_state).await
+}
+
+#[web::get("/archive/{year}/{month}")]
+async fn archive_month_wo_slash(path: web::types::Path<(String, String)>) -> web::HttpResponse {
+    web::HttpResponse::TemporaryRedirect()
+        .header("Location", format!("{}/", path.into_inner().1))
+        .content_type("text/html; charset=utf-8")
+        .finish()
+}
+
+#[web::get("/archive/{year}/{month}/")]
+async fn archive_month(
+    req: HttpRequest,
+    path: web::types::Path<(i32, u32)>,
+    app_state: web::types::State<Arc<AppState>>,
+) -> web::HttpResponse {
+    let (year, month) = path.into_inner();
+    if !(1..=12).contains(&month) {
+        return web::HttpResponse::BadRequest().body(format!("Invalid month {}", month));
+    }
+
+    let period = ArchivePeriod {
+        year: Some(year),
+        month: Some(month),
+    };
+    render_archive_page(req, period, &app_state).await
+}
+
+async fn render_archive_page(req: HttpRequest, period: ArchivePeriod, app_state: &Arc<AppState>) -> web::HttpResponse {
+    let origin: String = get_origin(&req, app_state);
+    app_state.metric_sender.archive(period.title(), origin).await;
+
+    let config = app_state.config.read().unwrap();
+    let preview_opt = get_preview_option(&config);
+    let post_links = app_state.post_links.read().unwrap();
+
+    let rendered_posts =
+        match retrieve_post_list(&app_state.summary_cache, &app_state.formats, &post_links, None, &preview_opt) {
+            Ok(posts) => posts,
+            Err(e) => {
+                return web::HttpResponse::InternalServerError()
+                    .body(format!("Error listing posts: {}", e))
+            }
+        };
+
+    let site = get_site_context(&config, &rendered_posts, &app_state.view_counter.counts());
+    let cur_page: u32 = get_cur_page(req);
+    let archive_page = match render_archive(&config, &app_state.templates.read().unwrap(), rendered_posts, &period, cur_page, &app_state.authors, &site) {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            return web::HttpResponse::InternalServerError()
+                .body(format!("Error rendering archive: {}", e))
+        }
+    };
+
+    web::HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(archive_page)
+}
+
 #[web::get("/rss")]
 async fn rss(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) -> web::HttpResponse {
-    let origin: String = get_origin(&req);
+    let origin: String = get_origin(&req, &app_state);
     app_state.metric_sender.rss(origin).await;
 
     let config = app_state.config.read().unwrap();
@@ -225,7 +506,7 @@ async fn rss(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) -> w
     if let Some(ref rss_feed) = config.rss_feed {
         let preview_opt = get_preview_option(&config);
         let rendered_posts =
-            match retrieve_post_list(&app_state.summary_cache, post_links, None, &preview_opt) {
+            match retrieve_post_list(&app_state.summary_cache, &app_state.formats, post_links, None, &preview_opt) {
                 Ok(posts) => posts,
                 Err(e) => {
                     return web::HttpResponse::InternalServerError()
@@ -233,7 +514,7 @@ async fn rss(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) -> w
                 }
             };
 
-        let post_list = match render_rss(rss_feed, rendered_posts) {
+        let post_list = match render_rss(rss_feed, rendered_posts, &app_state.authors) {
             Ok(posts) => posts,
             Err(e) => {
                 return web::HttpResponse::InternalServerError()
@@ -251,22 +532,30 @@ async fn rss(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) -> w
 
 #[web::get("/view/{post}/{file}")]
 async fn post_files(
+    req: HttpRequest,
     path: web::types::Path<(String, String)>,
     app_state: web::types::State<Arc<AppState>>,
-) -> Result<NamedFile, web::Error> {
+) -> Result<web::HttpResponse, web::Error> {
     let (post, file) = path.into_inner();
+    if let Some(asset) = get_notebook_asset(&app_state.asset_cache, &app_state.post_links.read().unwrap(), &post, &file) {
+        return Ok(asset);
+    }
     let posts_dir = &app_state.config.read().unwrap().paths.posts_dir;
-    get_file(posts_dir, post, file)
+    Ok(get_file(posts_dir, post, file)?.into_response(&req))
 }
 
 #[web::get("/page/{post}/{file}")]
 async fn page_files(
+    req: HttpRequest,
     path: web::types::Path<(String, String)>,
     app_state: web::types::State<Arc<AppState>>,
-) -> Result<NamedFile, web::Error> {
+) -> Result<web::HttpResponse, web::Error> {
     let (post, file) = path.into_inner();
+    if let Some(asset) = get_notebook_asset(&app_state.asset_cache, &app_state.page_links.read().unwrap(), &post, &file) {
+        return Ok(asset);
+    }
     let pages_dir = &app_state.config.read().unwrap().paths.pages_dir;
-    get_file(pages_dir, post, file)
+    Ok(get_file(pages_dir, post, file)?.into_response(&req))
 }
 
 #[web::get("/public/{file_name}")]
@@ -279,14 +568,17 @@ async fn public_files(
     }
 
     let config = app_state.config.read().unwrap();
-    let file_path = config.paths.public_dir.join(path.into_inner());
+    let public_dirs = layered_dirs(&config.paths.public_dir, app_state.theme.as_ref().map(|t| t.public_dir()));
+    let Some(file_path) = resolve_file(&public_dirs, &path) else {
+        return Err(web::error::ErrorNotFound("File not found").into());
+    };
 
     Ok(NamedFile::open(file_path)?)
 }
 
 #[web::get("/")]
 async fn index(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) -> web::HttpResponse {
-    let origin: String = get_origin(&req);
+    let origin: String = get_origin(&req, &app_state);
     app_state.metric_sender.index(origin).await;
 
     let read_cache = app_state.post_cache.read().unwrap();
@@ -301,13 +593,21 @@ async fn index(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) ->
 
             let TomlDate(blog_start_date) = config.personal.blog_start_date;
             let activity_start_year = config.personal.activity_start_year;
+            let site = match load_site_context(&app_state, &config) {
+                Ok(site) => site,
+                Err(e) => {
+                    return web::HttpResponse::InternalServerError()
+                        .body(format!("Error listing posts: {}", e))
+                }
+            };
 
             let rendered_post = match render_index(
                 req,
                 num_of_posts,
-                &config.paths.template_dir,
+                &app_state.templates.read().unwrap(),
                 activity_start_year,
                 blog_start_date,
+                &site,
             ) {
                 Ok(rendered_post) => rendered_post,
                 Err(e) => {
@@ -330,29 +630,169 @@ async fn index(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) ->
         .body(rendered_page)
 }
 
-fn get_origin(req: &web::HttpRequest) -> String {
-    if let Some(header) = req.headers().get("X-Forwarded-For") {
-        if let Ok(addr) = header.to_str() {
-            return addr.to_string();
+// Begin: JSON API region --------
+#[web::get("/api/posts")]
+async fn api_posts(req: HttpRequest, app_state: web::types::State<Arc<AppState>>) -> web::HttpResponse {
+    let query = QueryString::from(req.uri().query().unwrap_or(""));
+    let tag = query.get("tag").map(|t| t.to_string());
+    let cur_page = query.get_page();
+
+    let config = app_state.config.read().unwrap();
+    let preview_opt = get_preview_option(&config);
+    let post_links = app_state.post_links.read().unwrap();
+    let res = retrieve_post_list(&app_state.summary_cache, &app_state.formats, &post_links, tag.clone(), &preview_opt)
+        .and_then(|posts| render_api_list(&config, posts, cur_page, tag.as_deref()));
+    api_response(res)
+}
+
+#[web::get("/api/posts/{post}")]
+async fn api_post(path: web::types::Path<String>, app_state: web::types::State<Arc<AppState>>) -> web::HttpResponse {
+    let preview_opt = get_preview_option(&app_state.config.read().unwrap());
+    let post_links = app_state.post_links.read().unwrap();
+    api_response(render_api_content(&app_state.formats, &post_links, ApiKind::Post, &path, &preview_opt))
+}
+
+#[web::get("/api/pages/{page}")]
+async fn api_page(path: web::types::Path<String>, app_state: web::types::State<Arc<AppState>>) -> web::HttpResponse {
+    let preview_opt = get_preview_option(&app_state.config.read().unwrap());
+    let page_links = app_state.page_links.read().unwrap();
+    api_response(render_api_content(&app_state.formats, &page_links, ApiKind::Page, &path, &preview_opt))
+}
+
+#[web::get("/api/tags")]
+async fn api_tags(app_state: web::types::State<Arc<AppState>>) -> web::HttpResponse {
+    let config = app_state.config.read().unwrap();
+    let preview_opt = get_preview_option(&config);
+    let post_links = app_state.post_links.read().unwrap();
+    let res = retrieve_post_list(&app_state.summary_cache, &app_state.formats, &post_links, None, &preview_opt)
+        .and_then(|posts| render_api_tags(&posts));
+    api_response(res)
+}
+
+/// Json body or, on error, {"error": "description"}
+fn api_response(res: std::io::Result<String>) -> web::HttpResponse {
+    match res {
+        Ok(json) => web::HttpResponse::Ok()
+            .content_type("application/json")
+            .body(json),
+        Err(e) => {
+            let mut response = match e.kind() {
+                std::io::ErrorKind::NotFound => web::HttpResponse::NotFound(),
+                _ => web::HttpResponse::InternalServerError(),
+            };
+            response
+                .content_type("application/json")
+                .body(serde_json::json!({ "error": e.to_string() }).to_string())
         }
     }
+}
+// End: JSON API region --------
+
+#[web::get("/metrics")]
+async fn server_metrics_endpoint(app_state: web::types::State<Arc<AppState>>) -> web::HttpResponse {
+    let gauges = ServerGauges {
+        posts: app_state.post_links.read().unwrap().len(),
+        pages: app_state.page_links.read().unwrap().len(),
+        post_cache: app_state.post_cache.read().unwrap().stats(),
+        summary_cache: app_state.summary_cache.read().unwrap().stats(),
+        metric_drops: app_state.metric_sender.dropped(),
+    };
+    web::HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(app_state.server_metrics.encode(&gauges))
+}
+
+/// Site context of the templates, computed from all the posts
+fn load_site_context(app_state: &AppState, config: &Config) -> std::io::Result<SiteContext> {
+    let preview_opt = get_preview_option(config);
+    let post_links = app_state.post_links.read().unwrap();
+    let posts = retrieve_post_list(&app_state.summary_cache, &app_state.formats, &post_links, None, &preview_opt)?;
+    Ok(get_site_context(config, &posts, &app_state.view_counter.counts()))
+}
+
+/// Local template_dir, overriding the theme templates
+fn template_dirs(config: &Config, theme: Option<&Theme>) -> Vec<PathBuf> {
+    layered_dirs(&config.paths.template_dir, theme.map(|t| t.template_dir()))
+}
+
+/// Reloads the templates when any template file changes. Invalid templates are logged and
+/// the previous ones are kept
+fn watch_templates(app_state: Arc<AppState>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut last_error = None;
+        loop {
+            tokio::time::sleep(TEMPLATE_CHECK_INTERVAL).await;
+            if !app_state.templates.read().unwrap().is_outdated() {
+                continue;
+            }
+
+            let template_dirs = template_dirs(&app_state.config.read().unwrap(), app_state.theme.as_ref());
+            let templates = TemplateRegistry::from_dirs(&template_dirs)
+                .and_then(|t| t.require(REQUIRED_TEMPLATES).map(|_| t));
+            match templates {
+                Ok(templates) => {
+                    info!("Templates changed. Reloading templates from {:?}", template_dirs);
+                    *app_state.templates.write().unwrap() = templates;
+                    if let Err(e) = app_state.shortcodes.reload(&template_dirs) {
+                        error!("Error reloading shortcodes, keeping the previous ones: {}", e);
+                    }
+                    // Rendered posts and pages used the previous templates and shortcodes
+                    app_state.post_cache.write().unwrap().clear();
+                    app_state.summary_cache.write().unwrap().clear();
+                    last_error = None;
+                }
+                Err(e) => {
+                    let e = e.to_string();
+                    if last_error.as_ref() != Some(&e) {
+                        error!("Error reloading templates, keeping the previous ones: {}", e);
+                        last_error = Some(e);
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Writes the view counters periodically, so they survive restarts
+fn save_view_counts(app_state: Arc<AppState>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        if !app_state.view_counter.is_enabled() {
+            return;
+        }
+        loop {
+            tokio::time::sleep(VIEW_COUNTS_SAVE_INTERVAL).await;
+            if let Err(e) = app_state.view_counter.save() {
+                error!("Error saving view counters: {}", e);
+            }
+        }
+    })
+}
 
-    req.peer_addr().map_or("".to_string(), |x| format!("{}", x))
+/// Address of the client, behind the trusted proxies. Empty when unknown
+fn get_origin(req: &web::HttpRequest, app_state: &AppState) -> String {
+    let peer = req.peer_addr().map(|addr| addr.ip());
+    app_state.client_addresses.resolve(peer, req.headers()).map_or("".to_string(), |ip| ip.to_string())
 }
 
 pub async fn server_run(config: Config) -> Result<()> {
+    let formats = FormatRegistry::with_markdown(&config.markdown);
+    server_run_with_formats(config, formats).await
+}
+
+/// Runs the server rendering posts and pages with the formats registered, e.g. to add a custom format
+pub async fn server_run_with_formats(mut config: Config, mut formats: FormatRegistry) -> Result<()> {
     let index_base_name = match config.defaults.index_base_name {
         None => PostListType::AnyContentFile,
         Some(ref base_name) => PostListType::IndexBaseName(base_name.clone()),
     };
 
     // List post files and generate list of link -> post file
-    let post_link_vec: Vec<PostLink> = list_post_files(&config.paths.posts_dir, &index_base_name)?;
+    let post_link_vec: Vec<PostLink> = list_post_files(&config.paths.posts_dir, &index_base_name, &formats)?;
     for file in post_link_vec.iter() {
         info!("Post added to listing: {:?}", file.post_name);
     }
 
-    let page_link_vec: Vec<PostLink> = list_post_files(&config.paths.pages_dir, &index_base_name)?;
+    let page_link_vec: Vec<PostLink> = list_post_files(&config.paths.pages_dir, &index_base_name, &formats)?;
     for file in page_link_vec.iter() {
         info!("Page found: {:?}", file.post_name);
     }
@@ -367,30 +807,59 @@ pub async fn server_run(config: Config) -> Result<()> {
         .map(|link| (link.post_name, link.post_path))
         .collect();
 
-    let (post_cache, summary_cache) = match config.defaults.rendering_cache_enabled {
-        true => (ContentCache::new(), ContentCache::new()),
-        false => (ContentCache::non_caching(), ContentCache::non_caching()),
+    let wiki_links = Arc::new(WikiLinks::new(&post_links, &page_links, &formats));
+    formats.set_wiki_links(wiki_links.clone());
+
+    let (post_cache, summary_cache, asset_cache) = match config.defaults.rendering_cache_enabled {
+        true => (ContentCache::new(), ContentCache::new(), ContentCache::new()),
+        false => (ContentCache::non_caching(), ContentCache::non_caching(), ContentCache::non_caching()),
     };
 
-    let (metric_sender, _metrics) = if let Some(ref metrics_cfg) = config.metrics {
+    let (metric_sender, _metrics, view_counter) = if let Some(ref metrics_cfg) = config.metrics {
         // When configuration is loaded, we already set a location if the metrics section is defined
         let location = metrics_cfg.location.as_ref().unwrap();
-        let time_slot = Duration::seconds(metrics_cfg.time_slot_secs.unwrap());
-        let metrics = MetricWriter::new(location, time_slot)?;
+        let metrics = MetricWriter::new(metrics_cfg)?;
         let metric_handler = MetricHandler::new(metrics);
         let sender = metric_handler.new_sender();
-        (sender, Some(metric_handler))
+        let view_counts_file = metrics_cfg.view_counts_file.clone().unwrap_or(location.with_file_name("view_counts.json"));
+        info!("View counters are kept in {:?}", view_counts_file);
+        (sender, Some(metric_handler), ViewCounter::load(&view_counts_file)?)
     } else {
-        (MetricHandler::no_op(), None)
+        (MetricHandler::no_op(), None, ViewCounter::no_op())
     };
 
+    let client_addresses = ClientAddressResolver::new(&config.server.trusted_proxies)?;
+
+    let authors = match config.paths.authors_file {
+        Some(ref authors_file) => AuthorRegistry::from_file(authors_file)?,
+        None => AuthorRegistry::empty(),
+    };
+
+    let theme = configured_theme(&config)?;
+    if let Some(ref theme) = theme {
+        info!("Using theme {} {} from {:?}", theme.manifest.name, theme.manifest.version, theme.dir);
+        theme.merge_site_defaults(&mut config.site);
+        for variable in theme.missing_variables(&config.site) {
+            warn!("Theme {} uses site.{}, which is not set in [site]", theme.manifest.name, variable);
+        }
+    }
+
+    let templates = TemplateRegistry::from_dirs(&template_dirs(&config, theme.as_ref()))?;
+    templates.require(REQUIRED_TEMPLATES)?;
+    let shortcodes = Arc::new(Shortcodes::from_dirs(&template_dirs(&config, theme.as_ref()))?);
+    formats.set_shortcodes(shortcodes.clone());
+
     let post_links = RwLock::new(post_links);
     let page_links = RwLock::new(page_links);
     let bind_addr = config.server.address.clone();
     let bind_port = config.server.port;
+    let metrics_address = config.server.metrics_address.clone();
+    let metrics_with_blog = config.server.metrics_endpoint && metrics_address.is_none();
     let config = RwLock::new(config);
     let post_cache = RwLock::new(post_cache);
     let summary_cache = RwLock::new(summary_cache);
+    let asset_cache = RwLock::new(asset_cache);
+    let templates = RwLock::new(templates);
 
     let app_state = Arc::new(AppState {
         post_links,
@@ -398,16 +867,60 @@ pub async fn server_run(config: Config) -> Result<()> {
         config,
         post_cache,
         summary_cache,
+        asset_cache,
         metric_sender,
+        view_counter,
+        server_metrics: Arc::new(ServerMetrics::default()),
+        client_addresses,
+        authors,
+        templates,
+        theme,
+        formats,
+        wiki_links,
+        shortcodes,
     });
 
+    let _template_watcher = watch_templates(app_state.clone());
+    let _view_counts_writer = save_view_counts(app_state.clone());
+    let server_state = app_state.clone();
+
+    let metrics_server = match metrics_address {
+        Some(ref metrics_address) => {
+            info!("Serving the server metrics at http://{}/metrics", metrics_address);
+            let metrics_state = app_state.clone();
+            let server = web::HttpServer::new(move || web::App::new().state(metrics_state.clone()).service(server_metrics_endpoint))
+                .workers(1)
+                .disable_signals()
+                .bind(metrics_address)?
+                .run();
+            Some(server)
+        }
+        None => None,
+    };
+
     web::HttpServer::new(move || {
-        web::App::new()
+        let app = web::App::new()
             .state(app_state.clone())
+            .wrap(RequestMetrics::new(app_state.server_metrics.clone()));
+        let app = match metrics_with_blog {
+            true => app.service(server_metrics_endpoint),
+            false => app,
+        };
+        app
             .service(index)
             .service(public_files)
             .service(list)
             .service(list_with_tags)
+            .service(author)
+            .service(author_wo_slash)
+            .service(series)
+            .service(series_wo_slash)
+            .service(archive)
+            .service(archive_wo_slash)
+            .service(archive_year)
+            .service(archive_year_wo_slash)
+            .service(archive_month)
+            .service(archive_month_wo_slash)
             .service(rss)
             .service(view)
             .service(view_wo_slash)
@@ -415,9 +928,18 @@ pub async fn server_run(config: Config) -> Result<()> {
             .service(page)
             .service(page_wo_slash)
             .service(page_files)
+            .service(api_posts)
+            .service(api_post)
+            .service(api_page)
+            .service(api_tags)
     })
     .bind((bind_addr, bind_port))?
     .run()
-    .await
-    .map_err(anyhow::Error::from)
+    .await?;
+
+    if let Some(metrics_server) = metrics_server {
+        metrics_server.stop(true).await;
+    }
+    server_state.view_counter.save()?;
+    Ok(())
 }
@@ -44,4 +44,56 @@ pub const POST_DATA_HTML: &str = "<!--
 <p>You finished university and learned a lot. You solved many hard problems. It's common to think you are awesome and the smartest person in the planet. Some day in your life, you will find that you are not and that there are many developers much better than you. Not in capacity, but in wisdom and knowledge. <strong>The earlier you find that, the better.</strong> This will drive you to improve yourself as you now recognize better your weakest points.</p>
 <h3>The awesome thing you learned doesn't solve all the problems</h3>
 <p>The less knowledge you have, the more you will feel that something awesome you learned is the solution for everything. <strong>There is no Saint Graal</strong>. Always search for alternatives, even if they don't look good. The more you know, the more you will see the problems of new trends and concepts and you will be able to choose the best solution for the problem you need to solve</p>
-";
\ No newline at end of file
+";
+
+/// Optional fields of the post made by create_cont. Empty values keep the defaults
+#[cfg(test)]
+#[derive(Default)]
+pub struct ContOptions<'a> {
+    pub authors: &'a [&'a str],
+    pub tags: &'a [&'a str],
+    pub series: Option<&'a str>,
+    pub series_part: Option<u32>,
+    pub rendered: Option<&'a str>,
+    pub word_count: usize,
+}
+
+/// Post published on date (`%Y-%m-%d`), with a title and summary made from its id
+#[cfg(test)]
+pub fn create_cont(id: &str, date: &str, options: ContOptions) -> std::sync::Arc<crate::content::Content> {
+    use crate::content::{Content, ContentHeader, PostId};
+    use crate::content::parsing_utils::reading_time;
+
+    let dt = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap().and_hms_opt(5, 6, 7).unwrap();
+    let authors: Vec<String> = match options.authors {
+        [] => vec!["Thiago".to_string()],
+        authors => authors.iter().map(|a| a.to_string()).collect(),
+    };
+    std::sync::Arc::new(Content {
+        header: ContentHeader {
+            file_name: std::path::PathBuf::from(format!("post-{}.md", id)),
+            id: PostId(id.to_string()),
+            date: dt,
+            author: authors.join(", "),
+            authors,
+            tags: options.tags.iter().map(|t| t.to_string()).collect(),
+            series: options.series.map(|s| s.to_string()),
+            series_part: options.series_part,
+            updated: dt,
+        },
+        link: format!("post-{}", id),
+        title: format!("title-of-post-{}", id),
+        rendered: options.rendered.map_or_else(|| format!("summary-of-post-{}", id), |r| r.to_string()),
+        word_count: options.word_count,
+        reading_time: reading_time(options.word_count),
+    })
+}
+
+/// Empty directory for the files of a test, removed and created again on each run
+#[cfg(test)]
+pub fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("texted-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
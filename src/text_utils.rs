@@ -28,8 +28,12 @@ pub fn parse_date_time(buf: &str) -> Result<NaiveDateTime, String> {
     let mn: u32 = to_u32(caps.index(5))?;
     let s: u32 = to_u32(caps.index(6))?;
 
-    let date = NaiveDate::from_ymd_opt(y, m, d).unwrap();
-    let time = NaiveTime::from_hms_opt(h, mn, s).unwrap();
+    let Some(date) = NaiveDate::from_ymd_opt(y, m, d) else {
+        return Err(format!("Invalid date {}", buf));
+    };
+    let Some(time) = NaiveTime::from_hms_opt(h, mn, s) else {
+        return Err(format!("Invalid time {}", buf));
+    };
 
     let date_time = NaiveDateTime::new(
         date,
@@ -39,6 +43,14 @@ pub fn parse_date_time(buf: &str) -> Result<NaiveDateTime, String> {
     Ok(date_time)
 }
 
+/// Converts a name into a url friendly id. E.g. "Ana Lúcia" -> "ana_lucia"
+pub fn slugify(name: &str) -> String {
+    let id: String = unidecode::unidecode(name).chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    id.trim_matches('_').to_string()
+}
+
 pub fn format_date_time(date_time: &NaiveDateTime) -> (String, String) {
     let date = date_time.format("%Y-%m-%d").to_string();
     let time = date_time.format("%H:%M:%S").to_string();
@@ -49,6 +61,13 @@ pub fn format_date_time(date_time: &NaiveDateTime) -> (String, String) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Ana Lúcia"), "ana_lucia");
+        assert_eq!(slugify("Rust web tutorial!"), "rust_web_tutorial");
+        assert_eq!(slugify("thiago"), "thiago");
+    }
+
     #[test]
     fn test_parse_date_time() {
         let date_time = parse_date_time("2017-09-10 10:42:32.123").unwrap();
@@ -65,5 +84,9 @@ mod tests {
         let (date, time) = format_date_time(&date_time);
         assert_eq!(date, "2017-09-10");
         assert_eq!(time, "10:42:32");
+
+        assert_eq!(parse_date_time("2017-13-10 10:42:32").err().unwrap(), "Invalid date 2017-13-10 10:42:32");
+        assert_eq!(parse_date_time("2017-02-30 10:42:32").err().unwrap(), "Invalid date 2017-02-30 10:42:32");
+        assert_eq!(parse_date_time("2017-09-10 25:42:32").err().unwrap(), "Invalid time 2017-09-10 25:42:32");
     }
 }
\ No newline at end of file
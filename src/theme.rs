@@ -0,0 +1,252 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use serde::Deserialize;
+
+use crate::config::Config;
+
+pub const THEME_MANIFEST: &str = "theme.toml";
+pub const THEME_TEMPLATE_DIR: &str = "template";
+pub const THEME_PUBLIC_DIR: &str = "public";
+
+/// Manifest of a theme, the theme.toml file in the root of the theme directory. E.g.
+/// ```toml
+/// name = "default"
+/// version = "1.0.0"
+/// description = "Texted default theme"
+/// texted_version = "1.2.0"
+/// variables = ["title", "description", "nav"]
+///
+/// [site]
+/// title = "Texted"
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ThemeManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// Minimum texted version the theme works with
+    pub texted_version: String,
+    /// Site variables used by the templates, e.g. `title` for {{site.title}}
+    #[serde(default)]
+    pub variables: Vec<String>,
+    /// Default values of the [site] configuration
+    #[serde(default)]
+    pub site: toml::Table,
+}
+
+/// A theme directory, containing the manifest, template/ and public/
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub dir: PathBuf,
+    pub manifest: ThemeManifest,
+}
+
+impl Theme {
+    pub fn from_dir(dir: &Path) -> io::Result<Self> {
+        let manifest_file = dir.join(THEME_MANIFEST);
+        let content = fs::read_to_string(&manifest_file)
+            .map_err(|e| io::Error::new(e.kind(), format!("Error opening theme manifest {}: {}", manifest_file.to_str().unwrap(), e)))?;
+        let manifest = match toml::from_str(&content) {
+            Ok(manifest) => manifest,
+            Err(e) => return Err(io::Error::new(ErrorKind::InvalidData, format!("Error parsing theme manifest {}: {}", manifest_file.to_str().unwrap(), e))),
+        };
+
+        Ok(Theme {
+            dir: dir.to_path_buf(),
+            manifest,
+        })
+    }
+
+    /// Loads the installed theme name of themes_dir, checking it supports this texted version
+    pub fn load(themes_dir: &Path, name: &str) -> io::Result<Self> {
+        let dir = themes_dir.join(name);
+        if !dir.is_dir() {
+            return Err(io::Error::new(ErrorKind::NotFound, format!("Theme {} not found in {}", name, themes_dir.to_str().unwrap())));
+        }
+        let theme = Theme::from_dir(&dir)?;
+        theme.check_version(env!("CARGO_PKG_VERSION"))?;
+        Ok(theme)
+    }
+
+    pub fn template_dir(&self) -> PathBuf {
+        self.dir.join(THEME_TEMPLATE_DIR)
+    }
+
+    pub fn public_dir(&self) -> PathBuf {
+        self.dir.join(THEME_PUBLIC_DIR)
+    }
+
+    pub fn check_version(&self, texted_version: &str) -> io::Result<()> {
+        if is_compatible(&self.manifest.texted_version, texted_version) {
+            return Ok(());
+        }
+        Err(io::Error::new(ErrorKind::Unsupported, format!("Theme {} requires texted {} or newer, but this is texted {}",
+                                                           self.manifest.name, self.manifest.texted_version, texted_version)))
+    }
+
+    /// Adds the default site values of the theme missing in site
+    pub fn merge_site_defaults(&self, site: &mut toml::Table) {
+        for (key, value) in self.manifest.site.iter() {
+            if !site.contains_key(key) {
+                site.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    /// Variables the theme supports that are not set in site
+    pub fn missing_variables(&self, site: &toml::Table) -> Vec<&str> {
+        self.manifest.variables.iter()
+            .filter(|v| !site.contains_key(v.as_str()))
+            .map(|v| v.as_str())
+            .collect()
+    }
+}
+
+/// Theme set in the [theme] section of the configuration, if any
+pub fn configured_theme(config: &Config) -> io::Result<Option<Theme>> {
+    let Some(ref theme) = config.theme else {
+        return Ok(None);
+    };
+    let Some(ref themes_dir) = config.paths.themes_dir else {
+        return Err(io::Error::new(ErrorKind::InvalidInput, format!("Theme {} is set, but paths.themes_dir is not", theme.name)));
+    };
+    Theme::load(themes_dir, &theme.name).map(Some)
+}
+
+/// Directories to look for a file, local_dir first so it overrides the theme files.
+/// local_dir is optional when a theme is used
+pub fn layered_dirs(local_dir: &Path, theme_dir: Option<PathBuf>) -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if theme_dir.is_none() || local_dir.is_dir() {
+        dirs.push(local_dir.to_path_buf());
+    }
+    dirs.extend(theme_dir);
+    dirs
+}
+
+/// Path of file in the first directory containing it, or in the first directory if none does
+pub fn resolve_file(dirs: &[PathBuf], file: &str) -> Option<PathBuf> {
+    dirs.iter()
+        .map(|dir| dir.join(file))
+        .find(|path| path.is_file())
+        .or_else(|| dirs.first().map(|dir| dir.join(file)))
+}
+
+/// Themes installed in themes_dir, sorted by name. Directories without a manifest are ignored
+pub fn list_themes(themes_dir: &Path) -> io::Result<Vec<Theme>> {
+    let mut themes = vec![];
+    for entry in fs::read_dir(themes_dir)? {
+        let path = entry?.path();
+        if path.join(THEME_MANIFEST).is_file() {
+            themes.push(Theme::from_dir(&path)?);
+        }
+    }
+    themes.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+    Ok(themes)
+}
+
+/// True if texted_version is equal or newer than the required version. E.g. 1.2 requires 1.2.0 or newer
+pub fn is_compatible(required: &str, texted_version: &str) -> bool {
+    parse_version(required) <= parse_version(texted_version)
+}
+
+fn parse_version(version: &str) -> Vec<u32> {
+    let mut parts: Vec<u32> = version.trim().trim_start_matches(">=").trim()
+        .split('.')
+        .map(|p| p.trim().parse().unwrap_or(0))
+        .collect();
+    parts.resize(3, 0);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::temp_dir;
+
+    const MANIFEST: &str = r#"
+name = "simple"
+version = "1.0.0"
+texted_version = "1.2"
+variables = ["title", "nav"]
+
+[site]
+title = "Simple theme"
+"#;
+
+    fn create_theme(name: &str, manifest: &str) -> PathBuf {
+        let dir = temp_dir(&format!("themes-{}", name));
+        fs::create_dir_all(dir.join("simple")).unwrap();
+        fs::write(dir.join("simple").join(THEME_MANIFEST), manifest).unwrap();
+        fs::create_dir_all(dir.join("not-a-theme")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_load_theme() {
+        let dir = create_theme("load", MANIFEST);
+        let theme = Theme::load(&dir, "simple").unwrap();
+        assert_eq!(theme.manifest.name, "simple");
+        assert_eq!(theme.manifest.variables, vec!["title", "nav"]);
+        assert_eq!(theme.template_dir(), dir.join("simple").join("template"));
+        assert!(Theme::load(&dir, "other").is_err());
+
+        let themes = list_themes(&dir).unwrap();
+        assert_eq!(themes.len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let dir = create_theme("newer", &MANIFEST.replace("1.2", "99.0"));
+        let err = Theme::load(&dir, "simple").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_site_defaults() {
+        let theme = Theme {
+            dir: PathBuf::from("simple"),
+            manifest: toml::from_str(MANIFEST).unwrap(),
+        };
+        let mut site: toml::Table = toml::from_str(r#"description = "My blog""#).unwrap();
+        theme.merge_site_defaults(&mut site);
+        assert_eq!(site["title"].as_str(), Some("Simple theme"));
+        assert_eq!(site["description"].as_str(), Some("My blog"));
+        assert_eq!(theme.missing_variables(&site), vec!["nav"]);
+
+        let mut site: toml::Table = toml::from_str(r#"title = "My blog""#).unwrap();
+        theme.merge_site_defaults(&mut site);
+        assert_eq!(site["title"].as_str(), Some("My blog"));
+    }
+
+    #[test]
+    fn test_layered_dirs() {
+        let dir = create_theme("layered", MANIFEST);
+        let local = dir.join("local");
+        let theme = dir.join("simple");
+        assert_eq!(layered_dirs(&local, Some(theme.clone())), vec![theme.clone()]);
+        assert_eq!(layered_dirs(&local, None), vec![local.clone()]);
+
+        fs::create_dir_all(&local).unwrap();
+        fs::write(local.join(THEME_MANIFEST), "").unwrap();
+        let dirs = layered_dirs(&local, Some(theme.clone()));
+        assert_eq!(dirs, vec![local.clone(), theme.clone()]);
+        assert_eq!(resolve_file(&dirs, THEME_MANIFEST), Some(local.join(THEME_MANIFEST)));
+        assert_eq!(resolve_file(&dirs, "style.css"), Some(local.join("style.css")));
+
+        fs::remove_file(local.join(THEME_MANIFEST)).unwrap();
+        assert_eq!(resolve_file(&dirs, THEME_MANIFEST), Some(theme.join(THEME_MANIFEST)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_compatible() {
+        assert!(is_compatible("1.2", "1.2.0"));
+        assert!(is_compatible("1.2.0", "1.2.1"));
+        assert!(is_compatible(">= 1.1.9", "1.2.1"));
+        assert!(!is_compatible("1.3", "1.2.1"));
+        assert!(!is_compatible("2.0.0", "1.9.9"));
+    }
+}
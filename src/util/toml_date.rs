@@ -54,6 +54,6 @@ activity_start_year = 2000
 blog_start_date = 2024-04-22
 "##;
         let cfg: Config = toml::from_str::<Config>(toml_str).unwrap();
-        assert_eq!(cfg.personal.blog_start_date, TomlDate(NaiveDate::from_ymd_opt(2024, 04, 22).unwrap()));
+        assert_eq!(cfg.personal.blog_start_date, TomlDate(NaiveDate::from_ymd_opt(2024, 4, 22).unwrap()));
     }
 }
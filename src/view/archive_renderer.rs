@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use chrono::Datelike;
+use ramhorns::Template;
+
+use crate::authors::AuthorRegistry;
+use crate::content::Content;
+use crate::view::list_renderer::{PostItem, ViewPagination};
+use crate::view::site_context::{SiteContext, WithSite};
+
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// Period of time being browsed. No year means the whole archive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArchivePeriod {
+    pub year: Option<i32>,
+    pub month: Option<u32>,
+}
+
+impl ArchivePeriod {
+    pub fn all() -> Self {
+        ArchivePeriod { year: None, month: None }
+    }
+
+    pub fn contains(&self, content: &Content) -> bool {
+        let date = content.header.date;
+        let year_match = self.year.is_none_or(|y| y == date.year());
+        let month_match = self.month.is_none_or(|m| m == date.month());
+        year_match && month_match
+    }
+
+    pub fn title(&self) -> String {
+        match (self.year, self.month) {
+            (Some(year), Some(month)) => format!("{} {}", month_name(month), year),
+            (Some(year), None) => year.to_string(),
+            _ => "All posts".to_string(),
+        }
+    }
+}
+
+#[derive(ramhorns::Content)]
+struct ArchivePage {
+    title: String,
+    periods: Vec<ArchiveYear>,
+    post_list: Vec<PostItem>,
+    post_count: usize,
+    page_list: Vec<ViewPagination>,
+    show_pagination: bool,
+}
+
+#[derive(ramhorns::Content)]
+struct ArchiveYear {
+    year: i32,
+    link: String,
+    count: usize,
+    current: bool,
+    months: Vec<ArchiveMonth>,
+}
+
+#[derive(ramhorns::Content)]
+struct ArchiveMonth {
+    month: u32,
+    name: &'static str,
+    link: String,
+    count: usize,
+    current: bool,
+}
+
+pub struct ArchiveRenderer<'a> {
+    pub template: &'a Template<'a>,
+    pub page_count: u32,
+    /// Number of posts in the period, in all pages
+    pub post_count: usize,
+}
+
+impl<'a> ArchiveRenderer<'a> {
+    pub fn new(template: &'a Template<'a>, page_count: u32, post_count: usize) -> ArchiveRenderer<'a> {
+        ArchiveRenderer {
+            template,
+            page_count,
+            post_count,
+        }
+    }
+
+    /// Renders one page of the posts inside period. all_contents is used to generate the list of periods
+    pub fn render(&self, all_contents: &[Arc<Content>], period: &ArchivePeriod, contents: &[Arc<Content>],
+                  cur_page: u32, authors: &AuthorRegistry, site: &SiteContext) -> String {
+        let post_list = contents.iter().map(|c| PostItem::from(c, authors, site)).collect();
+        let page_list = ViewPagination::page_list(self.page_count, cur_page);
+
+        let page = ArchivePage {
+            title: period.title(),
+            periods: group_by_period(all_contents, period),
+            post_list,
+            post_count: self.post_count,
+            page_list,
+            show_pagination: self.page_count > 1,
+        };
+        self.template.render(&WithSite::new(&page, site))
+    }
+}
+
+fn month_name(month: u32) -> &'static str {
+    MONTH_NAMES.get((month as usize).wrapping_sub(1)).unwrap_or(&"")
+}
+
+/// Groups the posts by year and month, most recent first
+fn group_by_period(contents: &[Arc<Content>], current: &ArchivePeriod) -> Vec<ArchiveYear> {
+    let mut years: BTreeMap<i32, BTreeMap<u32, usize>> = BTreeMap::new();
+    for content in contents {
+        let date = content.header.date;
+        *years.entry(date.year()).or_default().entry(date.month()).or_insert(0) += 1;
+    }
+
+    years.into_iter().rev().map(|(year, months)| {
+        let months: Vec<ArchiveMonth> = months.into_iter().rev().map(|(month, count)| {
+            ArchiveMonth {
+                month,
+                name: month_name(month),
+                link: format!("/archive/{}/{:02}/", year, month),
+                count,
+                current: current.year == Some(year) && current.month == Some(month),
+            }
+        }).collect();
+
+        ArchiveYear {
+            year,
+            link: format!("/archive/{}/", year),
+            count: months.iter().map(|m| m.count).sum(),
+            current: current.year == Some(year) && current.month.is_none(),
+            months,
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{create_cont, ContOptions};
+
+    #[test]
+    fn test_period() {
+        let content = create_cont("1", "2024-02-01", ContOptions::default());
+        assert!(ArchivePeriod::all().contains(&content));
+        assert!(ArchivePeriod { year: Some(2024), month: None }.contains(&content));
+        assert!(ArchivePeriod { year: Some(2024), month: Some(2) }.contains(&content));
+        assert!(!ArchivePeriod { year: Some(2024), month: Some(3) }.contains(&content));
+        assert!(!ArchivePeriod { year: Some(2023), month: None }.contains(&content));
+
+        assert_eq!(ArchivePeriod::all().title(), "All posts");
+        assert_eq!(ArchivePeriod { year: Some(2024), month: None }.title(), "2024");
+        assert_eq!(ArchivePeriod { year: Some(2024), month: Some(2) }.title(), "February 2024");
+    }
+
+    #[test]
+    fn render_archive() {
+        let template_src = r##"{{title}}:{{post_count}}
+{{#periods}}[{{year}}({{count}}){{#current}}*{{/current}}{{#months}} {{name}}({{count}}){{#current}}*{{/current}} {{link}}{{/months}}]
+{{/periods}}{{#post_list}}({{title}}){{/post_list}}"##;
+        let all = vec![
+            create_cont("1", "2023-12-01", ContOptions::default()),
+            create_cont("2", "2024-01-01", ContOptions::default()),
+            create_cont("3", "2024-01-01", ContOptions::default()),
+            create_cont("4", "2024-03-01", ContOptions::default()),
+        ];
+        let period = ArchivePeriod { year: Some(2024), month: Some(1) };
+        let contents: Vec<_> = all.iter().filter(|c| period.contains(c)).cloned().collect();
+
+        let template = Template::new(template_src).unwrap();
+        let renderer = ArchiveRenderer::new(&template, 1, contents.len());
+        let res = renderer.render(&all, &period, &contents, 1, &AuthorRegistry::empty(), &SiteContext::default());
+        assert_eq!(res, r##"January 2024:2
+[2024(3) March(1) /archive/2024/03/ January(2)* /archive/2024/01/]
+[2023(1) December(1) /archive/2023/12/]
+(title-of-post-2)(title-of-post-3)"##);
+    }
+}
@@ -0,0 +1,45 @@
+use crate::authors::{Author, AuthorLink};
+
+#[derive(ramhorns::Content)]
+pub(crate) struct AuthorItem {
+    id: String,
+    name: String,
+    bio: String,
+    avatar: Option<String>,
+    email: Option<String>,
+    link: String,
+    links: Vec<AuthorLinkItem>,
+}
+
+#[derive(ramhorns::Content)]
+struct AuthorLinkItem {
+    name: String,
+    url: String,
+}
+
+impl AuthorItem {
+    pub(crate) fn from(author: &Author) -> Self {
+        AuthorItem {
+            id: author.id.clone(),
+            name: author.name.clone(),
+            bio: author.bio.clone(),
+            avatar: author.avatar.clone(),
+            email: author.email.clone(),
+            link: format!("/author/{}/", author.id),
+            links: author.links.iter().map(AuthorLinkItem::from).collect(),
+        }
+    }
+
+    pub(crate) fn from_all(authors: &[Author]) -> Vec<Self> {
+        authors.iter().map(AuthorItem::from).collect()
+    }
+}
+
+impl AuthorLinkItem {
+    fn from(link: &AuthorLink) -> Self {
+        AuthorLinkItem {
+            name: link.name.clone(),
+            url: link.url.clone(),
+        }
+    }
+}
@@ -1,11 +1,12 @@
-use std::io;
-use std::io::ErrorKind;
 use std::sync::Arc;
 
 use ramhorns::Template;
 
+use crate::authors::{Author, AuthorRegistry};
 use crate::content::Content;
 use crate::text_utils::format_date_time;
+use crate::view::author_item::AuthorItem;
+use crate::view::site_context::{SiteContext, WithSite};
 
 #[derive(ramhorns::Content)]
 struct ListPage<'a> {
@@ -13,15 +14,42 @@ struct ListPage<'a> {
     tags: Vec<ViewTag<'a>>,
     page_list: Vec<ViewPagination>,
     show_pagination: bool,
+    /// Author being listed in /author/{id}/
+    author: Option<AuthorItem>,
 }
 
 #[derive(ramhorns::Content)]
-struct PostItem {
+pub(crate) struct PostItem {
     date: String,
     time: String,
     link: String,
     title: String,
     summary: String,
+    authors: Vec<AuthorItem>,
+    updated_date: String,
+    word_count: usize,
+    reading_time: usize,
+    /// All-time views of the post
+    view_count: u64,
+}
+
+impl PostItem {
+    pub(crate) fn from(content: &Content, authors: &AuthorRegistry, site: &SiteContext) -> Self {
+        let (date, time) = format_date_time(&content.header.date);
+        let (updated_date, _) = format_date_time(&content.header.updated);
+        PostItem {
+            date,
+            time,
+            link: format!("/view/{}", &content.link),
+            title: content.title.clone(),
+            summary: content.rendered.clone(),
+            authors: AuthorItem::from_all(&authors.resolve_all(&content.header.authors)),
+            updated_date,
+            word_count: content.word_count,
+            reading_time: content.reading_time,
+            view_count: site.view_count(&content.link),
+        }
+    }
 }
 
 #[derive(ramhorns::Content)]
@@ -30,60 +58,51 @@ struct ViewTag<'a> {
 }
 
 #[derive(ramhorns::Content)]
-struct ViewPagination {
+pub(crate) struct ViewPagination {
     current: bool,
     number: u32,
 }
 
+impl ViewPagination {
+    pub(crate) fn page_list(page_count: u32, cur_page: u32) -> Vec<ViewPagination> {
+        let mut page_list: Vec<ViewPagination> = Vec::with_capacity(page_count as usize);
+        for i in 1..=page_count {
+            let current = i == cur_page;
+            page_list.push(ViewPagination {
+                current,
+                number: i,
+            })
+        }
+        page_list
+    }
+}
+
 pub struct ListRenderer<'a> {
-    pub template: Template<'a>,
+    pub template: &'a Template<'a>,
     pub page_size: u32,
 }
 
-impl ListRenderer<'_> {
-    pub fn new(list_tpl_src: &str, page_size: u32) -> io::Result<ListRenderer> {
-        let template = match Template::new(list_tpl_src) {
-            Ok(x) => x,
-            Err(e) => {
-                return Err(io::Error::new(ErrorKind::InvalidInput, format!("Error parsing list template: {}", e)));
-            }
-        };
-
-        Ok(ListRenderer {
+impl<'a> ListRenderer<'a> {
+    pub fn new(template: &'a Template<'a>, page_size: u32) -> ListRenderer<'a> {
+        ListRenderer {
             template,
             page_size,
-        })
-    }
-
-    pub fn render(&self, contents: &[Arc<Content>], cur_page: u32, tags: Vec<String>) -> String {
-        let mut post_list = vec![];
-        for content in contents {
-            let (date, time) = format_date_time(&content.header.date);
-            let post_item = PostItem {
-                date,
-                time,
-                link: format!("/view/{}", &content.link),
-                title: content.title.clone(),
-                summary: content.rendered.clone(),
-            };
-            post_list.push(post_item);
         }
+    }
 
-        let mut page_list: Vec<ViewPagination> = Vec::with_capacity(self.page_size as usize);
-        for i in 1..=self.page_size {
-            let current = i == cur_page;
-            page_list.push(ViewPagination {
-                current,
-                number: i,
-            })
-        }
+    pub fn render(&self, contents: &[Arc<Content>], cur_page: u32, tags: Vec<String>,
+                  authors: &AuthorRegistry, author: Option<&Author>, site: &SiteContext) -> String {
+        let post_list = contents.iter().map(|c| PostItem::from(c, authors, site)).collect();
+        let page_list = ViewPagination::page_list(self.page_size, cur_page);
 
         let tags: Vec<_> = tags.iter().map(|t| ViewTag { tag: t.as_str() }).collect();
-        self.template.render(&ListPage {
+        let page = ListPage {
             post_list,
             tags,
             page_list,
             show_pagination: true,
-        })
+            author: author.map(AuthorItem::from),
+        };
+        self.template.render(&WithSite::new(&page, site))
     }
 }
\ No newline at end of file
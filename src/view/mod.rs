@@ -1,3 +1,8 @@
 pub mod post_renderer;
 pub mod list_renderer;
 pub mod rss_renderer;
+pub mod archive_renderer;
+pub mod author_item;
+pub mod series_renderer;
+pub mod template_registry;
+pub mod site_context;
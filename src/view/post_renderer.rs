@@ -1,61 +1,146 @@
-use std::io;
-use std::io::ErrorKind;
-
 use ramhorns::Template;
 
+use crate::authors::Author;
 use crate::content::Content;
+use crate::post_navigation::{NavLink, PostNavigation};
+use crate::series::{SeriesNavigation, SeriesPartLink};
 use crate::text_utils::format_date_time;
+use crate::view::author_item::AuthorItem;
+use crate::view::site_context::{SiteContext, WithSite};
 
 #[derive(ramhorns::Content)]
 struct ViewTag<'a> {
     tag: &'a str,
 }
 
+#[derive(ramhorns::Content)]
+struct ViewLink<'a> {
+    link: &'a str,
+    title: &'a str,
+}
+
+impl<'a> ViewLink<'a> {
+    fn from(nav_link: &'a NavLink) -> Self {
+        ViewLink {
+            link: nav_link.link.as_str(),
+            title: nav_link.title.as_str(),
+        }
+    }
+}
+
+#[derive(ramhorns::Content)]
+struct ViewSeriesPart<'a> {
+    number: usize,
+    link: &'a str,
+    title: &'a str,
+    current: bool,
+}
+
+#[derive(ramhorns::Content)]
+struct ViewSeries<'a> {
+    name: &'a str,
+    link: &'a str,
+    part: usize,
+    count: usize,
+    parts: Vec<ViewSeriesPart<'a>>,
+    previous: Option<ViewLink<'a>>,
+    next: Option<ViewLink<'a>>,
+}
+
+impl<'a> ViewSeriesPart<'a> {
+    fn from(part: &'a SeriesPartLink) -> Self {
+        ViewSeriesPart {
+            number: part.number,
+            link: part.link.as_str(),
+            title: part.title.as_str(),
+            current: part.current,
+        }
+    }
+}
+
+impl<'a> ViewSeries<'a> {
+    fn from(series: &'a SeriesNavigation) -> Self {
+        ViewSeries {
+            name: series.name.as_str(),
+            link: series.link.as_str(),
+            part: series.part,
+            count: series.count,
+            parts: series.parts.iter().map(ViewSeriesPart::from).collect(),
+            previous: series.previous.as_ref().map(ViewLink::from),
+            next: series.next.as_ref().map(ViewLink::from),
+        }
+    }
+}
+
 #[derive(ramhorns::Content)]
 struct ViewItem<'a> {
     errors: Vec<String>,
     id: &'a str,
     author: &'a str,
+    authors: Vec<AuthorItem>,
     tags: &'a Vec<ViewTag<'a>>,
     date: &'a str,
     time: &'a str,
+    updated_date: &'a str,
+    updated_time: &'a str,
+    /// True when the post was updated after the day it was published
+    is_updated: bool,
+    word_count: usize,
+    reading_time: usize,
+    /// All-time views of the post
+    view_count: u64,
     post_title: &'a str,
     post_content: &'a str,
+    previous: Option<ViewLink<'a>>,
+    next: Option<ViewLink<'a>>,
+    related: Vec<ViewLink<'a>>,
+    has_related: bool,
+    series: Option<ViewSeries<'a>>,
+    /// Posts linking to this one
+    backlinks: Vec<ViewLink<'a>>,
+    has_backlinks: bool,
 }
 
 pub struct PostRenderer<'a> {
-    pub template: Template<'a>,
+    pub template: &'a Template<'a>,
 }
 
-impl PostRenderer<'_> {
-    pub fn new(view_tpl_src: &str) -> io::Result<PostRenderer> {
-        let template = match Template::new(view_tpl_src) {
-            Ok(x) => x,
-            Err(e) => {
-                return Err(io::Error::new(ErrorKind::InvalidInput, format!("Error parsing post view template: {}", e)));
-            }
-        };
-
-        Ok(PostRenderer {
+impl<'a> PostRenderer<'a> {
+    pub fn new(template: &'a Template<'a>) -> PostRenderer<'a> {
+        PostRenderer {
             template,
-        })
+        }
     }
 
-    pub fn render(&self, content: &Content) -> String {
+    pub fn render(&self, content: &Content, authors: &[Author], navigation: &PostNavigation, site: &SiteContext) -> String {
         let tags: &Vec<ViewTag> = &content.header.tags.iter().map(|t| ViewTag { tag: t.as_str() }).collect();
         let (date, time) = format_date_time(&content.header.date);
-        let rendered_page = self.template.render(&ViewItem {
+        let (updated_date, updated_time) = format_date_time(&content.header.updated);
+        let page = ViewItem {
             errors: vec![],
             id: content.header.id.0.as_str(),
             author: content.header.author.as_str(),
+            authors: AuthorItem::from_all(authors),
             tags,
             date: date.as_str(),
             time: time.as_str(),
+            updated_date: updated_date.as_str(),
+            updated_time: updated_time.as_str(),
+            is_updated: updated_date > date,
+            word_count: content.word_count,
+            reading_time: content.reading_time,
+            view_count: site.view_count(&content.link),
             post_title: content.title.as_str(),
             post_content: content.rendered.as_str(),
-        });
-
-        rendered_page
+            previous: navigation.previous.as_ref().map(ViewLink::from),
+            next: navigation.next.as_ref().map(ViewLink::from),
+            related: navigation.related.iter().map(ViewLink::from).collect(),
+            has_related: !navigation.related.is_empty(),
+            series: navigation.series.as_ref().map(ViewSeries::from),
+            backlinks: navigation.backlinks.iter().map(ViewLink::from).collect(),
+            has_backlinks: !navigation.backlinks.is_empty(),
+        };
+        self.template.render(&WithSite::new(&page, site))
     }
 }
 
@@ -64,9 +149,13 @@ mod tests {
     use std::path::PathBuf;
 
     use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+    use ramhorns::Template;
 
+    use crate::authors::AuthorRegistry;
     use crate::content::{Content, ContentHeader, PostId};
+    use crate::post_navigation::{NavLink, PostNavigation};
     use crate::view::post_renderer::PostRenderer;
+    use crate::view::site_context::SiteContext;
 
     #[test]
     fn render_view() {
@@ -77,30 +166,131 @@ DATE=[{{date}}]
 TIME=[{{time}}]
 TAGS=[{{#tags}}({{tag}}){{/tags}}]
 POST_CONTENT=[{{{post_content}}}]
+WORDS=[{{word_count}}|{{reading_time}}]
+UPDATED=[{{#is_updated}}{{updated_date}} {{updated_time}}{{/is_updated}}]
 "##;
-        let post_renderer = PostRenderer::new(template_src).unwrap();
+        let template = Template::new(template_src).unwrap();
+        let post_renderer = PostRenderer::new(&template);
         let content = Content {
             header: ContentHeader {
                 file_name: PathBuf::from("file_name.md"),
                 id: PostId("post-id".to_string()),
                 date: NaiveDateTime::new(
-                    NaiveDate::from_ymd_opt(2024, 01, 02).unwrap(),
+                    NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
                     NaiveTime::from_hms_opt(3, 4, 5).unwrap(),
                 ),
                 author: "<Thiago>".to_string(),
+                authors: vec!["<Thiago>".to_string()],
                 tags: vec!["<rust>".to_string(), "programming".to_string()],
+                series: None,
+                series_part: None,
+                updated: NaiveDateTime::new(
+                    NaiveDate::from_ymd_opt(2024, 2, 3).unwrap(),
+                    NaiveTime::from_hms_opt(4, 5, 6).unwrap(),
+                ),
             },
             link: "".to_string(),
             title: "<post-title>".to_string(),
             rendered: "<post-content>".to_string(),
+            word_count: 450,
+            reading_time: 3,
         };
-        let res = post_renderer.render(&content);
+        let res = post_renderer.render(&content, &[], &PostNavigation::default(), &SiteContext::default());
         assert_eq!(res, r##"
 TITLE=[<post-title>]
 AUTHOR=[&lt;Thiago&gt;]
 DATE=[2024-01-02]
 TIME=[03:04:05]
 TAGS=[(&lt;rust&gt;)(programming)]
-POST_CONTENT=[<post-content>]"##);
+POST_CONTENT=[<post-content>]
+WORDS=[450|3]
+UPDATED=[2024-02-03 04:05:06]"##);
+    }
+
+    #[test]
+    fn render_view_authors() {
+        let template_src = r##"{{#authors}}[{{name}}|{{link}}|{{bio}}{{#links}}({{name}}={{url}}){{/links}}]{{/authors}}"##;
+        let template = Template::new(template_src).unwrap();
+        let post_renderer = PostRenderer::new(&template);
+        let content = Content {
+            header: ContentHeader {
+                file_name: PathBuf::from("file_name.md"),
+                id: PostId("post-id".to_string()),
+                date: NaiveDateTime::new(
+                    NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+                    NaiveTime::from_hms_opt(3, 4, 5).unwrap(),
+                ),
+                author: "thiago, Joe".to_string(),
+                authors: vec!["thiago".to_string(), "Joe".to_string()],
+                tags: vec![],
+                series: None,
+                series_part: None,
+                updated: NaiveDateTime::new(
+                    NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+                    NaiveTime::from_hms_opt(3, 4, 5).unwrap(),
+                ),
+            },
+            link: "".to_string(),
+            title: "title".to_string(),
+            rendered: "content".to_string(),
+            word_count: 0,
+            reading_time: 0,
+        };
+        let registry = AuthorRegistry::from_toml(r##"
+[thiago]
+name = "Thiago Guedes"
+bio = "Writes code"
+links = [{ name = "GitHub", url = "https://github.com/thiagomg" }]
+"##).unwrap();
+        let authors = registry.resolve_all(&content.header.authors);
+        let res = post_renderer.render(&content, &authors, &PostNavigation::default(), &SiteContext::default());
+        assert_eq!(res, "[Thiago Guedes|/author/thiago/|Writes code(GitHub=https://github.com/thiagomg)][Joe|/author/joe/|]");
+    }
+
+    #[test]
+    fn render_view_navigation() {
+        let template_src = r##"PREV=[{{#previous}}{{link}}|{{title}}{{/previous}}]
+NEXT=[{{#next}}{{link}}|{{title}}{{/next}}{{^next}}none{{/next}}]
+RELATED=[{{#related}}({{link}}|{{title}}){{/related}}]
+BACKLINKS=[{{#has_backlinks}}{{#backlinks}}({{link}}){{/backlinks}}{{/has_backlinks}}]"##;
+        let template = Template::new(template_src).unwrap();
+        let post_renderer = PostRenderer::new(&template);
+        let content = Content {
+            header: ContentHeader {
+                file_name: PathBuf::from("file_name.md"),
+                id: PostId("post-id".to_string()),
+                date: NaiveDateTime::new(
+                    NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+                    NaiveTime::from_hms_opt(3, 4, 5).unwrap(),
+                ),
+                author: "Thiago".to_string(),
+                authors: vec!["Thiago".to_string()],
+                tags: vec![],
+                series: None,
+                series_part: None,
+                updated: NaiveDateTime::new(
+                    NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+                    NaiveTime::from_hms_opt(3, 4, 5).unwrap(),
+                ),
+            },
+            link: "".to_string(),
+            title: "title".to_string(),
+            rendered: "content".to_string(),
+            word_count: 0,
+            reading_time: 0,
+        };
+        let nav_link = |id: &str| NavLink { link: format!("/view/{}", id), title: format!("Title {}", id) };
+        let navigation = PostNavigation {
+            previous: Some(nav_link("first")),
+            next: None,
+            related: vec![nav_link("a"), nav_link("b")],
+            series: None,
+            backlinks: vec![nav_link("c")],
+        };
+        let res = post_renderer.render(&content, &[], &navigation, &SiteContext::default());
+        assert_eq!(res, r##"PREV=[/view/first|Title first]
+NEXT=[none]
+RELATED=[(/view/a|Title a)(/view/b|Title b)]
+BACKLINKS=[(/view/c)]"##);
     }
 }
\ No newline at end of file
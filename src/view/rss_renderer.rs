@@ -5,6 +5,7 @@ use chrono::{TimeZone, Utc};
 use quick_xml::events::{BytesCData, BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::Writer;
 
+use crate::authors::AuthorRegistry;
 use crate::content::{Content, PostId};
 
 /* Example
@@ -34,6 +35,7 @@ pub struct RssChannel<'a> {
     pub ch_title: &'a str,
     pub ch_link: &'a str,
     pub ch_desc: &'a str,
+    pub authors: &'a AuthorRegistry,
 }
 
 
@@ -48,6 +50,7 @@ impl<'a> RssChannel<'a> {
         // <rss version="2.0">
         let mut rss = BytesStart::new("rss");
         rss.push_attribute(("version", "2.0"));
+        rss.push_attribute(("xmlns:dc", "http://purl.org/dc/elements/1.1/"));
         writer.write_event(Event::Start(rss))?;
 
         // <channel>
@@ -86,12 +89,22 @@ impl<'a> RssChannel<'a> {
             let description = content.rendered.as_str();
             push_cdata(&mut writer, "description", description)?;
 
+            // <author>thiago@example.com (Thiago)</author>
+            // <dc:creator>Thiago</dc:creator>
+            let authors = self.authors.resolve_all(&content.header.authors);
+            // RSS allows one author per item, the first one with an email
+            if let Some((author, email)) = authors.iter().find_map(|a| a.email.as_ref().map(|email| (a, email))) {
+                push_text(&mut writer, "author", &format!("{} ({})", email, author.name))?;
+            }
+            for author in authors.iter() {
+                push_text(&mut writer, "dc:creator", &author.name)?;
+            }
+
             // <pubDate>Wed, 20 Apr 2022 16:00:00 +0200</pubDate>
             let dt = &content.header.date;
             let dt = TimeZone::from_utc_datetime(Utc::now().offset(), dt);
             push_text(&mut writer, "pubDate", &dt.to_rfc2822())?;
 
-
             // </item>
             writer.write_event(Event::End(BytesEnd::new("item")))?;
         }
@@ -142,52 +155,61 @@ fn push_cdata(writer: &mut Writer<Cursor<Vec<u8>>>, tag: &str, text: &str) -> qu
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
     use std::str;
-    use std::sync::Arc;
-
-    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-
-    use crate::content::{Content, ContentHeader, PostId};
 
     use super::*;
-
-    fn create_cont(id: &str) -> Arc<Content> {
-        let dt = NaiveDateTime::new(
-            NaiveDate::from_ymd_opt(2024, 01, 02).unwrap(),
-            NaiveTime::from_hms_opt(5, 6, 7).unwrap(),
-        );
-        let content = Content {
-            header: ContentHeader {
-                file_name: PathBuf::from(format!("post-{}.md", id)),
-                id: PostId(id.to_string()),
-                date: dt,
-                author: "Thiago".to_string(),
-                tags: vec![format!("first-tag-{}", id), format!("second-tag-{}", id)],
-            },
-            link: format!("post-{}", id),
-            title: format!("title-of-post-{}", id),
-            rendered: format!("summary-of-post-{}", id),
-        };
-
-        Arc::new(content)
-    }
+    use crate::test_data::{create_cont, ContOptions};
 
     #[test]
     fn render_xml() {
-        let contents = vec![create_cont("1"), create_cont("2")];
+        let contents = vec![
+            create_cont("1", "2024-01-02", ContOptions::default()),
+            create_cont("2", "2024-01-02", ContOptions::default()),
+        ];
 
         let ch_title = "my feed";
         let ch_link = "https://thiagocafe.com";
         let ch_desc = "My blog feed";
+        let authors = AuthorRegistry::from_toml(r##"
+[Thiago]
+name = "Thiago Guedes"
+email = "thiago@example.com"
+"##).unwrap();
         let rss = RssChannel {
             ch_title,
             ch_link,
             ch_desc,
+            authors: &authors,
         };
         let xml = rss.render(&contents).unwrap();
         assert_eq!(str::from_utf8(&xml).unwrap(), EXPECTED);
     }
 
-    const EXPECTED: &str = r##"<?xml version="1.0" encoding="UTF-8"?><rss version="2.0"><channel><title>my feed</title><link>https://thiagocafe.com</link><description>My blog feed</description><item><title>title-of-post-1</title><link>https://thiagocafe.com/view/post-1/</link><guid isPermaLink="false">1</guid><description><![CDATA[summary-of-post-1]]></description><pubDate>Tue, 2 Jan 2024 05:06:07 +0000</pubDate></item><item><title>title-of-post-2</title><link>https://thiagocafe.com/view/post-2/</link><guid isPermaLink="false">2</guid><description><![CDATA[summary-of-post-2]]></description><pubDate>Tue, 2 Jan 2024 05:06:07 +0000</pubDate></item></channel></rss>"##;
+    #[test]
+    fn render_one_author_per_item() {
+        let content = create_cont("1", "2024-01-02", ContOptions { authors: &["Joe", "Thiago", "Ana"], ..Default::default() });
+
+        let authors = AuthorRegistry::from_toml(r##"
+[Thiago]
+name = "Thiago Guedes"
+email = "thiago@example.com"
+
+[Ana]
+name = "Ana"
+email = "ana@example.com"
+"##).unwrap();
+        let rss = RssChannel {
+            ch_title: "my feed",
+            ch_link: "https://thiagocafe.com",
+            ch_desc: "My blog feed",
+            authors: &authors,
+        };
+        let xml = rss.render(&[content]).unwrap();
+        let xml = str::from_utf8(&xml).unwrap();
+        assert_eq!(xml.matches("<author>").count(), 1);
+        assert!(xml.contains("<author>thiago@example.com (Thiago Guedes)</author>"));
+        assert!(xml.contains("<dc:creator>Joe</dc:creator><dc:creator>Thiago Guedes</dc:creator><dc:creator>Ana</dc:creator>"));
+    }
+
+    const EXPECTED: &str = r##"<?xml version="1.0" encoding="UTF-8"?><rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/"><channel><title>my feed</title><link>https://thiagocafe.com</link><description>My blog feed</description><item><title>title-of-post-1</title><link>https://thiagocafe.com/view/post-1/</link><guid isPermaLink="false">1</guid><description><![CDATA[summary-of-post-1]]></description><author>thiago@example.com (Thiago Guedes)</author><dc:creator>Thiago Guedes</dc:creator><pubDate>Tue, 2 Jan 2024 05:06:07 +0000</pubDate></item><item><title>title-of-post-2</title><link>https://thiagocafe.com/view/post-2/</link><guid isPermaLink="false">2</guid><description><![CDATA[summary-of-post-2]]></description><author>thiago@example.com (Thiago Guedes)</author><dc:creator>Thiago Guedes</dc:creator><pubDate>Tue, 2 Jan 2024 05:06:07 +0000</pubDate></item></channel></rss>"##;
 }
\ No newline at end of file
@@ -0,0 +1,47 @@
+use ramhorns::Template;
+
+use crate::authors::AuthorRegistry;
+use crate::series::Series;
+use crate::view::list_renderer::PostItem;
+use crate::view::site_context::{SiteContext, WithSite};
+
+#[derive(ramhorns::Content)]
+struct SeriesPage<'a> {
+    name: &'a str,
+    count: usize,
+    parts: Vec<SeriesPostItem>,
+}
+
+#[derive(ramhorns::Content)]
+struct SeriesPostItem {
+    number: usize,
+    post: PostItem,
+}
+
+pub struct SeriesRenderer<'a> {
+    pub template: &'a Template<'a>,
+}
+
+impl<'a> SeriesRenderer<'a> {
+    pub fn new(template: &'a Template<'a>) -> SeriesRenderer<'a> {
+        SeriesRenderer {
+            template,
+        }
+    }
+
+    pub fn render(&self, series: &Series, authors: &AuthorRegistry, site: &SiteContext) -> String {
+        let parts = series.contents.iter().enumerate().map(|(i, content)| {
+            SeriesPostItem {
+                number: i + 1,
+                post: PostItem::from(content, authors, site),
+            }
+        }).collect();
+
+        let page = SeriesPage {
+            name: series.name.as_str(),
+            count: series.contents.len(),
+            parts,
+        };
+        self.template.render(&WithSite::new(&page, site))
+    }
+}
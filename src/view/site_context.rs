@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{Datelike, Utc};
+use ramhorns::encoding::Encoder;
+use ramhorns::traits::ContentSequence;
+use ramhorns::{Content, Section};
+
+use crate::content::Content as PostContent;
+use crate::metrics::view_counter::ViewCounts;
+use crate::text_utils::format_date_time;
+
+/// Values available in every template as `{{site.*}}`. Values of the `[site]` configuration
+/// section, e.g. `{{site.title}}`, plus values computed from the posts:
+///
+/// - `post_count`: Number of posts
+/// - `tags`: Tags with the number of posts of each one, most used first. `{{#site.tags}}{{name}}({{count}}){{/site.tags}}`
+/// - `recent_posts`: Most recent posts, newest first. `{{#site.recent_posts}}{{title}}{{/site.recent_posts}}`
+/// - `popular_posts`: Most viewed posts of all time, with their `view_count`
+/// - `popular_posts_week`: Most viewed posts of the last 7 days, `view_count` being the views of the week
+/// - `version`: Texted version
+/// - `year`: Current year
+#[derive(Default)]
+pub struct SiteContext {
+    values: toml::Table,
+    post_count: usize,
+    tags: Vec<SiteTag>,
+    recent_posts: Vec<SitePost>,
+    popular_posts: Vec<SitePost>,
+    popular_posts_week: Vec<SitePost>,
+    /// All-time views by post name, for the `view_count` of the posts
+    views: HashMap<String, u64>,
+    version: &'static str,
+    year: i32,
+}
+
+#[derive(ramhorns::Content)]
+struct SiteTag {
+    name: String,
+    count: usize,
+    link: String,
+}
+
+#[derive(ramhorns::Content)]
+struct SitePost {
+    title: String,
+    link: String,
+    date: String,
+    view_count: u64,
+}
+
+impl SitePost {
+    fn from(content: &PostContent, view_count: u64) -> Self {
+        let (date, _time) = format_date_time(&content.header.date);
+        SitePost {
+            title: content.title.clone(),
+            link: format!("/view/{}", &content.link),
+            date,
+            view_count,
+        }
+    }
+}
+
+/// Page rendered with the site context. Names starting with `site.` are taken from the site
+/// context and all the others from the page
+pub struct WithSite<'a, T: Content> {
+    pub page: &'a T,
+    pub site: &'a SiteContext,
+}
+
+/// Value of the `[site]` configuration section. Tables and arrays can be used as sections
+struct SiteValue<'a>(&'a toml::Value);
+
+const SITE_PREFIX: &str = "site";
+
+impl SiteContext {
+    pub fn new(values: &toml::Table, contents: &[Arc<PostContent>], recent_count: usize, views: &ViewCounts, popular_count: usize) -> Self {
+        let mut tag_map: HashMap<&str, usize> = HashMap::new();
+        for content in contents {
+            for tag in content.header.tags.iter() {
+                *tag_map.entry(tag.as_str()).or_insert(0) += 1;
+            }
+        }
+
+        // Most used tags first, then by name
+        let mut tags: Vec<SiteTag> = tag_map.into_iter().map(|(name, count)| SiteTag {
+            name: name.to_string(),
+            count,
+            link: format!("/list/{}/", name),
+        }).collect();
+        tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+
+        let mut by_date: Vec<&Arc<PostContent>> = contents.iter().collect();
+        by_date.sort_by_key(|c| std::cmp::Reverse(c.header.date));
+        let recent_posts = by_date.into_iter().take(recent_count)
+            .map(|c| SitePost::from(c, views.views(&c.link)))
+            .collect();
+
+        SiteContext {
+            values: values.clone(),
+            post_count: contents.len(),
+            tags,
+            recent_posts,
+            popular_posts: popular_posts(contents, &views.all_time, popular_count),
+            popular_posts_week: popular_posts(contents, &views.last_week, popular_count),
+            views: contents.iter().map(|c| (c.link.clone(), views.views(&c.link))).collect(),
+            version: env!("CARGO_PKG_VERSION"),
+            year: Utc::now().year(),
+        }
+    }
+
+    /// All-time views of the post
+    pub fn view_count(&self, post_name: &str) -> u64 {
+        self.views.get(post_name).copied().unwrap_or(0)
+    }
+}
+
+/// Posts with the most views, ignoring the posts without views. Ties are sorted by title
+fn popular_posts(contents: &[Arc<PostContent>], views: &HashMap<String, u64>, count: usize) -> Vec<SitePost> {
+    let mut viewed: Vec<(&Arc<PostContent>, u64)> = contents.iter()
+        .filter_map(|c| views.get(&c.link).map(|count| (c, *count)))
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    viewed.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.title.cmp(&b.title)));
+    viewed.into_iter().take(count).map(|(c, count)| SitePost::from(c, count)).collect()
+}
+
+impl Content for SiteContext {
+    fn render_section<C, E>(&self, section: Section<C>, encoder: &mut E) -> Result<(), E::Error>
+    where
+        C: ContentSequence,
+        E: Encoder,
+    {
+        section.with(self).render(encoder)
+    }
+
+    fn render_field_escaped<E: Encoder>(&self, _hash: u64, name: &str, encoder: &mut E) -> Result<bool, E::Error> {
+        match name {
+            "post_count" => self.post_count.render_escaped(encoder).map(|_| true),
+            "version" => self.version.render_escaped(encoder).map(|_| true),
+            "year" => self.year.render_escaped(encoder).map(|_| true),
+            _ => SiteValue::field(&self.values, name)
+                .map_or(Ok(false), |v| v.render_escaped(encoder).map(|_| true)),
+        }
+    }
+
+    fn render_field_unescaped<E: Encoder>(&self, hash: u64, name: &str, encoder: &mut E) -> Result<bool, E::Error> {
+        match name {
+            "post_count" | "version" | "year" => self.render_field_escaped(hash, name, encoder),
+            _ => SiteValue::field(&self.values, name)
+                .map_or(Ok(false), |v| v.render_unescaped(encoder).map(|_| true)),
+        }
+    }
+
+    fn render_field_section<C, E>(&self, _hash: u64, name: &str, section: Section<C>, encoder: &mut E) -> Result<bool, E::Error>
+    where
+        C: ContentSequence,
+        E: Encoder,
+    {
+        match name {
+            "tags" => self.tags.render_section(section, encoder).map(|_| true),
+            "recent_posts" => self.recent_posts.render_section(section, encoder).map(|_| true),
+            "popular_posts" => self.popular_posts.render_section(section, encoder).map(|_| true),
+            "popular_posts_week" => self.popular_posts_week.render_section(section, encoder).map(|_| true),
+            "post_count" => self.post_count.render_section(section, encoder).map(|_| true),
+            _ => SiteValue::field(&self.values, name)
+                .map_or(Ok(false), |v| v.render_section(section, encoder).map(|_| true)),
+        }
+    }
+
+    fn render_field_inverse<C, E>(&self, _hash: u64, name: &str, section: Section<C>, encoder: &mut E) -> Result<bool, E::Error>
+    where
+        C: ContentSequence,
+        E: Encoder,
+    {
+        match name {
+            "tags" => self.tags.render_inverse(section, encoder).map(|_| true),
+            "recent_posts" => self.recent_posts.render_inverse(section, encoder).map(|_| true),
+            "popular_posts" => self.popular_posts.render_inverse(section, encoder).map(|_| true),
+            "popular_posts_week" => self.popular_posts_week.render_inverse(section, encoder).map(|_| true),
+            "post_count" => self.post_count.render_inverse(section, encoder).map(|_| true),
+            _ => match SiteValue::field(&self.values, name) {
+                Some(v) => v.render_inverse(section, encoder).map(|_| true),
+                // Missing values are falsy
+                None => section.render(encoder).map(|_| true),
+            },
+        }
+    }
+}
+
+impl<'a> SiteValue<'a> {
+    /// Finds the value by name. Dots access nested tables, e.g. `social.github`
+    fn field(table: &'a toml::Table, name: &str) -> Option<SiteValue<'a>> {
+        match name.split_once('.') {
+            None => table.get(name).map(SiteValue),
+            Some((first, rest)) => match table.get(first) {
+                Some(toml::Value::Table(inner)) => SiteValue::field(inner, rest),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl Content for SiteValue<'_> {
+    fn is_truthy(&self) -> bool {
+        match self.0 {
+            toml::Value::String(s) => !s.is_empty(),
+            toml::Value::Boolean(b) => *b,
+            toml::Value::Array(a) => !a.is_empty(),
+            toml::Value::Table(t) => !t.is_empty(),
+            _ => true,
+        }
+    }
+
+    fn render_escaped<E: Encoder>(&self, encoder: &mut E) -> Result<(), E::Error> {
+        match self.0 {
+            toml::Value::String(s) => encoder.write_escaped(s),
+            toml::Value::Array(_) | toml::Value::Table(_) => Ok(()),
+            v => encoder.format_escaped(v),
+        }
+    }
+
+    fn render_unescaped<E: Encoder>(&self, encoder: &mut E) -> Result<(), E::Error> {
+        match self.0 {
+            toml::Value::String(s) => encoder.write_unescaped(s),
+            toml::Value::Array(_) | toml::Value::Table(_) => Ok(()),
+            v => encoder.format_unescaped(v),
+        }
+    }
+
+    fn render_section<C, E>(&self, section: Section<C>, encoder: &mut E) -> Result<(), E::Error>
+    where
+        C: ContentSequence,
+        E: Encoder,
+    {
+        match self.0 {
+            toml::Value::Array(items) => {
+                for item in items {
+                    SiteValue(item).render_section(section, encoder)?;
+                }
+                Ok(())
+            }
+            _ if self.is_truthy() => section.with(self).render(encoder),
+            _ => Ok(()),
+        }
+    }
+
+    fn render_field_escaped<E: Encoder>(&self, _hash: u64, name: &str, encoder: &mut E) -> Result<bool, E::Error> {
+        match self.0 {
+            toml::Value::Table(table) => SiteValue::field(table, name)
+                .map_or(Ok(false), |v| v.render_escaped(encoder).map(|_| true)),
+            _ => Ok(false),
+        }
+    }
+
+    fn render_field_unescaped<E: Encoder>(&self, _hash: u64, name: &str, encoder: &mut E) -> Result<bool, E::Error> {
+        match self.0 {
+            toml::Value::Table(table) => SiteValue::field(table, name)
+                .map_or(Ok(false), |v| v.render_unescaped(encoder).map(|_| true)),
+            _ => Ok(false),
+        }
+    }
+
+    fn render_field_section<C, E>(&self, _hash: u64, name: &str, section: Section<C>, encoder: &mut E) -> Result<bool, E::Error>
+    where
+        C: ContentSequence,
+        E: Encoder,
+    {
+        match self.0 {
+            toml::Value::Table(table) => SiteValue::field(table, name)
+                .map_or(Ok(false), |v| v.render_section(section, encoder).map(|_| true)),
+            _ => Ok(false),
+        }
+    }
+
+    fn render_field_inverse<C, E>(&self, _hash: u64, name: &str, section: Section<C>, encoder: &mut E) -> Result<bool, E::Error>
+    where
+        C: ContentSequence,
+        E: Encoder,
+    {
+        match self.0 {
+            toml::Value::Table(table) => SiteValue::field(table, name)
+                .map_or(Ok(false), |v| v.render_inverse(section, encoder).map(|_| true)),
+            _ => Ok(false),
+        }
+    }
+}
+
+impl<'a, T: Content> WithSite<'a, T> {
+    pub fn new(page: &'a T, site: &'a SiteContext) -> Self {
+        WithSite { page, site }
+    }
+
+    /// Name inside the site context, e.g. `title` for `site.title`
+    fn site_name(name: &str) -> Option<&str> {
+        name.strip_prefix(SITE_PREFIX).and_then(|rest| rest.strip_prefix('.'))
+    }
+}
+
+impl<T: Content> Content for WithSite<'_, T> {
+    fn render_field_escaped<E: Encoder>(&self, hash: u64, name: &str, encoder: &mut E) -> Result<bool, E::Error> {
+        match Self::site_name(name) {
+            Some(name) => self.site.render_field_escaped(hash, name, encoder),
+            None => self.page.render_field_escaped(hash, name, encoder),
+        }
+    }
+
+    fn render_field_unescaped<E: Encoder>(&self, hash: u64, name: &str, encoder: &mut E) -> Result<bool, E::Error> {
+        match Self::site_name(name) {
+            Some(name) => self.site.render_field_unescaped(hash, name, encoder),
+            None => self.page.render_field_unescaped(hash, name, encoder),
+        }
+    }
+
+    fn render_field_section<C, E>(&self, hash: u64, name: &str, section: Section<C>, encoder: &mut E) -> Result<bool, E::Error>
+    where
+        C: ContentSequence,
+        E: Encoder,
+    {
+        if name == SITE_PREFIX {
+            return self.site.render_section(section, encoder).map(|_| true);
+        }
+        match Self::site_name(name) {
+            Some(name) => self.site.render_field_section(hash, name, section, encoder),
+            None => self.page.render_field_section(hash, name, section, encoder),
+        }
+    }
+
+    fn render_field_inverse<C, E>(&self, hash: u64, name: &str, section: Section<C>, encoder: &mut E) -> Result<bool, E::Error>
+    where
+        C: ContentSequence,
+        E: Encoder,
+    {
+        match Self::site_name(name) {
+            Some(name) => self.site.render_field_inverse(hash, name, section, encoder),
+            None => self.page.render_field_inverse(hash, name, section, encoder),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ramhorns::Template;
+
+    use super::*;
+    use crate::test_data::{create_cont, ContOptions};
+
+    #[derive(ramhorns::Content)]
+    struct Page {
+        title: String,
+        items: Vec<String>,
+    }
+
+    fn render(template_src: &str, site: &SiteContext) -> String {
+        let template = Template::new(template_src).unwrap();
+        let page = Page { title: "page".to_string(), items: vec!["a".to_string(), "b".to_string()] };
+        template.render(&WithSite::new(&page, site))
+    }
+
+    #[test]
+    fn test_site_values() {
+        let values: toml::Table = toml::from_str(r##"
+title = "My <blog>"
+show_bio = false
+nav = [{ name = "Home", url = "/" }, { name = "About", url = "/page/bio/" }]
+social = { github = "thiagomg" }
+"##).unwrap();
+        let site = SiteContext::new(&values, &[], 5, &ViewCounts::default(), 5);
+
+        assert_eq!(render("{{title}}|{{site.title}}|{{{site.title}}}", &site), "page|My &lt;blog&gt;|My <blog>");
+        assert_eq!(render("{{#site.nav}}[{{name}}={{url}}]{{/site.nav}}", &site), "[Home=/][About=/page/bio/]");
+        assert_eq!(render("{{site.social.github}}{{site.missing}}", &site), "thiagomg");
+        assert_eq!(render("{{#site.show_bio}}bio{{/site.show_bio}}{{^site.show_bio}}no bio{{/site.show_bio}}", &site), "no bio");
+        assert_eq!(render("{{#items}}({{site.social.github}}){{/items}}", &site), "(thiagomg)(thiagomg)");
+        assert_eq!(render("{{#site}}{{title}}{{/site}}", &site), "My &lt;blog&gt;");
+    }
+
+    #[test]
+    fn test_computed_values() {
+        let contents = vec![
+            create_cont("1", "2024-01-01", ContOptions { tags: &["rust", "web"], ..Default::default() }),
+            create_cont("2", "2024-01-02", ContOptions { tags: &["rust"], ..Default::default() }),
+            create_cont("3", "2024-01-03", ContOptions::default()),
+        ];
+        let views = ViewCounts {
+            all_time: HashMap::from([("post-1".to_string(), 10), ("post-2".to_string(), 3), ("post-3".to_string(), 0)]),
+            last_week: HashMap::from([("post-2".to_string(), 3)]),
+        };
+        let site = SiteContext::new(&toml::Table::new(), &contents, 2, &views, 5);
+
+        assert_eq!(render("{{site.post_count}}", &site), "3");
+        assert_eq!(render("{{#site.tags}}({{name}}:{{count}}:{{link}}){{/site.tags}}", &site), "(rust:2:/list/rust/)(web:1:/list/web/)");
+        assert_eq!(render("{{#site.recent_posts}}({{title}}:{{link}}:{{date}}){{/site.recent_posts}}", &site),
+                   "(title-of-post-3:/view/post-3:2024-01-03)(title-of-post-2:/view/post-2:2024-01-02)");
+        assert_eq!(render("{{#site.popular_posts}}({{title}}:{{view_count}}){{/site.popular_posts}}", &site), "(title-of-post-1:10)(title-of-post-2:3)");
+        assert_eq!(render("{{#site.popular_posts_week}}({{title}}:{{view_count}}){{/site.popular_posts_week}}", &site), "(title-of-post-2:3)");
+        assert_eq!(site.view_count("post-1"), 10);
+        assert_eq!(render("{{site.version}}", &site), env!("CARGO_PKG_VERSION"));
+        assert_eq!(render("{{site.year}}", &site), Utc::now().year().to_string());
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{fs, io};
+
+use lazy_static::lazy_static;
+use ramhorns::{Error, Ramhorns, Template};
+use regex::Regex;
+
+const TEMPLATE_EXTENSION: &str = "tpl";
+const LAYOUT_DIR: &str = "layouts";
+/// Partial used by layouts to include the page being rendered
+const CONTENT_PARTIAL: &str = "content";
+
+lazy_static! {
+    static ref CONTENT_PARTIAL_REGEX: Regex = Regex::new(r"\{\{>\s*content\s*\}\}").unwrap();
+}
+
+/// All templates of template_dir, parsed once. Templates are named after their path,
+/// without extension. E.g. `view.tpl` is `view` and `partials/header.tpl` is `partials/header`
+///
+/// Templates can include partials with `{{> header}}` and use a layout from `layouts/` declaring
+/// it in a comment, e.g. `{{! layout: base }}`. The layout includes the page with `{{> content}}`
+///
+/// When loaded from several directories, e.g. a local template_dir and a theme, a template of the
+/// first directory overrides the one with the same name in the following ones
+pub struct TemplateRegistry {
+    template_dirs: Vec<PathBuf>,
+    /// File of each template name
+    files: HashMap<String, PathBuf>,
+    templates: Ramhorns,
+    version: TemplateVersion,
+}
+
+/// Number of files and last modification of template_dir, used to detect changes
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TemplateVersion {
+    file_count: usize,
+    modified: Option<SystemTime>,
+}
+
+impl TemplateRegistry {
+    /// Loads and parses the templates of all template_dirs, the first ones taking precedence.
+    /// Fails with the file and line of the first invalid template
+    pub fn from_dirs(template_dirs: &[PathBuf]) -> io::Result<Self> {
+        let Some(first_dir) = template_dirs.first() else {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "No template directory"));
+        };
+        let version = TemplateVersion::from_dirs(template_dirs)?;
+        let mut sources = HashMap::new();
+        let mut files = HashMap::new();
+        for template_dir in template_dirs {
+            read_sources(template_dir, template_dir, &mut sources, &mut files)?;
+        }
+
+        let templates = match Ramhorns::lazy(first_dir) {
+            Ok(x) => x,
+            Err(e) => return Err(io::Error::new(ErrorKind::InvalidInput, format!("Error loading templates from {}: {}", first_dir.to_str().unwrap(), e))),
+        };
+        let mut registry = TemplateRegistry {
+            template_dirs: template_dirs.to_vec(),
+            files,
+            templates,
+            version,
+        };
+
+        // Layouts are only parsed as part of the pages using them
+        let mut names: Vec<String> = sources.keys()
+            .filter(|name| !name.starts_with(&format!("{}/", LAYOUT_DIR)))
+            .cloned()
+            .collect();
+        names.sort();
+        for name in names.iter() {
+            registry.load(name, &sources, &mut vec![])?;
+        }
+
+        Ok(registry)
+    }
+
+    pub fn get(&self, name: &str) -> io::Result<&Template<'static>> {
+        match self.templates.get(name) {
+            Some(template) => Ok(template),
+            None => Err(io::Error::new(ErrorKind::NotFound, format!("Template {} not found", self.file_name(name)))),
+        }
+    }
+
+    /// Checks that all the templates exist
+    pub fn require(&self, names: &[&str]) -> io::Result<()> {
+        for name in names {
+            self.get(name)?;
+        }
+        Ok(())
+    }
+
+    /// True when any file of the template directories changed after the templates were loaded
+    pub fn is_outdated(&self) -> bool {
+        match TemplateVersion::from_dirs(&self.template_dirs) {
+            Ok(version) => version != self.version,
+            Err(_) => true,
+        }
+    }
+
+    fn file_name(&self, name: &str) -> String {
+        let path = match self.files.get(name) {
+            Some(path) => path.clone(),
+            None => self.template_dirs[0].join(format!("{}.{}", name, TEMPLATE_EXTENSION)),
+        };
+        path.to_str().unwrap().to_string()
+    }
+
+    /// Parses the template name, parsing the partials it depends on first
+    fn load(&mut self, name: &str, sources: &HashMap<String, String>, loading: &mut Vec<String>) -> io::Result<()> {
+        if self.templates.get(name).is_some() {
+            return Ok(());
+        }
+        if loading.iter().any(|n| n == name) {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Recursive partial {} in templates {}", name, loading.join(" -> "))));
+        }
+        let Some(src) = sources.get(name) else {
+            let used_by = match loading.last() {
+                Some(parent) => format!(" used by {}", self.file_name(parent)),
+                None => "".to_string(),
+            };
+            return Err(io::Error::new(ErrorKind::NotFound, format!("Template {}{} not found", self.file_name(name), used_by)));
+        };
+
+        loading.push(name.to_string());
+        for partial in partial_names(src) {
+            if partial != CONTENT_PARTIAL {
+                self.load(&partial, sources, loading)?;
+            }
+        }
+
+        match layout_name(src) {
+            None => {
+                let res = self.templates.insert(src.clone(), name.to_string());
+                self.check(res, name, src)?;
+            }
+            Some(layout) => {
+                let layout_name = format!("{}/{}", LAYOUT_DIR, layout);
+                let Some(layout_src) = sources.get(&layout_name) else {
+                    return Err(io::Error::new(ErrorKind::NotFound, format!("Layout {} used by {} not found", self.file_name(&layout_name), self.file_name(name))));
+                };
+                for partial in partial_names(layout_src) {
+                    if partial != CONTENT_PARTIAL {
+                        self.load(&partial, sources, loading)?;
+                    }
+                }
+
+                // Partials are resolved when parsing, so the page is the content partial of its layout. Parsed
+                // templates borrow the source of their partials, so each page keeps its own partial
+                let content_name = format!("{}:{}", CONTENT_PARTIAL, name);
+                let res = self.templates.insert(src.clone(), content_name.clone());
+                self.check(res, name, src)?;
+                let page_layout_src = CONTENT_PARTIAL_REGEX.replace_all(layout_src, format!("{{{{> {}}}}}", content_name));
+                let res = self.templates.insert(page_layout_src.to_string(), name.to_string());
+                self.check(res, &layout_name, layout_src)?;
+            }
+        };
+        loading.pop();
+
+        Ok(())
+    }
+
+    fn check(&self, res: Result<(), Error>, name: &str, src: &str) -> io::Result<()> {
+        let Err(e) = res else {
+            return Ok(());
+        };
+
+        let location = match error_line(src, &e) {
+            Some(line) => format!("{}:{}", self.file_name(name), line),
+            None => self.file_name(name),
+        };
+        Err(io::Error::new(ErrorKind::InvalidInput, format!("Error parsing template {}: {}", location, e)))
+    }
+}
+
+impl TemplateVersion {
+    fn from_dirs(dirs: &[PathBuf]) -> io::Result<Self> {
+        let mut version = TemplateVersion::default();
+        for dir in dirs {
+            let dir_version = TemplateVersion::from_dir(dir)?;
+            version.file_count += dir_version.file_count;
+            version.modified = version.modified.max(dir_version.modified);
+        }
+        Ok(version)
+    }
+
+    fn from_dir(dir: &Path) -> io::Result<Self> {
+        let mut version = TemplateVersion {
+            file_count: 0,
+            modified: Some(fs::metadata(dir)?.modified()?),
+        };
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let file_version = match path.is_dir() {
+                true => TemplateVersion::from_dir(&path)?,
+                false => TemplateVersion {
+                    file_count: 1,
+                    modified: Some(fs::metadata(&path)?.modified()?),
+                },
+            };
+            version.file_count += file_version.file_count;
+            version.modified = version.modified.max(file_version.modified);
+        }
+
+        Ok(version)
+    }
+}
+
+/// Reads the templates of dir not already read from a previous directory
+fn read_sources(root_dir: &Path, dir: &Path, sources: &mut HashMap<String, String>, files: &mut HashMap<String, PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            read_sources(root_dir, &path, sources, files)?;
+        } else if path.extension().is_some_and(|e| e == TEMPLATE_EXTENSION) {
+            let name = path.strip_prefix(root_dir).unwrap().with_extension("");
+            let name = name.to_str().unwrap().replace('\\', "/");
+            if !sources.contains_key(&name) {
+                sources.insert(name.clone(), fs::read_to_string(&path)?);
+                files.insert(name, path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Names of the partials used in the template, e.g. `{{> header}}`
+fn partial_names(src: &str) -> Vec<String> {
+    src.split("{{>").skip(1)
+        .filter_map(|s| s.split_once("}}"))
+        .map(|(name, _)| name.trim().to_string())
+        .collect()
+}
+
+/// Line of the template where the parsing error happened, if it can be found
+fn error_line(src: &str, error: &Error) -> Option<usize> {
+    let pos = match error {
+        // The last section opened with this name is the one not closed
+        Error::UnclosedSection(name) => {
+            let regex = Regex::new(&format!(r"\{{\{{\s*[#^]\s*{}\s*\}}\}}", regex::escape(name))).unwrap();
+            regex.find_iter(src).last().map(|m| m.start())
+        }
+        Error::UnopenedSection(name) => {
+            let regex = Regex::new(&format!(r"\{{\{{\s*/\s*{}\s*\}}\}}", regex::escape(name))).unwrap();
+            regex.find(src).map(|m| m.start())
+        }
+        Error::NotFound(name) | Error::IllegalPartial(name) => {
+            let regex = Regex::new(&format!(r"\{{\{{\s*>\s*{}\s*\}}\}}", regex::escape(name))).unwrap();
+            regex.find(src).map(|m| m.start())
+        }
+        // A tag without closing braces before the next tag
+        Error::UnclosedTag => {
+            src.match_indices("{{").map(|(pos, _)| pos).find(|pos| {
+                let rest = &src[pos + 2..];
+                let next_tag = rest.find("{{").unwrap_or(rest.len());
+                !rest[..next_tag].contains("}}")
+            })
+        }
+        _ => None,
+    }?;
+
+    Some(src[..pos].matches('\n').count() + 1)
+}
+
+/// Layout declared in the template, e.g. `{{! layout: base }}`
+fn layout_name(src: &str) -> Option<String> {
+    src.split("{{!").skip(1)
+        .filter_map(|s| s.split_once("}}"))
+        .filter_map(|(comment, _)| comment.trim().strip_prefix("layout:"))
+        .map(|name| name.trim().to_string())
+        .next()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::test_data::temp_dir;
+
+    #[derive(ramhorns::Content)]
+    struct Page {
+        title: String,
+    }
+
+    fn create_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = temp_dir(&format!("templates-{}", name));
+        for (file_name, src) in files {
+            let path = dir.join(file_name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, src).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_partials_and_layout() {
+        let dir = create_dir("layout", &[
+            ("view.tpl", "{{! layout: base }}<p>{{title}}</p>"),
+            ("page.tpl", "{{! layout: base }}<div>{{title}}</div>"),
+            ("list.tpl", "{{> partials/header}}<ul></ul>"),
+            ("partials/header.tpl", "<h1>{{> logo}} {{title}}</h1>"),
+            ("logo.tpl", "[logo]"),
+            ("layouts/base.tpl", "<html>{{> partials/header}}{{> content}}</html>"),
+        ]);
+        let registry = TemplateRegistry::from_dirs(std::slice::from_ref(&dir)).unwrap();
+        let page = Page { title: "Title".to_string() };
+
+        assert_eq!(registry.get("view").unwrap().render(&page), "<html><h1>[logo] Title</h1><p>Title</p></html>");
+        assert_eq!(registry.get("page").unwrap().render(&page), "<html><h1>[logo] Title</h1><div>Title</div></html>");
+        assert_eq!(registry.get("list").unwrap().render(&page), "<h1>[logo] Title</h1><ul></ul>");
+        assert!(registry.get("layouts/base").is_err());
+        assert!(registry.get("not_found").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_layout_pages_keep_their_content() {
+        let dir = create_dir("layout-pages", &[
+            ("view.tpl", "{{! layout: base }}<p>{{title}}</p>"),
+            ("page.tpl", "{{! layout: base }}<div>{{title}}</div>"),
+            ("layouts/base.tpl", "<html>{{> content}}</html>"),
+        ]);
+        let registry = TemplateRegistry::from_dirs(std::slice::from_ref(&dir)).unwrap();
+        let page = Page { title: "Title".to_string() };
+
+        let view = registry.get("view").unwrap().render(&page);
+        let other_page = registry.get("page").unwrap().render(&page);
+        assert_eq!(view, "<html><p>Title</p></html>");
+        assert_eq!(other_page, "<html><div>Title</div></html>");
+        assert_ne!(view, other_page);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_error_line() {
+        let dir = create_dir("error", &[
+            ("view.tpl", "<html>\n{{#tags}}\n<p>{{tag}}</p>\n{{/tag}}\n</html>"),
+        ]);
+        let err = TemplateRegistry::from_dirs(std::slice::from_ref(&dir)).err().unwrap();
+        assert!(err.to_string().contains("view.tpl:2:"), "{}", err);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(error_line("a\n{{/tags}}", &Error::UnopenedSection("tags".into())), Some(2));
+        assert_eq!(error_line("a\nb\n{{title\n{{other}}", &Error::UnclosedTag), Some(3));
+        assert_eq!(error_line("{{> header }}", &Error::NotFound("header".into())), Some(1));
+        assert_eq!(error_line("", &Error::StackOverflow), None);
+    }
+
+    #[test]
+    fn test_outdated() {
+        let dir = create_dir("outdated", &[
+            ("view.tpl", "<p>{{title}}</p>"),
+        ]);
+        let registry = TemplateRegistry::from_dirs(std::slice::from_ref(&dir)).unwrap();
+        assert!(!registry.is_outdated());
+        assert!(registry.require(&["view"]).is_ok());
+        assert!(registry.require(&["view", "postlist"]).is_err());
+
+        fs::write(dir.join("postlist.tpl"), "<ul></ul>").unwrap();
+        assert!(registry.is_outdated());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_override() {
+        let theme_dir = create_dir("theme", &[
+            ("view.tpl", "{{! layout: base }}<p>{{title}}</p>"),
+            ("header.tpl", "<h1>Theme</h1>"),
+            ("layouts/base.tpl", "<html>{{> header}}{{> content}}</html>"),
+        ]);
+        let local_dir = create_dir("local", &[
+            ("header.tpl", "<h1>Local</h1>"),
+        ]);
+        let registry = TemplateRegistry::from_dirs(&[local_dir.clone(), theme_dir.clone()]).unwrap();
+        let page = Page { title: "Title".to_string() };
+        assert_eq!(registry.get("view").unwrap().render(&page), "<html><h1>Local</h1><p>Title</p></html>");
+        assert!(!registry.is_outdated());
+
+        fs::write(theme_dir.join("page.tpl"), "<p></p>").unwrap();
+        assert!(registry.is_outdated());
+        fs::remove_dir_all(&theme_dir).unwrap();
+        fs::remove_dir_all(&local_dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_partial() {
+        let dir = create_dir("missing", &[
+            ("view.tpl", "{{> header}}"),
+        ]);
+        let res = TemplateRegistry::from_dirs(std::slice::from_ref(&dir));
+        assert!(res.is_err());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let dir = create_dir("recursive", &[
+            ("a.tpl", "{{> b}}"),
+            ("b.tpl", "{{> a}}"),
+        ]);
+        let res = TemplateRegistry::from_dirs(std::slice::from_ref(&dir));
+        assert!(res.is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}